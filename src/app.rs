@@ -3,14 +3,63 @@ use iced::{
     executor, window as iced_window,
 };
 use iced::theme;
-use iced::widget::{column, container, scrollable};
-use log::{debug, error, info};
-use futures::StreamExt;
+use iced::widget::{column, container, scrollable, Column};
+use log::{debug, error, info, warn};
+use futures::future::{AbortHandle, Aborted};
+use futures::stream::{BoxStream, StreamExt};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use crate::config::{ApiStyle, ThemeMode, WindowLevel};
 use crate::ui::window as ui_window;
-use crate::ollama::api::OllamaClient;
+use crate::ollama::api::{OllamaClient, StreamedResponse};
+use crate::ollama::breaker::CircuitBreaker;
+use crate::ollama::error::ResponseError;
+use crate::ollama::limit::RequestLimiter;
+use crate::ollama::replay::Replay;
+use crate::data::conversation::{Conversation, Message as ConversationMessage, ResponseMetadata};
+use crate::ollama::models::{ChatCompletionRequest, GenerateRequest, PullProgress, RunningModel};
 use crate::data::conversation::MessageRole;
+use crate::data::autosave::DirtyFlag;
+use crate::data::export::ExportFormat;
+use crate::data::issues::{FailedRequestLog, MAX_FAILED_REQUESTS};
+use crate::data::storage::Storage;
+use crate::data::search::{with_current_conversation, SearchHit};
+use crate::data::trace::ApiTracer;
+use crate::data::transcript::TranscriptWriter;
+use crate::ui::images::ImageCache;
+use crate::ui::layout::{estimate_message_height, message_offset, smooth_scroll_step, SMOOTH_SCROLL_FRAME};
+use crate::ui::log_viewer::{LogViewer, LOG_REFRESH_INTERVAL};
+use crate::ui::presentation::PresentationOptions;
+use crate::ui::settings::{Setting, SettingsForm};
+use crate::ui::theme::{resolve_theme, Palette};
+use crate::utils::speech::Speaker;
+use crate::utils::{extract_and_prettify_json, extract_single_code_block};
+use crate::utils::redact::Redactor;
+use crate::utils::diff::{diff_lines, DiffSegment};
+use crate::utils::text::{strip_markdown, truncate_chars};
+
+/// Number of earlier versions kept for each regenerated response
+const MAX_RESPONSE_HISTORY: usize = 5;
+
+/// Width in pixels of the shadow drawn along the window edge
+const WINDOW_SHADOW_WIDTH: f32 = 2.0;
+
+/// Message sent to pick up a response that stopped at the token limit
+const CONTINUE_PROMPT: &str = "Continue";
+
+/// A timer the app runs while there is something for it to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timer {
+    /// Saves unsaved changes periodically
+    AutoSave,
+    /// Follows the log file while the log viewer is open
+    RefreshLogs,
+    /// Moves a smooth scroll one frame closer to the bottom
+    SmoothScroll,
+}
 
 /// Main application state
 pub struct App {
@@ -30,8 +79,20 @@ pub struct App {
     is_sending: bool,
     /// Whether to scroll to the bottom of the conversation
     scroll_to_bottom: bool,
+    /// Relative vertical scroll offset of the conversation, from 0 at the top to 1 at the bottom
+    scroll_offset: f32,
+    /// Whether a smooth scroll to the bottom is under way
+    smooth_scrolling: bool,
+    /// Whether the window has focus
+    focused: bool,
+    /// Whether the platform reports the window as hidden, such as when it is minimized
+    hidden: bool,
     /// Current error message, if any
     error: Option<String>,
+    /// Warning about the last message sent, if any
+    warning: Option<String>,
+    /// Redacts secrets from outgoing messages
+    redactor: Redactor,
     /// Ollama API client
     ollama_client: Option<OllamaClient>,
     /// Current streaming response content
@@ -47,6 +108,100 @@ pub struct App {
     channel_state: Arc<Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<String>>>>,
     /// Whether streaming is active
     is_streaming: bool,
+    /// Writer for the API trace, if enabled
+    tracer: Option<ApiTracer>,
+    /// The most recent request sent to Ollama and when it was sent
+    last_request: Option<(ChatCompletionRequest, chrono::DateTime<chrono::Utc>)>,
+    /// Plain-text transcript that streamed responses are appended to
+    transcript: Option<TranscriptWriter>,
+    /// Text-to-speech engine, if enabled and available
+    speaker: Option<Speaker>,
+    /// Index of the message currently being read aloud
+    speaking: Option<usize>,
+    /// Light or dark theme resolved from the configured mode
+    theme: Theme,
+    /// Colors resolved from the configured color scheme for the theme
+    palette: Palette,
+    /// Position in the bookmark list of the last bookmark jumped to
+    bookmark_cursor: Option<usize>,
+    /// Text typed into the find bar, or None while it is closed
+    find_query: Option<String>,
+    /// Indices of the messages matching the find query
+    find_matches: Vec<usize>,
+    /// Position in the find matches of the last match jumped to
+    find_cursor: Option<usize>,
+    /// Whether clearing the conversation is waiting to be confirmed
+    confirming_clear: bool,
+    /// ID of the conversation whose deletion is waiting to be confirmed
+    confirming_delete: Option<String>,
+    /// Handle for aborting the connection probe while it is in flight
+    connect_handle: Option<AbortHandle>,
+    /// Handle for stopping the response being streamed
+    stream_handle: Option<AbortHandle>,
+    /// Limits how many requests to Ollama run at once
+    limiter: RequestLimiter,
+    /// Index of the assistant message receiving the current response
+    response_index: Option<usize>,
+    /// Indices of messages whose response details are expanded
+    expanded_details: HashSet<usize>,
+    /// Indices of messages selected for exporting as a snippet
+    selected_messages: HashSet<usize>,
+    /// Earlier versions of regenerated responses by message index, oldest first
+    response_history: HashMap<usize, Vec<String>>,
+    /// Index of the response whose changes from its previous version are shown
+    diff_shown: Option<usize>,
+    /// JSON of the last request built in dry-run mode
+    dry_run_preview: Option<String>,
+    /// Unsaved changes to the current conversation
+    conversation_dirty: DirtyFlag,
+    /// Unsaved changes to the configuration
+    config_dirty: DirtyFlag,
+    /// Saved conversations listed in the sidebar, newest first
+    conversations: Vec<Conversation>,
+    /// Whether the conversation sidebar is shown
+    sidebar_visible: bool,
+    /// Query typed into the sidebar search
+    search_query: String,
+    /// Messages of the saved conversations matching the search query
+    search_hits: Vec<SearchHit>,
+    /// IDs of conversations whose info panel is open
+    info_visible: HashSet<String>,
+    /// Whether the outline of the conversation's headings is shown
+    outline_visible: bool,
+    /// Where conversations and the configuration are saved, if storage is writable
+    storage: Storage,
+    /// Requests to Ollama that failed, listed in the Issues panel
+    failed_requests: FailedRequestLog,
+    /// Whether the Issues panel is shown
+    issues_visible: bool,
+    /// Settings as typed into the settings panel, while it is open
+    settings: Option<SettingsForm>,
+    /// Model entered for replaying the conversation
+    replay_model: String,
+    /// Replay of the conversation against another model, while one is running
+    replay: Option<Replay>,
+    /// Handle for aborting the replay turn in flight
+    replay_handle: Option<AbortHandle>,
+    /// Model being downloaded because it wasn't installed, if any
+    pulling: Option<String>,
+    /// Progress of the model download, kept apart from the loading message of responses
+    pull_status: Option<String>,
+    /// Names of the installed models, listed once connected
+    models: Vec<String>,
+    /// Index of the user message being edited in the input, if any
+    editing: Option<usize>,
+    /// Log viewer panel, while it is open
+    log_viewer: Option<LogViewer>,
+    /// Whether the last input change was cut off at `ui.max_input_chars`
+    input_truncated: bool,
+    /// Index in `config.templates` of the template wrapping the input, if one is selected
+    template: Option<usize>,
+    /// Values entered for the variables of the selected template, by name
+    template_values: BTreeMap<String, String>,
+    /// Images to send with the next message
+    pending_images: Vec<PendingImage>,
+    /// Decoded images of the conversation's messages
+    images: ImageCache,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +212,11 @@ pub enum Message {
     Close,
     InputChanged(String),
     SendMessage,
+    SelectTemplate(Option<usize>),
+    TemplateValueChanged(String, String),
+    AttachImage(PathBuf),
+    ImageRead(PathBuf, Result<Vec<u8>, String>),
+    RemovePendingImage(usize),
     // Window-related messages
     Resize(u32, u32),
     ResizeEnded,
@@ -64,20 +224,121 @@ pub enum Message {
     MouseDown,
     MouseUp,
     MouseMoved(Point),
+    FocusChanged(bool),
+    WindowModeFetched(iced_window::Mode),
     // UI-related messages
     NewLine,
+    JoinLine,
     ScrollToBottom,
+    ScrollToMessage(usize),
+    ConversationScrolled(f32),
+    SmoothScrollTick,
     // API-related messages
     OllamaConnected(OllamaClient),
     OllamaConnectionFailed(String),
+    CancelConnecting,
+    ConnectionProbeAborted,
+    ModelPrewarmed(Result<(), String>),
+    ModelChecked(Result<bool, String>),
+    ModelPullProgress(PullProgress),
+    ModelPullFinished(Result<(), String>),
+    ModelsListed(Result<Vec<String>, String>),
+    ListRunningModels,
+    RunningModelsListed(Result<Vec<RunningModel>, String>),
+    ModelSelected(String),
+    Reconnect,
     MessageChunkReceived(String),
     MessageReceived(String),
-    MessageError(String),
+    GeneratedResponse(String, String, Option<Vec<i64>>),
+    CompleteResponseReceived(ResponseMetadata, String),
+    MessageError(ResponseError),
     SaveConfig,
     // Streaming-related messages
     StartStreaming,
     StreamChunk(String),
     EndStreaming,
+    CancelStreaming,
+    StreamAborted,
+    StreamCompleted(Option<ResponseMetadata>, Vec<String>),
+    ResponseHookFinished(usize, String, Result<String, String>),
+    SaveImage(usize, usize),
+    ToggleResponseDetails(usize),
+    // Text-to-speech messages
+    SpeakMessage(usize),
+    StopSpeaking,
+    SpeechFinished(usize, Result<(), String>),
+    // Bookmark messages
+    ToggleBookmark(usize),
+    NextBookmark,
+    // Finding messages in the conversation
+    ToggleFind,
+    FindChanged(String),
+    NextFindMatch,
+    PreviousFindMatch,
+    // Editing messages
+    MoveMessage(usize, usize),
+    RegenerateMoreCreative,
+    ContinueResponse,
+    ShowDiff(usize),
+    // Conversation info messages
+    ToggleConversationInfo,
+    ToggleOutline,
+    ToggleIssues,
+    RetryFailedRequest(usize),
+    ClearFailedRequests,
+    // Window appearance messages
+    CycleOpacity,
+    ToggleAlwaysOnTop,
+    SetTheme(ThemeMode),
+    CycleThink,
+    SystemPromptChanged(String),
+    // Dry run messages
+    DismissDryRunPreview,
+    CopyAsCurl,
+    // Clipboard messages
+    CopyMessage(usize),
+    CopyToClipboard(String),
+    CopyConversation,
+    // Selection messages
+    ToggleMessageSelection(usize),
+    ClearSelection,
+    CopySelection,
+    SaveSelectionAsSnippet,
+    // Conversation navigation messages
+    ToggleSettings,
+    SettingChanged(Setting, String),
+    SaveSettings,
+    OpenConfigFile,
+    ToggleSidebar,
+    SearchChanged(String),
+    SearchCompleted(String, Result<Vec<SearchHit>, String>),
+    OpenSearchHit(String, usize),
+    SelectConversation(String),
+    NewConversation,
+    RequestClearConversation,
+    ClearConversation,
+    CancelClearConversation,
+    EditMessage(usize),
+    CancelEdit,
+    RequestDeleteConversation(String),
+    DeleteConversation(String),
+    CancelDeleteConversation,
+    QuickSwitch(u8),
+    AssignQuickSwitch(u8),
+    ForkFromMessage(usize),
+    // Persistence messages
+    AutoSave,
+    SaveConversation,
+    // Replay messages
+    ReplayModelChanged(String),
+    ReplayWith(String),
+    ReplayTurnCompleted(Result<StreamedResponse, ResponseError>),
+    CancelReplay,
+    ReplayAborted,
+    // Log viewer messages
+    ToggleLogViewer,
+    RefreshLogs,
+    LogLevelSelected(log::Level),
 }
 
 impl App {
@@ -99,6 +360,522 @@ impl App {
         debug!("Streaming channel has been reset");
     }
 
+    /// Write the conversation and configuration to disk before the app exits
+    fn flush(&mut self) {
+        info!("Flushing state before exit");
+        
+        // Save unconditionally so a response that is still streaming is kept
+        self.conversation_dirty.take();
+        if let Err(e) = self.storage.save_conversation(&self.conversation) {
+            error!("Failed to save conversation: {}", e);
+        }
+        
+        // Save window position and any other pending configuration changes
+        self.config_dirty.take();
+        if let Err(e) = self.window.save_to_config(&mut self.config, &self.storage) {
+            debug!("Failed to save window position: {}", e);
+        }
+    }
+
+    /// Save the conversation after a change, unless auto-save is off
+    ///
+    /// With auto-save off the change is left unsaved until the conversation is
+    /// saved on demand, or written when switching away or quitting.
+    fn auto_save_conversation(&mut self) {
+        if !self.config.conversation.auto_save {
+            debug!("Auto-save is off, not saving conversation {}", self.conversation.id);
+            self.conversation_dirty.mark();
+            return;
+        }
+        self.conversation_dirty.take();
+        if let Err(e) = self.storage.save_conversation(&self.conversation) {
+            error!("Failed to save conversation: {}", e);
+        }
+    }
+
+    /// Reload the saved conversations listed in the sidebar
+    fn reload_conversations(&mut self) {
+        match self.storage.load_conversations() {
+            Ok(conversations) => self.conversations = conversations,
+            Err(e) => error!("Failed to reload conversations: {}", e),
+        }
+    }
+
+    /// Drop the oldest messages beyond `max_chars` characters, moving state kept by message index with the rest
+    ///
+    /// State tied to a dropped message is forgotten.
+    fn truncate_conversation(&mut self, max_chars: usize) {
+        let removed = self.conversation.truncate_to_chars(max_chars);
+        if removed == 0 {
+            return;
+        }
+        let shift = |index: usize| index.checked_sub(removed);
+        self.expanded_details = self.expanded_details.iter().filter_map(|&index| shift(index)).collect();
+        self.selected_messages = self.selected_messages.iter().filter_map(|&index| shift(index)).collect();
+        // Earlier versions belong to the response at their index, so they move with it
+        self.response_history = std::mem::take(&mut self.response_history)
+            .into_iter()
+            .filter_map(|(index, versions)| Some((shift(index)?, versions)))
+            .collect();
+        self.diff_shown = self.diff_shown.and_then(shift);
+        let dropped_matches = self.find_matches.iter().filter(|&&index| index < removed).count();
+        self.find_matches = self.find_matches.iter().filter_map(|&index| shift(index)).collect();
+        self.find_cursor = self.find_cursor.and_then(|cursor| cursor.checked_sub(dropped_matches));
+        self.editing = self.editing.and_then(shift);
+        self.response_index = self.response_index.and_then(shift);
+        self.speaking = self.speaking.and_then(shift);
+        // Dropped messages may have been bookmarked, so cycling starts over
+        self.bookmark_cursor = None;
+    }
+
+    /// Show another conversation, clearing view state tied to the previous one
+    fn switch_to(&mut self, conversation: Conversation) {
+        info!("Switching to conversation {}", conversation.id);
+        if self.conversation_dirty.take() {
+            if let Err(e) = self.storage.save_conversation(&self.conversation) {
+                error!("Failed to save conversation: {}", e);
+            }
+        }
+        self.conversation = conversation;
+        self.editing = None;
+        self.bookmark_cursor = None;
+        // Matches are indices into the previous conversation
+        self.find_matches.clear();
+        self.find_cursor = None;
+        self.response_index = None;
+        self.expanded_details.clear();
+        self.selected_messages.clear();
+        self.response_history.clear();
+        self.diff_shown = None;
+        self.dry_run_preview = None;
+        self.scroll_to_bottom = true;
+    }
+
+    /// Switch to the saved conversation with `id`, returning whether it was found
+    fn select_conversation(&mut self, id: &str) -> bool {
+        // Reload so the list reflects everything saved since it was last read
+        self.reload_conversations();
+        match self.conversations.iter().find(|conversation| conversation.id == id) {
+            Some(conversation) => {
+                let conversation = conversation.clone();
+                self.switch_to(conversation);
+                true
+            }
+            None => {
+                error!("Conversation {} not found", id);
+                false
+            }
+        }
+    }
+
+    /// Scroll to the next or previous message matching the find query, wrapping around at the ends
+    fn step_find_match(&mut self, forward: bool) -> Command<Message> {
+        // Messages may have changed since the query was typed
+        let query = self.find_query.as_deref().unwrap_or_default();
+        self.find_matches = self.conversation.search(query);
+        let count = self.find_matches.len();
+        if count == 0 {
+            self.find_cursor = None;
+            return Command::none();
+        }
+        let cursor = match self.find_cursor {
+            None if forward => 0,
+            None => count - 1,
+            Some(cursor) if forward => (cursor + 1) % count,
+            Some(cursor) => (cursor + count - 1) % count,
+        };
+        self.find_cursor = Some(cursor);
+        self.scroll_to_message(self.find_matches[cursor])
+    }
+
+    /// Scroll the conversation so message `index` is at the top of the viewport
+    fn scroll_to_message(&self, index: usize) -> Command<Message> {
+        // The sidebar takes its width from the conversation when it is open
+        let mut width = self.window.size().width;
+        if self.sidebar_visible {
+            width -= crate::ui::sidebar::SIDEBAR_WIDTH;
+        }
+        let heights: Vec<f32> = self
+            .conversation
+            .messages
+            .iter()
+            .map(|message| {
+                // JSON responses are drawn pretty-printed, one value per line
+                let pretty = match message.role {
+                    MessageRole::Assistant => extract_and_prettify_json(&message.content),
+                    MessageRole::User | MessageRole::System => None,
+                };
+                estimate_message_height(pretty.as_deref().unwrap_or(&message.content), width)
+            })
+            .collect();
+        
+        scrollable::scroll_to(
+            scrollable::Id::new("conversation_messages"),
+            scrollable::AbsoluteOffset {
+                x: 0.0,
+                y: message_offset(&heights, index),
+            },
+        )
+    }
+
+    /// Send the next prompt of the running replay, or save the replay once every prompt is answered
+    fn replay_next_turn(&mut self) -> Command<Message> {
+        let (Some(replay), Some(client)) = (&mut self.replay, &self.ollama_client) else {
+            return Command::none();
+        };
+        let Some(request) = replay.start_turn(&self.config.ollama) else {
+            return self.finish_replay();
+        };
+        
+        info!("Replaying turn {} of {} with {}", replay.turn(), replay.total(), replay.model());
+        self.loading_state = Some(format!(
+            "Replaying with {}: turn {} of {}",
+            replay.model(),
+            replay.turn(),
+            replay.total()
+        ));
+        
+        let client = client.clone();
+        let (turn, handle) = futures::future::abortable(async move {
+            client.stream_chat(&request, None, |_| {}).await
+        });
+        self.replay_handle = Some(handle);
+        
+        Command::perform(turn, |result| match result {
+            Ok(result) => Message::ReplayTurnCompleted(result),
+            Err(Aborted) => Message::ReplayAborted,
+        })
+    }
+
+    /// Save the finished replay as a new conversation and switch to it
+    fn finish_replay(&mut self) -> Command<Message> {
+        self.replay_handle = None;
+        self.is_sending = false;
+        self.loading_state = None;
+        let Some(replay) = self.replay.take() else {
+            return Command::none();
+        };
+        
+        let conversation = replay.into_conversation();
+        if let Err(e) = self.storage.save_conversation(&conversation) {
+            error!("Failed to save replay: {}", e);
+            self.error = Some(format!("Failed to save replay: {}", e));
+            return Command::none();
+        }
+        
+        info!("Replay saved as conversation {}", conversation.id);
+        self.switch_to(conversation);
+        self.reload_conversations();
+        self.sidebar_visible = true;
+        Command::perform(async {}, |_| Message::ScrollToBottom)
+    }
+
+    /// Append a streamed chunk to the transcript, disabling teeing on failure
+    fn tee_chunk(&mut self, chunk: &str) {
+        if let Some(transcript) = &mut self.transcript {
+            if let Err(e) = transcript.write_chunk(chunk) {
+                error!("Failed to tee response, disabling transcript: {}", e);
+                self.transcript = None;
+            }
+        }
+    }
+
+    /// Mark the end of a response in the transcript, disabling teeing on failure
+    fn end_transcript_turn(&mut self) {
+        if let Some(transcript) = &mut self.transcript {
+            if let Err(e) = transcript.end_turn() {
+                error!("Failed to tee response, disabling transcript: {}", e);
+                self.transcript = None;
+            }
+        }
+    }
+
+    /// Build the request for `messages`, sending it with `temperature` instead of the configured one if set
+    fn build_request(&self, messages: &[ConversationMessage], temperature: Option<f32>) -> ChatCompletionRequest {
+        let overrides = crate::ollama::request::RequestOverrides {
+            temperature,
+            ..crate::ollama::request::RequestOverrides::for_conversation(&self.conversation)
+        };
+        crate::ollama::request::build_request(messages, overrides, &self.config.ollama)
+    }
+
+    /// Show the request that would be sent instead of sending it
+    fn show_dry_run(&mut self, request: &ChatCompletionRequest) {
+        info!("Dry run, not sending request");
+        self.dry_run_preview = Some(
+            crate::ollama::request::preview_request(request).unwrap_or_else(|e| e.to_string()),
+        );
+        self.is_sending = false;
+        self.loading_state = None;
+    }
+
+    /// Pass the last response through the configured `hooks.on_response` command in the background
+    fn run_response_hook(&self) -> Command<Message> {
+        let Some(command) = self.config.hooks.on_response.clone() else {
+            return Command::none();
+        };
+        let index = self.conversation.messages.len().saturating_sub(1);
+        let Some(response) = self.conversation.messages.get(index) else {
+            return Command::none();
+        };
+        if response.role != MessageRole::Assistant || response.content.is_empty() {
+            return Command::none();
+        }
+
+        let original = response.content.clone();
+        let timeout = Duration::from_secs(self.config.hooks.timeout_secs);
+        Command::perform(
+            async move {
+                let result = crate::utils::hook::run_hook(&command, &original, timeout)
+                    .await
+                    .map_err(|e| e.to_string());
+                (original, result)
+            },
+            move |(original, result)| Message::ResponseHookFinished(index, original, result),
+        )
+    }
+
+    /// Request a response to the conversation and stream it into a new assistant message
+    fn start_response(&mut self, temperature: Option<f32>) -> Command<Message> {
+        self.is_sending = true;
+        self.streaming_content = String::new();
+        self.loading_state = Some(self.config.ui.loading_text.generating.clone());
+
+        // Check if we have a valid Ollama client
+        if let Some(client) = &self.ollama_client {
+            let client = client.clone();
+            // Build the request, applying any configured prompt wrapping
+            let request = self.build_request(&self.conversation.messages, temperature);
+            // Continue from the last response's context rather than re-sending the conversation, if enabled
+            let generate = self.config.ollama.reuse_context.then(|| {
+                let context = self.conversation.reusable_context(&request.model);
+                crate::ollama::request::build_generate_request(&request, context)
+            });
+            
+            info!("Sending message to Ollama API");
+            self.last_request = Some((request.clone(), chrono::Utc::now()));
+            
+            // Add an initial empty assistant message that we'll update with chunks
+            self.conversation.add_message(MessageRole::Assistant, "");
+            self.response_index = Some(self.conversation.messages.len() - 1);
+            if let Some(response) = self.conversation.messages.last_mut() {
+                response.temperature = temperature;
+                response.params = Some(request.parameters.clone());
+            }
+
+            if let Some(generate) = generate {
+                return self.request_generated_response(client, generate);
+            }
+            if !self.config.ollama.stream {
+                return self.request_complete_response(client, request);
+            }
+
+            self.reset_streaming_channel();
+            self.is_streaming = true;
+            
+            let sender = self.chunk_sender.clone().unwrap();
+            
+            // Compile the client-side stop pattern once for this response
+            let stop_regex = self.config.ollama.client_stop_regex.as_deref().and_then(|pattern| {
+                regex::Regex::new(pattern)
+                    .map_err(|e| error!("Ignoring invalid client stop regex: {}", e))
+                    .ok()
+            });
+            
+            // Create a command to start processing the stream
+            let start_stream_command = Command::perform(
+                async { }, 
+                |_| Message::StartStreaming
+            );
+            
+            // Create a command to process the stream, which Stop aborts
+            let (stream, handle) = futures::future::abortable(async move {
+                // The client holds a request slot for the whole duration of the stream
+                client
+                    .stream_chat(&request, stop_regex.as_ref(), |content| {
+                        // Send the chunk through the channel
+                        info!("Sending stream chunk: {}", content);
+                        let _ = sender.send(content);
+                    })
+                    .await
+            });
+            self.stream_handle = Some(handle);
+            let stream_command = Command::perform(stream, |result| match result {
+                Ok(Ok(response)) => Message::StreamCompleted(response.metadata, response.images),
+                Ok(Err(e)) => Message::MessageError(e),
+                Err(Aborted) => Message::StreamAborted,
+            });
+            
+            // Return both commands
+            Command::batch(vec![start_stream_command, stream_command])
+        } else {
+            // No Ollama client available
+            self.is_sending = false;
+            self.error = Some("Ollama API client not initialized. Please check your connection.".to_string());
+            Command::none()
+        }
+    }
+
+    /// Request the whole response at once, for `ollama.stream = false`
+    fn request_complete_response(
+        &mut self,
+        client: OllamaClient,
+        request: ChatCompletionRequest,
+    ) -> Command<Message> {
+        let (response, handle) = futures::future::abortable(async move {
+            client.chat_completion(&request).await
+        });
+        self.stream_handle = Some(handle);
+        Command::perform(response, |result| match result {
+            Ok(Ok(response)) if response.message.content.is_empty() => {
+                Message::MessageError(ResponseError::Empty)
+            }
+            Ok(Ok(response)) => Message::CompleteResponseReceived(response.metadata(), response.message.content),
+            Ok(Err(e)) => Message::MessageError(ResponseError::Failed(e.to_string())),
+            Err(Aborted) => Message::StreamAborted,
+        })
+    }
+
+    /// Request the whole response from the generate endpoint, for `ollama.reuse_context`
+    fn request_generated_response(
+        &mut self,
+        client: OllamaClient,
+        request: GenerateRequest,
+    ) -> Command<Message> {
+        let model = request.model.clone();
+        let (response, handle) = futures::future::abortable(async move {
+            client.generate(&request).await
+        });
+        self.stream_handle = Some(handle);
+        Command::perform(response, move |result| match result {
+            Ok(Ok(response)) if response.response.is_empty() => Message::MessageError(ResponseError::Empty),
+            Ok(Ok(response)) => Message::GeneratedResponse(model, response.response, response.context),
+            Ok(Err(e)) => Message::MessageError(ResponseError::Failed(e.to_string())),
+            Err(Aborted) => Message::StreamAborted,
+        })
+    }
+
+    /// Start probing the Ollama API, replacing any probe already in flight
+    fn connect(&mut self) -> Command<Message> {
+        if let Some(handle) = self.connect_handle.take() {
+            handle.abort();
+        }
+        
+        let api_url = self.config.ollama.api_url.clone();
+        let api_style = self.config.ollama.api_style;
+        let api_key = self.config.ollama.resolved_api_key();
+        let headers = self.config.ollama.headers.clone();
+        let request_timeout = self.config.ollama.request_timeout();
+        let connect_timeout = self.config.ollama.connect_timeout();
+        let max_retry_after = Duration::from_secs(self.config.ollama.max_retry_after_secs);
+        let probe_timeout = Duration::from_secs(self.config.ollama.probe_timeout_secs);
+        let probe_endpoint = self.config.ollama.probe_endpoint;
+        let breaker = (self.config.ollama.circuit_breaker_failures > 0).then(|| {
+            CircuitBreaker::new(
+                self.config.ollama.circuit_breaker_failures,
+                Duration::from_secs(self.config.ollama.circuit_breaker_window_secs),
+                Duration::from_secs(self.config.ollama.circuit_breaker_cooldown_secs),
+            )
+        });
+        let first_token_timeout = Duration::from_secs(self.config.ollama.first_token_timeout_secs);
+        let stream_idle_timeout = Duration::from_secs(self.config.ollama.stream_idle_timeout_secs);
+        let limiter = self.limiter.clone();
+        let (probe, handle) = futures::future::abortable(async move {
+            let client = OllamaClient::new(&api_url)
+                .and_then(|client| client.with_timeouts(request_timeout, connect_timeout));
+            match client {
+                Ok(client) => {
+                    let mut client = client
+                        .with_api_style(api_style)
+                        .with_api_key(api_key)
+                        .with_headers(&headers)
+                        .with_max_retry_after(max_retry_after)
+                        .with_probe_endpoint(probe_endpoint)
+                        .with_stream_timeouts(first_token_timeout, stream_idle_timeout)
+                        .with_limiter(limiter);
+                    if let Some(breaker) = breaker {
+                        client = client.with_circuit_breaker(breaker);
+                    }
+                    // Test connection to Ollama API
+                    match client.probe(probe_timeout).await {
+                        Ok(()) => Ok(client),
+                        Err(e) => Err(format!("Failed to connect to Ollama API: {}", e))
+                    }
+                },
+                Err(e) => Err(format!("Failed to create Ollama client: {}", e))
+            }
+        });
+        
+        self.connect_handle = Some(handle);
+        self.error = None;
+        self.loading_state = Some(self.config.ui.loading_text.connecting.clone());
+        
+        Command::perform(probe, |result| match result {
+            Ok(Ok(client)) => Message::OllamaConnected(client),
+            Ok(Err(e)) => Message::OllamaConnectionFailed(e),
+            Err(Aborted) => Message::ConnectionProbeAborted,
+        })
+    }
+
+    /// List the installed models for the model picker
+    fn list_models(&self) -> Command<Message> {
+        let Some(client) = self.ollama_client.clone() else {
+            return Command::none();
+        };
+        Command::perform(
+            async move {
+                let response = client.list_models().await.map_err(|e| e.to_string())?;
+                Ok(response.models.into_iter().map(|model| model.name).collect())
+            },
+            Message::ModelsListed,
+        )
+    }
+
+    /// List the models loaded into memory for the settings panel
+    fn list_running_models(&mut self) -> Command<Message> {
+        let Some(form) = &mut self.settings else {
+            return Command::none();
+        };
+        let Some(client) = self.ollama_client.clone() else {
+            form.running_models = Some(Err("Not connected to Ollama".to_string()));
+            return Command::none();
+        };
+        form.running_models = None;
+        Command::perform(
+            async move {
+                let response = client.list_running_models().await.map_err(|e| e.to_string())?;
+                Ok(response.models)
+            },
+            Message::RunningModelsListed,
+        )
+    }
+
+    /// Load the default model in the background so the first response starts sooner
+    fn prewarm(&self) -> Command<Message> {
+        let Some(client) = self.ollama_client.clone() else {
+            return Command::none();
+        };
+        if !self.config.ollama.prewarm {
+            return Command::none();
+        }
+        let model = self.config.ollama.default_model.clone();
+        Command::perform(
+            async move {
+                client.prewarm(&model).await.map_err(|e| e.to_string())
+            },
+            Message::ModelPrewarmed,
+        )
+    }
+
+    /// Write the last request and its assembled response to the API trace
+    fn trace_response(&self, response: &str) {
+        if let (Some(tracer), Some((request, requested_at))) = (&self.tracer, &self.last_request) {
+            if let Err(e) = tracer.record(request, *requested_at, response) {
+                error!("Failed to write API trace: {}", e);
+            }
+        }
+    }
+
     /// Update memory usage statistics
     pub fn update_memory_usage(&mut self) {
         // This is a simple implementation that gets the current process memory usage
@@ -138,10 +915,7 @@ impl App {
                 info!("Memory usage high ({}MB), optimizing conversation buffer", usage);
                 
                 // Truncate conversation if it's very long
-                let max_length = self.config.conversation.max_length;
-                if self.conversation.messages.len() > max_length / 2 {
-                    self.conversation.truncate(max_length / 2);
-                }
+                self.truncate_conversation(self.config.conversation.max_chars / 2);
                 
                 // Force garbage collection by clearing and shrinking buffers
                 self.streaming_content.shrink_to_fit();
@@ -187,29 +961,209 @@ impl App {
         self.is_sending
     }
     
+    /// Get the application configuration
+    pub fn config(&self) -> &crate::config::Config {
+        &self.config
+    }
+    
+    /// Get the conversation currently shown
+    pub fn conversation(&self) -> &Conversation {
+        &self.conversation
+    }
+    
+    /// Get the saved conversations listed in the sidebar
+    pub fn conversations(&self) -> &[Conversation] {
+        &self.conversations
+    }
+    
+    /// Get the warning about the last message sent, if any
+    pub fn warning(&self) -> Option<&str> {
+        self.warning.as_deref()
+    }
+    
+    /// Get the loading message, e.g. while connecting or generating a response
+    pub fn loading_state(&self) -> Option<&str> {
+        self.loading_state.as_deref()
+    }
+    
+    /// Get the progress of the missing default model's download, if one is running
+    pub fn pull_status(&self) -> Option<&str> {
+        self.pull_status.as_deref()
+    }
+    
+    /// Get the models offered in the model picker
+    ///
+    /// These are the installed models, plus the configured model if it isn't one of them.
+    pub fn model_choices(&self) -> Vec<String> {
+        let mut choices = self.models.clone();
+        let current = &self.config.ollama.default_model;
+        if !choices.contains(current) {
+            choices.insert(0, current.clone());
+        }
+        choices
+    }
+    
+    /// Check whether the conversation's estimated tokens are over `ollama.context_warning_tokens`
+    pub fn context_warning(&self) -> bool {
+        let threshold = self.config.ollama.context_warning_tokens;
+        threshold > 0 && self.conversation.approx_token_count() > threshold
+    }
+    
+    /// Get the index of the user message being edited, if any
+    pub fn editing(&self) -> Option<usize> {
+        self.editing
+    }
+    
+    /// Describe how fast the last response was generated, once it is complete
+    pub fn last_response_speed(&self) -> Option<String> {
+        if self.is_sending {
+            return None;
+        }
+        let last = self.conversation.messages.last().filter(|message| message.role == MessageRole::Assistant)?;
+        last.metadata.as_ref()?.speed_summary()
+    }
+    
+    /// Get the indices of the messages matching the find query, and the position of the current one
+    pub fn find_matches(&self) -> (&[usize], Option<usize>) {
+        (&self.find_matches, self.find_cursor)
+    }
+    
+    /// Get the base64-encoded images to send with the next message
+    pub fn pending_images(&self) -> Vec<&str> {
+        self.pending_images.iter().map(|image| image.data.as_str()).collect()
+    }
+    
+    /// Get the settings typed into the settings panel, if it is open
+    pub fn settings(&self) -> Option<&SettingsForm> {
+        self.settings.as_ref()
+    }
+    
+    /// Check whether the input was cut off at the maximum input length
+    pub fn input_truncated(&self) -> bool {
+        self.input_truncated
+    }
+    
+    /// Get the text of the indicator shown in the conversation while a response is generated
+    fn response_loading_text(&self) -> Option<&str> {
+        if self.error.is_some() || self.connect_handle.is_some() || self.replay.is_some() {
+            return None;
+        }
+        self.loading_state.as_deref()
+    }
+    
+    /// Compare the response whose changes are shown with the version it replaced
+    fn response_diff(&self) -> Option<(usize, Vec<DiffSegment>)> {
+        let index = self.diff_shown?;
+        let previous = self.response_history.get(&index)?.last()?;
+        let current = self.conversation.messages.get(index)?;
+        Some((index, diff_lines(previous, &current.content)))
+    }
+    
+    /// Get the earlier versions of the response at `index`, oldest first
+    pub fn response_history(&self, index: usize) -> &[String] {
+        self.response_history.get(&index).map_or(&[], Vec::as_slice)
+    }
+    
+    /// Get the indices of the selected messages, in conversation order
+    pub fn selected_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.selected_messages.iter().copied().collect();
+        indices.sort_unstable();
+        indices
+    }
+    
+    /// Get the request preview produced by the last dry-run send, if any
+    pub fn dry_run_preview(&self) -> Option<&str> {
+        self.dry_run_preview.as_deref()
+    }
+    
     /// Add a message to the conversation
     pub fn add_message(&mut self, role: MessageRole, content: &str) {
         self.conversation.add_message(role, content);
         self.scroll_to_bottom = true;
     }
-}
 
-impl Application for App {
-    type Executor = executor::Default;
-    type Message = Message;
-    type Theme = Theme;
-    type Flags = crate::config::Config;
+    /// Check whether the window is shown on screen, so redrawing it is worthwhile
+    ///
+    /// An unfocused window is still visible, so only hiding or minimizing it counts.
+    pub fn is_visible(&self) -> bool {
+        !self.hidden
+    }
 
-    fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+    /// Get the timers to run
+    ///
+    /// Only auto-saving carries on while the window isn't visible; animations
+    /// and the log viewer wait until it is shown again.
+    pub fn timers(&self) -> Vec<Timer> {
+        let mut timers = Vec::new();
+        if self.config.conversation.autosave_interval_secs > 0 {
+            timers.push(Timer::AutoSave);
+        }
+        if !self.is_visible() {
+            return timers;
+        }
+        if self.log_viewer.is_some() {
+            timers.push(Timer::RefreshLogs);
+        }
+        if self.smooth_scrolling {
+            timers.push(Timer::SmoothScroll);
+        }
+        timers
+    }
+
+    /// Record whether the window is hidden, catching up on skipped redraws once it is shown again
+    fn set_hidden(&mut self, hidden: bool) -> Command<Message> {
+        if self.hidden == hidden {
+            return Command::none();
+        }
+        self.hidden = hidden;
+
+        if hidden {
+            debug!("Window hidden, pausing animations");
+            return Command::none();
+        }
+        debug!("Window shown, resuming animations");
+        if let Some(viewer) = &mut self.log_viewer {
+            viewer.refresh();
+        }
+        if self.scroll_to_bottom {
+            return self.update(Message::ScrollToBottom);
+        }
+        Command::none()
+    }
+
+    /// Create the app, loading and saving conversations and the configuration through `storage`
+    pub fn with_storage(flags: crate::config::Config, storage: Storage) -> (Self, Command<Message>) {
         info!("Initializing App with configuration");
         
+        // Move old conversations to the trash before loading the rest
+        let max_conversations = flags.conversation.max_conversations;
+        if let Err(e) = storage.prune_conversations(max_conversations, &Conversation::is_kept) {
+            error!("Failed to prune old conversations: {}", e);
+        }
+        
         // Try to load the most recent conversation or create a new one
-        let conversation = match crate::data::conversation::Conversation::load_all() {
-            Ok(conversations) if !conversations.is_empty() => {
-                info!("Loaded existing conversation: {}", conversations[0].title);
-                conversations[0].clone()
+        let conversations = storage.load_conversations().unwrap_or_else(|e| {
+            error!("Failed to load conversations: {}", e);
+            Vec::new()
+        });
+        // A conversation asked for on the command line takes the place of the most recent one
+        let (requested, open_error) = match flags.open.as_deref() {
+            Some(query) => match Conversation::find_among(&conversations, query) {
+                Ok(Some(conversation)) => (Some(conversation.clone()), None),
+                Ok(None) => (None, Some(format!("No conversation matches \"{}\"", query))),
+                Err(e) => (None, Some(format!("Failed to open conversation: {}", e))),
+            },
+            None => (None, None),
+        };
+        if let Some(error) = &open_error {
+            error!("{}", error);
+        }
+        let conversation = match requested.as_ref().or(conversations.first()) {
+            Some(conversation) => {
+                info!("Loaded existing conversation: {}", conversation.title);
+                conversation.clone()
             }
-            _ => {
+            None => {
                 info!("Creating new conversation");
                 crate::data::conversation::Conversation::new(
                     "New Conversation", 
@@ -221,61 +1175,111 @@ impl Application for App {
         // Create a channel for streaming chunks
         let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
         
-        let app = Self {
+        let theme = resolve_theme(flags.theme.mode);
+        let mut app = Self {
             dragging: false,
             drag_start: None,
             message: String::new(),
             config: flags.clone(),
             window: ui_window::Window::new(&flags),
             conversation,
+            conversations,
+            sidebar_visible: false,
+            search_query: String::new(),
+            search_hits: Vec::new(),
+            log_viewer: None,
+            input_truncated: false,
+            template: None,
+            template_values: BTreeMap::new(),
+            pending_images: Vec::new(),
+            images: ImageCache::default(),
+            conversation_dirty: DirtyFlag::default(),
+            config_dirty: DirtyFlag::default(),
             is_sending: false,
             scroll_to_bottom: true, // Set to true initially to scroll to bottom on load
+            scroll_offset: 0.0,
+            smooth_scrolling: false,
+            focused: true,
+            hidden: false,
             error: None,
+            // The connection probe clears errors, so a failed lookup is shown as a warning
+            warning: open_error,
+            redactor: Redactor::new(&flags.privacy.redact_patterns).unwrap_or_else(|e| {
+                error!("Not redacting outgoing messages: {}", e);
+                Redactor::default()
+            }),
             ollama_client: None,
             streaming_content: String::new(),
-            loading_state: Some("Connecting to Ollama API...".to_string()),
+            loading_state: Some(flags.ui.loading_text.connecting.clone()),
             last_resize_time: std::time::Instant::now(),
             memory_usage: None,
             chunk_sender: Some(sender),
             channel_state: Arc::new(Mutex::new(Some(receiver))),
             is_streaming: false,
+            tracer: flags.logging.trace_api.then(ApiTracer::in_log_dir),
+            last_request: None,
+            transcript: flags.conversation.tee_transcript.as_deref().and_then(|path| {
+                TranscriptWriter::open(path)
+                    .map_err(|e| error!("Failed to open transcript, not teeing responses: {}", e))
+                    .ok()
+            }),
+            speaker: if flags.ui.text_to_speech { Speaker::detect() } else { None },
+            speaking: None,
+            palette: Palette::from_scheme(&flags.theme.colors, &theme),
+            theme,
+            bookmark_cursor: None,
+            find_query: None,
+            find_matches: Vec::new(),
+            find_cursor: None,
+            confirming_clear: false,
+            confirming_delete: None,
+            connect_handle: None,
+            stream_handle: None,
+            limiter: RequestLimiter::new(flags.ollama.max_concurrent_requests),
+            response_index: None,
+            expanded_details: HashSet::new(),
+            selected_messages: HashSet::new(),
+            response_history: HashMap::new(),
+            diff_shown: None,
+            dry_run_preview: None,
+            info_visible: HashSet::new(),
+            outline_visible: false,
+            storage,
+            failed_requests: if flags.logging.persist_failed_requests {
+                FailedRequestLog::in_log_dir(MAX_FAILED_REQUESTS)
+            } else {
+                FailedRequestLog::new(MAX_FAILED_REQUESTS)
+            },
+            issues_visible: false,
+            settings: None,
+            replay_model: String::new(),
+            replay: None,
+            replay_handle: None,
+            pulling: None,
+            pull_status: None,
+            models: Vec::new(),
+            editing: None,
         };
         
+        app.images.sync(&app.conversation);
+        
         // Initialize Ollama client
-        let api_url = flags.ollama.api_url.clone();
+        let connect_command = app.connect();
         
         (
             app,
             Command::batch(vec![
                 // Ensure we scroll to bottom after connection
                 Command::perform(async {}, |_| Message::ScrollToBottom),
-                Command::perform(
-                    async move {
-                        match OllamaClient::new(&api_url) {
-                            Ok(client) => {
-                                // Test connection to Ollama API
-                                match client.list_models().await {
-                                    Ok(_) => Ok(client),
-                                    Err(e) => Err(format!("Failed to connect to Ollama API: {}", e))
-                                }
-                            },
-                            Err(e) => Err(format!("Failed to create Ollama client: {}", e))
-                        }
-                    },
-                    |result| match result {
-                        Ok(client) => Message::OllamaConnected(client),
-                        Err(e) => Message::OllamaConnectionFailed(e),
-                    }
-                ),
+                connect_command,
             ])
         )
     }
+}
 
-    fn title(&self) -> String {
-        String::from("ScreenSage")
-    }
-
-    fn update(&mut self, message: Message) -> Command<Message> {
+impl App {
+    /// Apply a message to the app, returning the work it starts
+    fn handle_message(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::DragStarted(x, y) => {
                 self.dragging = true;
@@ -301,176 +1305,234 @@ impl Application for App {
                 self.dragging = false;
                 self.drag_start = None;
                 
-                // Save window position to config
-                if let Err(e) = self.window.save_to_config(&mut self.config) {
-                    debug!("Failed to save window position: {}", e);
-                }
+                // Record the window position; it is written on the next auto-save
+                self.window.apply_to_config(&mut self.config);
+                self.config_dirty.mark();
                 
                 Command::none()
             }
             Message::Close => {
-                // Save window position before closing
-                if let Err(e) = self.window.save_to_config(&mut self.config) {
-                    debug!("Failed to save window position: {}", e);
-                }
-                
+                // Every way of quitting (×, Esc, Cmd+Q, the OS close request) ends up here
+                self.flush();
                 iced_window::close()
             }
-            Message::InputChanged(value) => {
+            Message::InputChanged(mut value) => {
+                // Cut off runaway pastes instead of rendering megabytes of text
+                let max_chars = self.config.ui.max_input_chars;
+                self.input_truncated = max_chars > 0 && truncate_chars(&mut value, max_chars);
+                if self.input_truncated {
+                    warn!("Input cut off at {} characters", max_chars);
+                }
                 self.message = value;
                 Command::none()
             }
             Message::OllamaConnected(client) => {
                 info!("Successfully connected to Ollama API");
-                self.ollama_client = Some(client);
+                self.connect_handle = None;
+                self.ollama_client = Some(client.clone());
                 self.error = None;
                 self.loading_state = None;
                 
                 // Start memory usage monitoring
                 self.update_memory_usage();
                 
+                // Listing models succeeds without the default model, so check it is installed.
+                // Only Ollama's own API can show and pull models.
+                if self.config.ollama.api_style != ApiStyle::Ollama {
+                    return self.prewarm();
+                }
+                let model = self.config.ollama.default_model.clone();
+                Command::batch(vec![
+                    Command::perform(
+                        async move { client.check_model_exists(&model).await.map_err(|e| e.to_string()) },
+                        Message::ModelChecked,
+                    ),
+                    self.list_models(),
+                ])
+            }
+            Message::ModelChecked(result) => match result {
+                Ok(true) => self.prewarm(),
+                Ok(false) => {
+                    // The subscription downloads the model and reports its progress
+                    let model = self.config.ollama.default_model.clone();
+                    info!("Model '{}' isn't installed, downloading it", model);
+                    self.pull_status = Some(format!("Downloading {}...", model));
+                    self.pulling = Some(model);
+                    Command::none()
+                }
+                Err(e) => {
+                    warn!("Failed to check whether the default model is installed: {}", e);
+                    Command::none()
+                }
+            },
+            Message::ModelPullProgress(progress) => {
+                if let Some(model) = &self.pulling {
+                    self.pull_status = Some(match progress.percent() {
+                        Some(percent) => format!("Downloading {}: {}%", model, percent),
+                        None => format!("Downloading {}: {}", model, progress.status),
+                    });
+                }
+                Command::none()
+            }
+            Message::ModelPullFinished(result) => {
+                let Some(model) = self.pulling.take() else {
+                    return Command::none();
+                };
+                self.pull_status = None;
+                match result {
+                    Ok(()) => {
+                        info!("Downloaded model '{}'", model);
+                        Command::batch(vec![self.prewarm(), self.list_models()])
+                    }
+                    Err(e) => {
+                        error!("Failed to download model '{}': {}", model, e);
+                        self.error = Some(format!("Failed to download model {}: {}", model, e));
+                        Command::none()
+                    }
+                }
+            }
+            Message::ModelPrewarmed(result) => {
+                // A cold model only makes the first response slower, so failures aren't shown
+                if let Err(e) = result {
+                    warn!("Failed to prewarm model: {}", e);
+                }
+                Command::none()
+            }
+            Message::ModelsListed(result) => {
+                match result {
+                    Ok(models) => self.models = models,
+                    // The picker still offers the configured model
+                    Err(e) => warn!("Failed to list models: {}", e),
+                }
                 Command::none()
             }
+            Message::ModelSelected(model) => {
+                if model == self.config.ollama.default_model {
+                    return Command::none();
+                }
+                info!("Switching to model '{}'", model);
+                self.config.ollama.default_model = model;
+                if let Err(e) = self.storage.save_config(&self.config) {
+                    error!("Failed to save configuration: {}", e);
+                }
+                self.prewarm()
+            }
             Message::OllamaConnectionFailed(error) => {
                 error!("Failed to connect to Ollama API: {}", error);
+                self.connect_handle = None;
+                self.loading_state = None;
                 self.error = Some(format!("Failed to connect to Ollama API: {}", error));
                 Command::none()
             }
+            Message::CancelConnecting => {
+                // Skip the probe and let the user work offline until they reconnect
+                if let Some(handle) = self.connect_handle.take() {
+                    info!("Connection attempt cancelled, continuing offline");
+                    handle.abort();
+                }
+                self.loading_state = None;
+                Command::none()
+            }
+            Message::ConnectionProbeAborted => {
+                debug!("Connection probe aborted");
+                Command::none()
+            }
+            Message::Reconnect => {
+                info!("Reconnecting to Ollama API");
+                self.connect()
+            }
             Message::SendMessage => {
                 if self.message.trim().is_empty() || self.is_sending {
                     return Command::none();
                 }
                 
+                // Redact secrets before the message is sent or stored
+                self.warning = None;
+                self.input_truncated = false;
+
+                // Wrap the message in the selected template, which needs a value for each variable
+                if let Some(template) = self.template.and_then(|index| self.config.templates.get(index)) {
+                    let mut vars: BTreeMap<String, String> = self
+                        .template_values
+                        .iter()
+                        .filter(|(_, value)| !value.trim().is_empty())
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect();
+                    vars.insert(crate::utils::template::INPUT_VARIABLE.to_string(), self.message.clone());
+                    match crate::utils::template::apply_template(&template.body, &vars) {
+                        Ok(filled) => self.message = filled,
+                        Err(e) => {
+                            warn!("Not sending, template {} is incomplete: {}", template.name, e);
+                            self.warning = Some(format!("Template {}: {}", template.name, e));
+                            return Command::none();
+                        }
+                    }
+                }
+                if let Some(redacted) = self.redactor.redact(&self.message) {
+                    warn!("Redacted text matching a redact pattern from the outgoing message");
+                    self.message = redacted;
+                    self.warning = Some(
+                        "Parts of your message matched a redact pattern and were replaced with [REDACTED]."
+                            .to_string(),
+                    );
+                }
+                
+                // An edited message replaces the original, which must still be a user message
+                let editing = self.editing.filter(|&index| {
+                    self.conversation.messages.get(index).is_some_and(|message| message.role == MessageRole::User)
+                });
+                
+                // In dry-run mode, show the request instead of sending it
+                if self.config.dry_run {
+                    let kept = editing.unwrap_or(self.conversation.messages.len());
+                    let mut messages = self.conversation.messages[..kept].to_vec();
+                    let mut message = ConversationMessage::new(MessageRole::User, &self.message);
+                    message.images = self.pending_images.iter().map(|image| image.data.clone()).collect();
+                    messages.push(message);
+                    let request = self.build_request(&messages, None);
+                    self.show_dry_run(&request);
+                    return Command::none();
+                }
+                
                 debug!("Message sent: {}", self.message);
                 
-                // Add the user message to the conversation
+                // Add the user message to the conversation, or replace the edited one and drop its replies
                 let user_message = self.message.clone();
-                self.conversation.add_message(MessageRole::User, &user_message);
-                
-                // Save the conversation to disk
-                if let Err(e) = self.conversation.save() {
-                    error!("Failed to save conversation: {}", e);
+                let max_messages = self.config.conversation.max_messages;
+                self.editing = None;
+                if let Some(index) = editing {
+                    info!("Re-running conversation from edited message {}", index);
+                    self.conversation.edit_message(index, &user_message);
+                    self.conversation.truncate_after(index);
+                    self.expanded_details.clear();
+                    self.selected_messages.clear();
+                    self.response_history.clear();
+                    self.diff_shown = None;
+                    self.bookmark_cursor = None;
+                } else if let Some(archived) =
+                    self.conversation.add_message_capped(MessageRole::User, &user_message, max_messages)
+                {
+                    if let Err(e) = self.storage.save_conversation(&archived) {
+                        error!("Failed to save archived conversation: {}", e);
+                    }
+                    self.bookmark_cursor = None;
                 }
                 
-                // Truncate the conversation if it exceeds the maximum length
-                let max_length = self.config.conversation.max_length;
-                if max_length > 0 {
-                    self.conversation.truncate(max_length);
+                // Send the attached images with the message
+                if !self.pending_images.is_empty() {
+                    let index = editing.unwrap_or(self.conversation.messages.len().saturating_sub(1));
+                    let images = std::mem::take(&mut self.pending_images).into_iter().map(|image| image.data);
+                    self.conversation.attach_images(index, images.collect());
                 }
+                
+                self.auto_save_conversation();
+                
+                // Drop the oldest messages once the conversation has too many characters
+                self.truncate_conversation(self.config.conversation.max_chars);
 
-                // Clear the input and set sending state
+                // Clear the input and wait for the response
                 self.message = String::new();
-                self.is_sending = true;
-                self.streaming_content = String::new();
-                self.loading_state = Some("Waiting for response...".to_string());
-
-                // Check if we have a valid Ollama client
-                if let Some(client) = &self.ollama_client {
-                    let client = client.clone();
-                    let model = self.config.ollama.default_model.clone();
-                    let messages = self.conversation.messages.clone();
-
-                    // Convert our messages to Ollama API format
-                    let ollama_messages = messages.iter().map(|msg| {
-                        crate::ollama::models::ChatMessage {
-                            role: msg.role.as_str().to_string(),
-                            content: msg.content.clone(),
-                        }
-                    }).collect::<Vec<_>>();
-
-                    // Clone the configuration values we need
-                    let temperature = self.config.ollama.temperature;
-                    let top_p = self.config.ollama.top_p;
-                    let top_k = self.config.ollama.top_k;
-                    let max_tokens = self.config.ollama.max_tokens;
-
-                    let request = crate::ollama::models::ChatCompletionRequest {
-                        model,
-                        messages: ollama_messages,
-                        stream: Some(true),
-                        parameters: crate::ollama::models::GenerationParameters {
-                            temperature: Some(temperature),
-                            top_p: Some(top_p),
-                            top_k: Some(top_k),
-                            max_tokens: Some(max_tokens),
-                            presence_penalty: None,
-                            frequency_penalty: None,
-                            stop: None,
-                        },
-                    };
-                    
-                    info!("Sending message to Ollama API");
-                    
-                    // Add an initial empty assistant message that we'll update with chunks
-                    self.conversation.add_message(MessageRole::Assistant, "");
-
-                    self.reset_streaming_channel();
-                    self.is_streaming = true;
-                    
-                    let sender = self.chunk_sender.clone().unwrap();
-                    
-                    // Create a command to start processing the stream
-                    let start_stream_command = Command::perform(
-                        async { }, 
-                        |_| Message::StartStreaming
-                    );
-                    
-                    // Create a command to process the stream
-                    let stream_command = Command::perform(
-                        async move {
-                            let stream_result = client.chat_completion_stream(&request).await;
-                            match stream_result {
-                                Ok(mut stream) => {
-                                    let mut full_content = String::new();
-                                    
-                                    // Process each chunk as it arrives
-                                    while let Some(chunk_result) = stream.next().await {
-                                        match chunk_result {
-                                            Ok(chunk) => {
-                                                let content = chunk.message.content.clone();
-                                                if !content.is_empty() {
-                                                    full_content.push_str(&content);
-                                                    // Send the chunk through the channel
-                                                    info!("Sending stream chunk: {}", content);
-                                                    let _ = sender.send(content);
-                                                }
-                                                
-                                                // If this is the last chunk, break
-                                                if chunk.done {
-                                                    break;
-                                                }
-                                            },
-                                            Err(e) => {
-                                                return Err(format!("Stream error: {}", e));
-                                            }
-                                        }
-                                    }
-                                    
-                                    Ok(full_content)
-                                },
-                                Err(e) => Err(format!("Failed to create stream: {}", e)),
-                            }
-                        },
-                        |result| match result {
-                            Ok(content) => {
-                                if content.is_empty() {
-                                    Message::MessageError("Received empty response from Ollama".to_string())
-                                } else {
-                                    Message::EndStreaming
-                                }
-                            },
-                            Err(e) => Message::MessageError(e),
-                        }
-                    );
-                    
-                    // Return both commands
-                    Command::batch(vec![start_stream_command, stream_command])
-                } else {
-                    // No Ollama client available
-                    self.is_sending = false;
-                    self.error = Some("Ollama API client not initialized. Please check your connection.".to_string());
-                    Command::none()
-                }
+                self.start_response(None)
             }
 
             Message::StartStreaming => {
@@ -492,6 +1554,8 @@ impl Application for App {
             Message::StreamChunk(chunk) => {
                 // Append the chunk to the streaming content
                 self.streaming_content.push_str(&chunk);
+                self.tee_chunk(&chunk);
+                self.conversation_dirty.mark();
 
                 info!("In Message::StreamChunk: {}", chunk);
                 
@@ -513,11 +1577,11 @@ impl Application for App {
                     return Command::none();
                 }
                 info!("Streaming completed");
+                self.stream_handle = None;
+                self.trace_response(&self.streaming_content);
+                self.end_transcript_turn();
 
-                // Save the conversation to disk
-                if let Err(e) = self.conversation.save() {
-                    error!("Failed to save conversation: {}", e);
-                }
+                self.auto_save_conversation();
 
                 self.reset_streaming_channel();
 
@@ -530,12 +1594,95 @@ impl Application for App {
                 self.update_memory_usage();
                 self.optimize_conversation_buffer();
                 
-                // Ensure we scroll to the bottom
+                self.run_response_hook()
+            }
+            Message::ResponseHookFinished(index, original, result) => {
+                let output = match result {
+                    Ok(output) => output,
+                    Err(e) => {
+                        warn!("Response hook failed, showing the original response: {}", e);
+                        return Command::none();
+                    }
+                };
+                // The response may have been regenerated or removed while the hook ran
+                match self.conversation.messages.get_mut(index) {
+                    Some(response) if response.content == original => response.display_content = Some(output),
+                    _ => debug!("Response changed while the hook ran, discarding its output"),
+                }
                 Command::none()
             }
             
+            Message::CancelStreaming => {
+                // Stop applies to whatever is being generated, including a replay
+                if self.replay.is_some() {
+                    return self.update(Message::CancelReplay);
+                }
+                let Some(handle) = self.stream_handle.take() else {
+                    return Command::none();
+                };
+                info!("Stopping the response");
+                handle.abort();
+                
+                // Keep what was received, dropping the placeholder if nothing was
+                if let Some(index) = self.response_index.take() {
+                    let is_empty_placeholder = self.conversation.messages.get(index).is_some_and(|message| {
+                        message.role == MessageRole::Assistant && message.content.is_empty()
+                    });
+                    if is_empty_placeholder {
+                        self.conversation.messages.remove(index);
+                        self.response_history.remove(&index);
+                    }
+                }
+                
+                // A response that wasn't streamed has no stream to end
+                self.is_sending = false;
+                self.loading_state = None;
+                if !self.is_streaming {
+                    self.auto_save_conversation();
+                    return Command::none();
+                }
+                self.update(Message::EndStreaming)
+            }
+            Message::StreamAborted => {
+                debug!("Response stream aborted");
+                Command::none()
+            }
+            Message::StreamCompleted(metadata, images) => {
+                // Attach the final-chunk stats and any images to the message that received the response
+                if let Some(index) = self.response_index.take() {
+                    let mut attached = false;
+                    if let Some(metadata) = metadata {
+                        attached |= self.conversation.attach_metadata(index, metadata);
+                    }
+                    if !images.is_empty() {
+                        attached |= self.conversation.attach_images(index, images);
+                    }
+                    if attached {
+                        self.auto_save_conversation();
+                    }
+                }
+                self.update(Message::EndStreaming)
+            }
+            Message::SaveImage(message_index, image_index) => {
+                let root = Conversation::get_export_dir();
+                match self.conversation.export_image(message_index, image_index, &root) {
+                    Ok(path) => info!("Saved image to {}", path.display()),
+                    Err(e) => {
+                        error!("Failed to save image: {}", e);
+                        self.error = Some(format!("Failed to save image: {}", e));
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleResponseDetails(index) => {
+                if !self.expanded_details.remove(&index) {
+                    self.expanded_details.insert(index);
+                }
+                Command::none()
+            }
             Message::MessageReceived(response) => {
                 info!("Received complete response: {}", response);
+                self.trace_response(&response);
                 
                 // Update the last message or add a new one if needed
                 if let Some(last) = self.conversation.messages.last_mut() {
@@ -551,13 +1698,12 @@ impl Application for App {
                     self.conversation.add_message(MessageRole::Assistant, &response);
                 }
                 
-                // Save the conversation to disk
-                if let Err(e) = self.conversation.save() {
-                    error!("Failed to save conversation: {}", e);
-                }
+                self.auto_save_conversation();
 
                 // Reset sending state
                 self.is_sending = false;
+                self.response_index = None;
+                self.stream_handle = None;
                 self.streaming_content = String::new();
                 self.scroll_to_bottom = true;
                 self.loading_state = None;
@@ -566,15 +1712,72 @@ impl Application for App {
                 self.update_memory_usage();
                 self.optimize_conversation_buffer();
                 
-                Command::none()
+                self.run_response_hook()
+            }
+            Message::CompleteResponseReceived(metadata, response) => {
+                // Keep the stats of a response that wasn't streamed, like those of a streamed one
+                let index = self.response_index;
+                let command = self.update(Message::MessageReceived(response));
+                if let Some(index) = index {
+                    if self.conversation.attach_metadata(index, metadata) {
+                        self.auto_save_conversation();
+                    }
+                }
+                command
+            }
+            Message::GeneratedResponse(model, response, context) => {
+                let command = self.update(Message::MessageReceived(response));
+                // Keep the context so the next message doesn't need the conversation sent again
+                match context {
+                    Some(tokens) => self.conversation.set_context(&model, tokens),
+                    None => self.conversation.context = None,
+                }
+                command
             }
             Message::MessageError(error) => {
                 // Set the error message
                 error!("Message error: {}", error);
-                self.error = Some(error);
+                self.error = Some(error.to_string());
+                
+                // Keep the failure for the Issues panel
+                let model = match &self.last_request {
+                    Some((request, _)) => request.model.clone(),
+                    None => self.conversation.model.clone(),
+                };
+                let prompt = self
+                    .conversation
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|message| message.role == MessageRole::User)
+                    .map(|message| message.content.clone())
+                    .unwrap_or_default();
+                if let Err(e) = self.failed_requests.record(&model, error, &prompt) {
+                    error!("Failed to save failed requests: {}", e);
+                }
+                
+                // Close off any partial response in the transcript
+                if !self.streaming_content.is_empty() {
+                    self.end_transcript_turn();
+                }
+                
+                // Drop the assistant placeholder if nothing was streamed into it
+                if let Some(index) = self.response_index.take() {
+                    let is_empty_placeholder = self.conversation.messages.get(index).is_some_and(|message| {
+                        message.role == MessageRole::Assistant && message.content.is_empty()
+                    });
+                    if is_empty_placeholder {
+                        self.conversation.messages.remove(index);
+                        self.response_history.remove(&index);
+                    }
+                }
                 
                 // Reset sending state
                 self.is_sending = false;
+                self.is_streaming = false;
+                self.stream_handle = None;
+                self.loading_state = None;
+                self.reset_streaming_channel();
                 
                 Command::none()
             }
@@ -594,7 +1797,7 @@ impl Application for App {
             }
             Message::SaveConfig => {
                 // Save the current configuration
-                if let Err(e) = crate::config::save_config(&self.config, None) {
+                if let Err(e) = self.storage.save_config(&self.config) {
                     error!("Failed to save configuration: {}", e);
                     self.error = Some(format!("Failed to save configuration: {}", e));
                 } else {
@@ -607,18 +1810,73 @@ impl Application for App {
                 // Add a newline to the message
                 self.message.push('\n');
                 
-                Command::none()
+                // Keep the line above the input in view once the earlier lines scroll
+                scrollable::snap_to(
+                    scrollable::Id::new(crate::ui::input::INPUT_LINES_ID),
+                    scrollable::RelativeOffset::END,
+                )
+            }
+            Message::JoinLine => {
+                // Continue typing at the end of the line above
+                if self.message.ends_with('\n') {
+                    self.message.pop();
+                }
+                Command::batch([
+                    iced::widget::text_input::move_cursor_to_end(iced::widget::text_input::Id::new(
+                        crate::ui::input::INPUT_ID,
+                    )),
+                    scrollable::snap_to(
+                        scrollable::Id::new(crate::ui::input::INPUT_LINES_ID),
+                        scrollable::RelativeOffset::END,
+                    ),
+                ])
+            }
+            Message::ScrollToMessage(index) => {
+                self.scroll_to_message(index)
             }
             Message::ScrollToBottom => {
+                // Scroll once the window is shown again instead of redrawing a hidden window
+                if !self.is_visible() {
+                    self.scroll_to_bottom = true;
+                    return Command::none();
+                }
                 info!("Scrolling to bottom of conversation");
                 // Reset the scroll flag after sending the scroll command
                 self.scroll_to_bottom = false;
+                if self.config.ui.smooth_scroll {
+                    // The scroll subscription moves the offset a step closer each frame
+                    self.smooth_scrolling = true;
+                    return Command::none();
+                }
                 scrollable::scroll_to(
                     scrollable::Id::new("conversation_messages"),
                     scrollable::AbsoluteOffset { x: 0.0, y: f32::MAX }, // Use MAX to ensure we get to the bottom
                 )
             }
+            Message::ConversationScrolled(offset) => {
+                self.scroll_offset = offset;
+                Command::none()
+            }
+            Message::SmoothScrollTick => {
+                if !self.smooth_scrolling {
+                    return Command::none();
+                }
+                self.scroll_offset = smooth_scroll_step(self.scroll_offset, 1.0);
+                self.smooth_scrolling = self.scroll_offset < 1.0;
+                scrollable::snap_to(
+                    scrollable::Id::new("conversation_messages"),
+                    scrollable::RelativeOffset {
+                        x: 0.0,
+                        y: self.scroll_offset,
+                    },
+                )
+            }
             Message::Resize(width, height) => {
+                // Some platforms resize a minimized window to nothing, which isn't a size worth keeping
+                if width == 0 || height == 0 {
+                    return Command::none();
+                }
+
                 // Debounce resize events - only process if it's been at least 100ms since last resize
                 let now = std::time::Instant::now();
                 let duration = now.duration_since(self.last_resize_time);
@@ -632,7 +1890,7 @@ impl Application for App {
             }
             Message::ResizeEnded => {
                 // Save window size to config
-                if let Err(e) = self.window.save_to_config(&mut self.config) {
+                if let Err(e) = self.window.save_to_config(&mut self.config, &self.storage) {
                     debug!("Failed to save window size: {}", e);
                 }
                 
@@ -642,6 +1900,27 @@ impl Application for App {
                 
                 Command::none()
             }
+            Message::FocusChanged(focused) => {
+                self.focused = focused;
+                // Minimizing also takes focus away, so ask whether the window is still shown
+                let resumed = if focused {
+                    self.set_hidden(false)
+                } else {
+                    iced_window::fetch_mode(Message::WindowModeFetched)
+                };
+
+                // Only a window that floats while focused changes level with focus
+                let level = self.window.level();
+                if level == WindowLevel::FloatingWhenFocused {
+                    debug!("Window focus changed (focused: {})", focused);
+                    return Command::batch([
+                        resumed,
+                        iced_window::change_level(ui_window::level_for(level, focused)),
+                    ]);
+                }
+                resumed
+            }
+            Message::WindowModeFetched(mode) => self.set_hidden(mode == iced_window::Mode::Hidden),
             Message::Moved(x, y) => {
                 self.window.set_position(iced::window::Position::Specific(x, y));
                 Command::none()
@@ -660,6 +1939,738 @@ impl Application for App {
                 }
                 Command::none()
             }
+            Message::SpeakMessage(index) => {
+                let (Some(speaker), Some(message)) = (&self.speaker, self.conversation.messages.get(index)) else {
+                    return Command::none();
+                };
+                
+                // Only one message is read aloud at a time
+                speaker.stop();
+                self.speaking = Some(index);
+                
+                let speaker = speaker.clone();
+                let text = strip_markdown(&message.content);
+                Command::perform(
+                    async move { speaker.speak(&text).await.map_err(|e| e.to_string()) },
+                    move |result| Message::SpeechFinished(index, result)
+                )
+            }
+            Message::StopSpeaking => {
+                if let Some(speaker) = &self.speaker {
+                    speaker.stop();
+                }
+                self.speaking = None;
+                Command::none()
+            }
+            Message::SpeechFinished(index, result) => {
+                if let Err(e) = result {
+                    // Disable read aloud rather than failing on every message
+                    error!("Text-to-speech failed, disabling read aloud: {}", e);
+                    self.speaker = None;
+                }
+                if self.speaking == Some(index) {
+                    self.speaking = None;
+                }
+                Command::none()
+            }
+            Message::ToggleBookmark(index) => {
+                if self.conversation.toggle_bookmark(index) {
+                    self.conversation_dirty.mark();
+                }
+                Command::none()
+            }
+            Message::MoveMessage(from, to) => {
+                // Indices would shift under a response that is still streaming
+                if self.is_sending {
+                    return Command::none();
+                }
+                // or under the message being edited
+                self.editing = None;
+                if self.conversation.move_message(from, to) {
+                    self.conversation_dirty.mark();
+                    self.bookmark_cursor = None;
+                    self.expanded_details.clear();
+                    self.selected_messages.clear();
+                    self.response_history.clear();
+                    self.diff_shown = None;
+                    // Matches are found again in the new order, and stepping starts over
+                    self.find_matches = self.find_query.as_deref().map_or_else(Vec::new, |query| {
+                        self.conversation.search(query)
+                    });
+                    self.find_cursor = None;
+                }
+                Command::none()
+            }
+            Message::NextBookmark => {
+                let bookmarks = self.conversation.bookmarks();
+                if bookmarks.is_empty() {
+                    self.bookmark_cursor = None;
+                    return Command::none();
+                }
+                
+                // Cycle through the bookmarks, wrapping around at the end
+                let cursor = self.bookmark_cursor.map_or(0, |cursor| (cursor + 1) % bookmarks.len());
+                self.bookmark_cursor = Some(cursor);
+                
+                self.scroll_to_message(bookmarks[cursor])
+            }
+            Message::ToggleFind => {
+                self.find_matches.clear();
+                self.find_cursor = None;
+                if self.find_query.take().is_some() {
+                    return Command::none();
+                }
+                self.find_query = Some(String::new());
+                iced::widget::text_input::focus(iced::widget::text_input::Id::new(
+                    crate::ui::presentation::FIND_INPUT_ID,
+                ))
+            }
+            Message::FindChanged(query) => {
+                // Jump to the first match as the query is typed; a blank query clears the highlighting
+                self.find_query = Some(query);
+                self.find_cursor = None;
+                self.step_find_match(true)
+            }
+            Message::NextFindMatch => self.step_find_match(true),
+            Message::PreviousFindMatch => self.step_find_match(false),
+            Message::SaveConversation => {
+                info!("Saving conversation {}", self.conversation.id);
+                self.conversation_dirty.take();
+                if let Err(e) = self.storage.save_conversation(&self.conversation) {
+                    error!("Failed to save conversation: {}", e);
+                    self.error = Some(format!("Failed to save conversation: {}", e));
+                    self.conversation_dirty.mark();
+                }
+                Command::none()
+            }
+            Message::AutoSave => {
+                if self.config.conversation.auto_save && self.conversation_dirty.take() {
+                    debug!("Auto-saving conversation {}", self.conversation.id);
+                    if let Err(e) = self.storage.save_conversation(&self.conversation) {
+                        error!("Failed to auto-save conversation: {}", e);
+                        self.conversation_dirty.mark();
+                    }
+                }
+                if self.config_dirty.take() {
+                    debug!("Auto-saving configuration");
+                    if let Err(e) = self.storage.save_config(&self.config) {
+                        error!("Failed to auto-save configuration: {}", e);
+                        self.config_dirty.mark();
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleSettings => {
+                if self.settings.take().is_some() {
+                    return Command::none();
+                }
+                self.settings = Some(SettingsForm::from_config(&self.config));
+                self.list_running_models()
+            }
+            Message::ListRunningModels => self.list_running_models(),
+            Message::RunningModelsListed(result) => {
+                if let Err(e) = &result {
+                    warn!("Failed to list loaded models: {}", e);
+                }
+                if let Some(form) = &mut self.settings {
+                    form.running_models = Some(result);
+                }
+                Command::none()
+            }
+            Message::SettingChanged(setting, value) => {
+                let Some(form) = &mut self.settings else {
+                    return Command::none();
+                };
+                form.set(setting, value);
+                // Apply the settings live while they are valid, and explain why they aren't otherwise
+                match form.apply(&self.config) {
+                    Ok(config) => {
+                        form.error = None;
+                        self.window.set_opacity(config.window.opacity);
+                        self.config = config;
+                    }
+                    Err(e) => form.error = Some(format!("{:#}", e)),
+                }
+                Command::none()
+            }
+            Message::SaveSettings => {
+                match self.storage.save_config(&self.config) {
+                    Ok(()) => {
+                        info!("Saved settings");
+                        self.settings = None;
+                    }
+                    Err(e) => {
+                        error!("Failed to save settings: {}", e);
+                        if let Some(form) = &mut self.settings {
+                            form.error = Some(format!("Failed to save settings: {}", e));
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::OpenConfigFile => {
+                // Open the configuration file for settings the panel doesn't cover
+                let path = crate::config::get_config_path();
+                if !path.exists() {
+                    if let Err(e) = self.storage.save_config(&self.config) {
+                        error!("Failed to save configuration: {}", e);
+                    }
+                }
+                
+                let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+                info!("Opening configuration file {}", path.display());
+                if let Err(e) = std::process::Command::new(opener).arg(&path).spawn() {
+                    error!("Failed to open configuration file: {}", e);
+                    self.error = Some(format!("Failed to open configuration file: {}", e));
+                }
+                Command::none()
+            }
+            Message::ToggleSidebar => {
+                self.sidebar_visible = !self.sidebar_visible;
+                if self.sidebar_visible {
+                    self.reload_conversations();
+                }
+                Command::none()
+            }
+            Message::ToggleLogViewer => {
+                self.log_viewer = match self.log_viewer {
+                    Some(_) => None,
+                    None => Some(LogViewer::new()),
+                };
+                Command::none()
+            }
+            Message::RefreshLogs => {
+                if let Some(viewer) = &mut self.log_viewer {
+                    viewer.refresh();
+                }
+                Command::none()
+            }
+            Message::LogLevelSelected(level) => {
+                if let Some(viewer) = &mut self.log_viewer {
+                    viewer.set_min_level(level);
+                }
+                Command::none()
+            }
+            Message::SearchChanged(query) => {
+                self.search_query = query.clone();
+                if query.trim().is_empty() {
+                    self.search_hits.clear();
+                    return Command::none();
+                }
+                // Saved files are read off the UI thread, one at a time
+                let storage = self.storage.clone();
+                Command::perform(
+                    async move {
+                        let searched = query.clone();
+                        let search = tokio::task::spawn_blocking(move || storage.search_conversations(&searched));
+                        let hits = match search.await {
+                            Ok(hits) => hits.map_err(|e| format!("{:#}", e)),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        (query, hits)
+                    },
+                    |(query, hits)| Message::SearchCompleted(query, hits),
+                )
+            }
+            Message::SearchCompleted(query, hits) => {
+                // Results of a query that has since been changed are dropped
+                if query != self.search_query {
+                    return Command::none();
+                }
+                match hits {
+                    Ok(hits) => self.search_hits = with_current_conversation(hits, &self.conversation, &query),
+                    Err(e) => {
+                        error!("Failed to search conversations: {}", e);
+                        self.search_hits = with_current_conversation(Vec::new(), &self.conversation, &query);
+                    }
+                }
+                Command::none()
+            }
+            Message::SelectConversation(id) => {
+                if self.is_sending || id == self.conversation.id {
+                    return Command::none();
+                }
+                
+                if self.select_conversation(&id) {
+                    Command::perform(async {}, |_| Message::ScrollToBottom)
+                } else {
+                    Command::none()
+                }
+            }
+            Message::EditMessage(index) => {
+                if self.is_sending {
+                    return Command::none();
+                }
+                match self.conversation.messages.get(index) {
+                    Some(message) if message.role == MessageRole::User => {
+                        self.message = message.content.clone();
+                        self.editing = Some(index);
+                    }
+                    _ => warn!("Message {} can't be edited", index),
+                }
+                Command::none()
+            }
+            Message::CancelEdit => {
+                if self.editing.take().is_some() {
+                    self.message.clear();
+                }
+                Command::none()
+            }
+            Message::NewConversation => {
+                if self.is_sending {
+                    return Command::none();
+                }
+                // The new conversation is saved with its first message, so it isn't listed until then
+                let conversation = Conversation::new("New Conversation", &self.config.ollama.default_model);
+                info!("Starting new conversation {}", conversation.id);
+                self.switch_to(conversation);
+                self.reload_conversations();
+                Command::none()
+            }
+            Message::RequestClearConversation => {
+                self.confirming_clear = true;
+                Command::none()
+            }
+            Message::CancelClearConversation => {
+                self.confirming_clear = false;
+                Command::none()
+            }
+            Message::ClearConversation => {
+                // Only the confirmation bar clears, so a stray message can't wipe the conversation
+                if !self.confirming_clear {
+                    return Command::none();
+                }
+                self.confirming_clear = false;
+                // Stop a response first, which keeps and saves what was received so far
+                let stop = if self.is_sending { self.update(Message::CancelStreaming) } else { Command::none() };
+                self.streaming_content.clear();
+                self.is_sending = false;
+                self.loading_state = None;
+                self.error = None;
+                
+                if self.config.conversation.clear_deletes_file {
+                    if let Err(e) = self.storage.delete_conversation(&self.conversation) {
+                        error!("Failed to delete conversation: {}", e);
+                        self.error = Some(format!("Failed to delete conversation: {}", e));
+                        return stop;
+                    }
+                    // The deleted conversation mustn't be saved again on the way out
+                    self.conversation_dirty.take();
+                }
+                let conversation = Conversation::new("New Conversation", &self.config.ollama.default_model);
+                info!("Cleared conversation {}, continuing in {}", self.conversation.id, conversation.id);
+                self.switch_to(conversation);
+                self.reload_conversations();
+                Command::batch([
+                    stop,
+                    scrollable::snap_to(
+                        scrollable::Id::new("conversation_messages"),
+                        scrollable::RelativeOffset::START,
+                    ),
+                ])
+            }
+            Message::RequestDeleteConversation(id) => {
+                self.confirming_delete = Some(id);
+                Command::none()
+            }
+            Message::CancelDeleteConversation => {
+                self.confirming_delete = None;
+                Command::none()
+            }
+            Message::DeleteConversation(id) => {
+                // Only the conversation the confirmation was shown for is deleted
+                if self.confirming_delete.as_ref() != Some(&id) {
+                    return Command::none();
+                }
+                self.confirming_delete = None;
+                let is_current = id == self.conversation.id;
+                if is_current && self.is_sending {
+                    return Command::none();
+                }
+                let conversation = if is_current {
+                    Some(&self.conversation)
+                } else {
+                    self.conversations.iter().find(|conversation| conversation.id == id)
+                };
+                if let Some(conversation) = conversation {
+                    if let Err(e) = self.storage.delete_conversation(conversation) {
+                        error!("Failed to delete conversation: {}", e);
+                        self.error = Some(format!("Failed to delete conversation: {}", e));
+                        return Command::none();
+                    }
+                }
+                self.conversations.retain(|conversation| conversation.id != id);
+                self.info_visible.remove(&id);
+                if !is_current {
+                    return Command::none();
+                }
+                
+                // Show the next most recent conversation, or a new one if that was the last;
+                // the deleted one mustn't be saved again on the way out
+                self.conversation_dirty.take();
+                let next = self.conversations.first().cloned().unwrap_or_else(|| {
+                    Conversation::new("New Conversation", &self.config.ollama.default_model)
+                });
+                self.switch_to(next);
+                Command::perform(async {}, |_| Message::ScrollToBottom)
+            }
+            Message::QuickSwitch(slot) => {
+                // Reload so conversations deleted since the list was read count as missing
+                self.reload_conversations();
+                let slots = &self.config.ui.quick_switch;
+                match Conversation::find_quick_switch(&self.conversations, slots, slot) {
+                    Some(conversation) => {
+                        let id = conversation.id.clone();
+                        self.update(Message::SelectConversation(id))
+                    }
+                    None => Command::none(),
+                }
+            }
+            Message::SelectTemplate(index) => {
+                // Values are kept so switching back and forth doesn't lose them
+                self.template = index.filter(|&index| index < self.config.templates.len());
+                Command::none()
+            }
+            Message::TemplateValueChanged(name, value) => {
+                self.template_values.insert(name, value);
+                Command::none()
+            }
+            Message::AttachImage(path) => {
+                // Screenshots can be large, so they are read without blocking the UI
+                Command::perform(
+                    async move {
+                        let result = crate::utils::image::read_image_file(&path).await;
+                        (path, result.map_err(|e| format!("{:#}", e)))
+                    },
+                    |(path, result)| Message::ImageRead(path, result),
+                )
+            }
+            Message::ImageRead(path, result) => {
+                match result {
+                    Ok(bytes) => {
+                        info!("Attached image {}", path.display());
+                        self.pending_images.push(PendingImage {
+                            data: crate::utils::image::encode_image(&bytes),
+                            thumbnail: iced::widget::image::Handle::from_memory(bytes),
+                        });
+                    }
+                    Err(e) => {
+                        warn!("Failed to attach image: {}", e);
+                        self.warning = Some(e);
+                    }
+                }
+                Command::none()
+            }
+            Message::RemovePendingImage(index) => {
+                if index < self.pending_images.len() {
+                    self.pending_images.remove(index);
+                }
+                Command::none()
+            }
+            Message::AssignQuickSwitch(slot) => {
+                // Assigning the slot the conversation already has clears it
+                let slots = &mut self.config.ui.quick_switch;
+                if slots.get(&slot) == Some(&self.conversation.id) {
+                    info!("Clearing quick switch {}", slot);
+                    slots.remove(&slot);
+                } else {
+                    info!("Assigning quick switch {} to conversation {}", slot, self.conversation.id);
+                    slots.insert(slot, self.conversation.id.clone());
+                }
+                self.config_dirty.mark();
+                Command::none()
+            }
+            Message::OpenSearchHit(id, index) => {
+                if id != self.conversation.id && (self.is_sending || !self.select_conversation(&id)) {
+                    return Command::none();
+                }
+                
+                // Scroll once the selected conversation has been laid out
+                Command::perform(async {}, move |_| Message::ScrollToMessage(index))
+            }
+            Message::ForkFromMessage(index) => {
+                if self.is_sending {
+                    return Command::none();
+                }
+                let Some(fork) = self.conversation.fork_from(index) else {
+                    return Command::none();
+                };
+                
+                // Save both so the original stays selectable alongside the fork
+                if let Err(e) = self.storage.save_conversation(&self.conversation).and_then(|_| self.storage.save_conversation(&fork)) {
+                    error!("Failed to fork conversation: {}", e);
+                    self.error = Some(format!("Failed to fork conversation: {}", e));
+                    return Command::none();
+                }
+                
+                info!("Forked conversation {} at message {}", self.conversation.id, index);
+                self.switch_to(fork);
+                self.reload_conversations();
+                self.sidebar_visible = true;
+                Command::perform(async {}, |_| Message::ScrollToBottom)
+            }
+            Message::CopyMessage(index) => {
+                let Some(message) = self.conversation.messages.get(index) else {
+                    return Command::none();
+                };
+                let content = extract_single_code_block(&message.content).unwrap_or_else(|| message.content.clone());
+                iced::clipboard::write(content)
+            }
+            Message::CopyToClipboard(content) => iced::clipboard::write(content),
+            // Copies what has been received so far if a response is streaming
+            Message::CopyConversation => iced::clipboard::write(self.conversation.to_plaintext()),
+            Message::ToggleMessageSelection(index) => {
+                if index < self.conversation.messages.len() && !self.selected_messages.remove(&index) {
+                    self.selected_messages.insert(index);
+                }
+                Command::none()
+            }
+            Message::ClearSelection => {
+                self.selected_messages.clear();
+                Command::none()
+            }
+            Message::CopySelection => {
+                match self.conversation.export_messages(&self.selected_indices(), ExportFormat::Markdown) {
+                    Ok(snippet) => iced::clipboard::write(snippet),
+                    Err(e) => {
+                        error!("Failed to copy selected messages: {}", e);
+                        Command::none()
+                    }
+                }
+            }
+            Message::SaveSelectionAsSnippet => {
+                let root = Conversation::get_export_dir();
+                match self.conversation.export_snippet(&self.selected_indices(), &root) {
+                    Ok(path) => {
+                        info!("Saved snippet to {}", path.display());
+                        self.selected_messages.clear();
+                    }
+                    Err(e) => {
+                        error!("Failed to save snippet: {}", e);
+                        self.error = Some(format!("Failed to save snippet: {}", e));
+                    }
+                }
+                Command::none()
+            }
+            Message::CopyAsCurl => {
+                let (Some((request, _)), Some(client)) = (&self.last_request, &self.ollama_client) else {
+                    return Command::none();
+                };
+                match client.to_curl(request, self.config.ollama.stream) {
+                    Ok(command) => {
+                        info!("Copied last request as curl command");
+                        iced::clipboard::write(command)
+                    }
+                    Err(e) => {
+                        error!("Failed to build curl command: {}", e);
+                        Command::none()
+                    }
+                }
+            }
+            Message::ContinueResponse => {
+                // Send the prompt as a regular message, keeping whatever was typed in the input
+                info!("Continuing a response that stopped at the token limit");
+                let draft = std::mem::replace(&mut self.message, CONTINUE_PROMPT.to_string());
+                let template = self.template.take();
+                let command = self.update(Message::SendMessage);
+                self.message = draft;
+                self.template = template;
+                command
+            }
+            Message::RegenerateMoreCreative => {
+                if self.is_sending {
+                    return Command::none();
+                }
+                let Some(last) = self
+                    .conversation
+                    .messages
+                    .last()
+                    .filter(|message| message.role == MessageRole::Assistant)
+                else {
+                    return Command::none();
+                };
+                
+                // Each regeneration is a little hotter than the response it replaces
+                let temperature = crate::ollama::request::bumped_temperature(
+                    last.temperature.unwrap_or(self.config.ollama.temperature),
+                    self.config.ollama.temperature_bump,
+                );
+                let previous = last.content.clone();
+                let prompt_len = self.conversation.messages.len() - 1;
+                
+                if self.config.dry_run {
+                    let request = self.build_request(&self.conversation.messages[..prompt_len], Some(temperature));
+                    self.show_dry_run(&request);
+                    return Command::none();
+                }
+                
+                info!("Regenerating the last response with temperature {}", temperature);
+                
+                // Keep the response being replaced so the new one can be compared with it
+                let history = self.response_history.entry(prompt_len).or_default();
+                history.push(previous);
+                if history.len() > MAX_RESPONSE_HISTORY {
+                    history.remove(0);
+                }
+                
+                self.conversation.messages.truncate(prompt_len);
+                self.expanded_details.remove(&prompt_len);
+                self.selected_messages.remove(&prompt_len);
+                self.conversation_dirty.mark();
+                self.start_response(Some(temperature))
+            }
+            Message::ShowDiff(index) => {
+                self.diff_shown = match self.diff_shown {
+                    Some(shown) if shown == index => None,
+                    _ if self.response_history.contains_key(&index) => Some(index),
+                    shown => shown,
+                };
+                Command::none()
+            }
+            Message::ReplayModelChanged(model) => {
+                self.replay_model = model;
+                Command::none()
+            }
+            Message::ReplayWith(model) => {
+                let model = model.trim();
+                if model.is_empty() || self.is_sending {
+                    return Command::none();
+                }
+                if self.ollama_client.is_none() {
+                    self.error = Some("Ollama API client not initialized. Please check your connection.".to_string());
+                    return Command::none();
+                }
+                
+                let replay = Replay::new(&self.conversation, model);
+                if replay.total() == 0 {
+                    return Command::none();
+                }
+                
+                info!("Replaying conversation {} with {}", self.conversation.id, model);
+                self.error = None;
+                self.is_sending = true;
+                self.replay = Some(replay);
+                self.replay_next_turn()
+            }
+            Message::ReplayTurnCompleted(result) => {
+                self.replay_handle = None;
+                let Some(replay) = &mut self.replay else {
+                    return Command::none();
+                };
+                match result {
+                    Ok(response) => {
+                        replay.finish_turn(&response.content, response.metadata);
+                        self.replay_next_turn()
+                    }
+                    Err(e) => {
+                        error!("Replay failed: {}", e);
+                        self.error = Some(format!("Replay failed: {}", e));
+                        self.replay = None;
+                        self.is_sending = false;
+                        self.loading_state = None;
+                        Command::none()
+                    }
+                }
+            }
+            Message::CancelReplay => {
+                // Drop the partial replay; the original conversation is untouched
+                if let Some(handle) = self.replay_handle.take() {
+                    handle.abort();
+                }
+                if self.replay.take().is_some() {
+                    info!("Replay cancelled");
+                }
+                self.is_sending = false;
+                self.loading_state = None;
+                Command::none()
+            }
+            Message::ReplayAborted => {
+                debug!("Replay turn aborted");
+                Command::none()
+            }
+            Message::DismissDryRunPreview => {
+                self.dry_run_preview = None;
+                Command::none()
+            }
+            Message::CycleThink => {
+                // Cycle the conversation's reasoning override: default, on, off
+                self.conversation.think = match self.conversation.think {
+                    None => Some(true),
+                    Some(true) => Some(false),
+                    Some(false) => None,
+                };
+                self.conversation_dirty.mark();
+                Command::none()
+            }
+            Message::SystemPromptChanged(prompt) => {
+                // Clearing the field removes the conversation's own prompt
+                self.conversation.system_prompt = (!prompt.is_empty()).then_some(prompt);
+                self.conversation_dirty.mark();
+                Command::none()
+            }
+            Message::CycleOpacity => {
+                // Step from the opacity currently shown, which the window tracks
+                self.config.window.opacity = self.window.opacity();
+                let opacity = self.config.window.next_opacity();
+                info!("Changing window opacity to {}", opacity);
+                self.window.set_opacity(opacity);
+                self.config.window.opacity = opacity;
+                self.config_dirty.mark();
+                Command::none()
+            }
+            Message::SetTheme(mode) => {
+                info!("Changing theme to {}", mode);
+                self.theme = resolve_theme(mode);
+                self.palette = Palette::from_scheme(&self.config.theme.colors, &self.theme);
+                self.config.theme.mode = mode;
+                self.config_dirty.mark();
+                Command::none()
+            }
+            Message::ToggleAlwaysOnTop => {
+                let level = self.window.toggle_always_on_top();
+                info!("Changing window level to {:?}", level);
+                if let Err(e) = self.window.save_to_config(&mut self.config, &self.storage) {
+                    error!("Failed to save window level: {}", e);
+                }
+                // Platforms without window levels, such as Wayland, leave the window as it is
+                iced_window::change_level(ui_window::level_for(level, self.focused))
+            }
+            Message::ToggleOutline => {
+                self.outline_visible = !self.outline_visible;
+                Command::none()
+            }
+            Message::ToggleIssues => {
+                self.issues_visible = !self.issues_visible;
+                Command::none()
+            }
+            Message::RetryFailedRequest(index) => {
+                if self.is_sending {
+                    return Command::none();
+                }
+                let Some(failed) = self.failed_requests.get(index) else {
+                    return Command::none();
+                };
+                info!("Retrying failed request to {}", failed.model);
+                self.message = failed.prompt.clone();
+                if let Err(e) = self.failed_requests.remove(index) {
+                    error!("Failed to save failed requests: {}", e);
+                }
+                self.error = None;
+                self.update(Message::SendMessage)
+            }
+            Message::ClearFailedRequests => {
+                if let Err(e) = self.failed_requests.clear() {
+                    error!("Failed to save failed requests: {}", e);
+                }
+                Command::none()
+            }
+            Message::ToggleConversationInfo => {
+                let id = &self.conversation.id;
+                if !self.info_visible.remove(id) {
+                    self.info_visible.insert(id.clone());
+                }
+                Command::none()
+            }
             Message::MouseMoved(position) => {
                 // Handle mouse move for window dragging
                 if let Some(msg) = self.window.handle_mouse_move(position) {
@@ -669,45 +2680,266 @@ impl Application for App {
             }
         }
     }
+}
+
+impl Application for App {
+    type Executor = executor::Default;
+    type Message = Message;
+    type Theme = Theme;
+    type Flags = crate::config::Config;
+
+    fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+        Self::with_storage(flags, Storage::detect())
+    }
+
+    fn title(&self) -> String {
+        String::from("ScreenSage")
+    }
+
+    fn theme(&self) -> Theme {
+        self.theme.clone()
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let command = self.handle_message(message);
+        // Decode images that arrived or came into view, so drawing doesn't decode them again
+        self.images.sync(&self.conversation);
+        command
+    }
 
     fn view(&self) -> Element<Message> {
         // Create a title bar using the UI module
-        let title_bar = ui_window::title_bar(&self.window);
+        let title_bar = ui_window::title_bar(
+            &self.window,
+            !self.config.conversation.auto_save,
+            self.config.theme.mode,
+        );
 
         // Create the presentation area for the conversation
         let presentation = crate::ui::presentation::presentation_area(
             &self.conversation,
-            &Theme::Dark, // Use the dark theme for now
+            &PresentationOptions {
+                can_speak: self.speaker.is_some(),
+                speaking: self.speaking,
+                palette: self.palette,
+                show_metadata: self.config.ui.show_response_metadata,
+                can_copy_request: self.last_request.is_some() && self.ollama_client.is_some(),
+                can_reorder: !self.is_sending,
+                can_regenerate: !self.is_sending && (self.ollama_client.is_some() || self.config.dry_run),
+                expanded_details: self.expanded_details.clone(),
+                selected: self.selected_messages.clone(),
+                loading: self.response_loading_text().map(str::to_string),
+                streaming: self.response_index.filter(|_| self.is_sending),
+                regenerated: self.response_history.keys().copied().collect(),
+                diff: self.response_diff(),
+                found: self.find_matches.iter().copied().collect(),
+                images: self.images.decoded(),
+            },
         );
 
         // Create the input area
         let input_area = crate::ui::input::input_area(
             &self.message,
             self.is_sending,
-            &Theme::Dark, // Use the dark theme for now
+            self.conversation.approx_token_count(),
+            self.context_warning(),
+            &self.theme(),
         );
 
-        // Create content with error or loading indicators
-        let content = if let Some(error) = &self.error {
+        // Show the selection actions above the conversation while messages are selected
+        let presentation: Element<Message> = if self.selected_messages.is_empty() {
+            presentation
+        } else {
             column![
+                crate::ui::presentation::selection_bar(self.selected_messages.len()),
                 presentation,
-                crate::ui::presentation::error_message(error, &Theme::Dark),
-                input_area,
             ]
-            .spacing(10)
-        } else if let Some(loading_message) = &self.loading_state {
+            .into()
+        };
+
+        // Ask for confirmation above the conversation before clearing it
+        let presentation: Element<Message> = if self.confirming_clear {
+            column![
+                crate::ui::presentation::clear_confirmation_bar(self.config.conversation.clear_deletes_file),
+                presentation,
+            ]
+            .into()
+        } else {
+            presentation
+        };
+
+        // Ask for confirmation above the conversation before deleting one from the sidebar
+        let deleting = self.confirming_delete.as_ref().map(|id| {
+            let conversation = self.conversations.iter().find(|conversation| &conversation.id == id);
+            let title = if *id == self.conversation.id {
+                self.conversation.title.as_str()
+            } else {
+                conversation.map_or("this conversation", |conversation| conversation.title.as_str())
+            };
+            (id, title)
+        });
+        let presentation: Element<Message> = match deleting {
+            Some((id, title)) => column![
+                crate::ui::presentation::delete_confirmation_bar(id, title),
+                presentation,
+            ]
+            .into(),
+            None => presentation,
+        };
+
+        // Show the find bar above the conversation while it is open
+        let presentation: Element<Message> = match &self.find_query {
+            Some(query) => column![
+                crate::ui::presentation::find_bar(query, self.find_matches.len(), self.find_cursor),
+                presentation,
+            ]
+            .into(),
+            None => presentation,
+        };
+
+        // Show the bookmark navigator above the conversation when there are bookmarks
+        let bookmark_count = self.conversation.bookmarks().len();
+        let presentation: Element<Message> = if bookmark_count > 0 {
+            column![
+                crate::ui::presentation::bookmark_navigator(bookmark_count),
+                presentation,
+            ]
+            .into()
+        } else {
+            presentation
+        };
+
+        // Show the outline above the conversation, rebuilt from the current messages on every render
+        let presentation: Element<Message> = if self.outline_visible {
+            column![
+                crate::ui::presentation::outline_panel(&self.conversation.outline()),
+                presentation,
+            ]
+            .into()
+        } else {
+            presentation
+        };
+
+        // Show the failed requests above the conversation when the Issues panel is open
+        let presentation: Element<Message> = if self.issues_visible {
             column![
+                crate::ui::presentation::issues_panel(
+                    self.failed_requests.entries(),
+                    !self.is_sending && self.ollama_client.is_some(),
+                    &self.palette,
+                ),
                 presentation,
-                crate::ui::presentation::loading_indicator(loading_message, &Theme::Dark),
-                input_area,
             ]
-            .spacing(10)
+            .into()
         } else {
+            presentation
+        };
+
+        // Show the info panel above the conversation when it is toggled on
+        let presentation: Element<Message> = if self.info_visible.contains(&self.conversation.id) {
             column![
+                crate::ui::presentation::conversation_info(
+                    &self.conversation,
+                    &self.replay_model,
+                    &self.config.ui.quick_switch,
+                ),
                 presentation,
-                input_area,
             ]
-            .spacing(10)
+            .into()
+        } else {
+            presentation
+        };
+
+        // The settings panel covers the conversation while it is open
+        let presentation: Element<Message> = match &self.settings {
+            Some(form) => crate::ui::settings::settings_panel(&self.config, form),
+            None => presentation,
+        };
+
+        // Create content with error or loading indicators
+        let mut content = Column::new().spacing(10).push(presentation);
+        if let Some(preview) = &self.dry_run_preview {
+            content = content.push(crate::ui::presentation::dry_run_preview(preview, &self.palette));
+        }
+        if let Some(warning) = &self.warning {
+            content = content.push(crate::ui::presentation::warning_message(warning, &self.palette));
+        }
+        if !self.storage.is_persistent() {
+            let warning = "Not saving to disk: the data or config directory isn't writable.";
+            content = content.push(crate::ui::presentation::warning_message(warning, &self.palette));
+        }
+        if self.input_truncated {
+            let warning = format!(
+                "Your input was cut off at {} characters (ui.max_input_chars).",
+                self.config.ui.max_input_chars
+            );
+            content = content.push(crate::ui::presentation::warning_message(&warning, &self.palette));
+        }
+        if let Some(error) = &self.error {
+            content = content.push(crate::ui::presentation::error_message(error, &self.palette));
+        } else if let Some(loading_message) = &self.loading_state {
+            // Responses show their progress inline in the conversation instead
+            if self.connect_handle.is_some() {
+                content = content.push(crate::ui::presentation::connecting_indicator(loading_message));
+            } else if self.replay.is_some() {
+                content = content.push(crate::ui::presentation::replay_indicator(loading_message));
+            }
+        }
+        if let Some(status) = &self.pull_status {
+            content = content.push(crate::ui::presentation::download_indicator(status));
+        }
+        // A client refusing requests because Ollama keeps failing counts as offline too
+        let circuit_open = self.ollama_client.as_ref().is_some_and(OllamaClient::circuit_open);
+        if (self.ollama_client.is_none() || circuit_open) && self.connect_handle.is_none() {
+            content = content.push(crate::ui::presentation::offline_indicator());
+        }
+        if self.editing.is_some() {
+            content = content.push(crate::ui::input::edit_bar());
+        }
+        if let Some(summary) = self.last_response_speed() {
+            content = content.push(crate::ui::input::status_line(&summary));
+        }
+        if !self.models.is_empty() {
+            content = content.push(crate::ui::input::model_picker(
+                self.model_choices(),
+                &self.config.ollama.default_model,
+            ));
+        }
+        if !self.pending_images.is_empty() {
+            let thumbnails: Vec<_> = self.pending_images.iter().map(|image| image.thumbnail.clone()).collect();
+            content = content.push(crate::ui::input::attachment_bar(
+                &thumbnails,
+                &self.config.ollama.default_model,
+            ));
+        }
+        if !self.config.templates.is_empty() {
+            content = content.push(crate::ui::input::template_bar(
+                &self.config.templates,
+                self.template,
+                &self.template_values,
+            ));
+        }
+        let content = content.push(input_area);
+        let content = match &self.log_viewer {
+            Some(viewer) => content.push(crate::ui::log_viewer::log_viewer(viewer)),
+            None => content,
+        };
+
+        // Show the conversation sidebar next to the content when it is open
+        let content: Element<Message> = if self.sidebar_visible {
+            iced::widget::row![
+                crate::ui::sidebar::sidebar(
+                    &self.conversations,
+                    &self.conversation.id,
+                    self.config.conversation.summary_preview_len,
+                    &self.search_query,
+                    &self.search_hits,
+                ),
+                content,
+            ]
+            .into()
+        } else {
+            content.into()
         };
 
         // Combine all elements into a content column
@@ -718,10 +2950,30 @@ impl Application for App {
         .spacing(0);
         
         // Create the container with styling
+        // Round the corners, shade the edge and fade the background as currently configured
+        let corner_radius = self.config.window.corner_radius;
+        let shadow = self.config.window.shadow;
+        let opacity = self.window.opacity();
         container(content_column)
             .width(Length::Fill)
             .height(Length::Fill)
-            .style(theme::Container::Box)
+            .style(move |theme: &Theme| {
+                let appearance = container::StyleSheet::appearance(theme, &theme::Container::Box);
+                let background = appearance.background.map(|background| match background {
+                    iced::Background::Color(color) => iced::Background::Color(iced::Color {
+                        a: color.a * opacity,
+                        ..color
+                    }),
+                    other => other,
+                });
+                container::Appearance {
+                    background,
+                    border_radius: corner_radius.into(),
+                    border_width: if shadow > 0.0 { WINDOW_SHADOW_WIDTH } else { 0.0 },
+                    border_color: iced::Color::from_rgba(0.0, 0.0, 0.0, shadow),
+                    ..appearance
+                }
+            })
         .into()
     }
 
@@ -731,9 +2983,33 @@ impl Application for App {
             crate::ui::input::keyboard_subscription(),
         ];
 
+        for timer in self.timers() {
+            let subscription = match timer {
+                // Periodically save unsaved changes
+                Timer::AutoSave => {
+                    let autosave_interval = self.config.conversation.autosave_interval_secs;
+                    iced::time::every(Duration::from_secs(autosave_interval)).map(|_| Message::AutoSave)
+                }
+                // Keep the log viewer following the log file while it is open
+                Timer::RefreshLogs => iced::time::every(LOG_REFRESH_INTERVAL).map(|_| Message::RefreshLogs),
+                // Animate a smooth scroll to the bottom frame by frame
+                Timer::SmoothScroll => {
+                    iced::time::every(SMOOTH_SCROLL_FRAME).map(|_| Message::SmoothScrollTick)
+                }
+            };
+            subscriptions.push(subscription);
+        }
+
+        // Download a missing default model, reporting progress until it finishes
+        if let (Some(model), Some(client)) = (&self.pulling, &self.ollama_client) {
+            let state = PullState::Starting(client.clone(), model.clone());
+            subscriptions.push(iced::subscription::unfold(("model_pull", model.clone()), state, pull_step));
+        }
+
         let state = self.channel_state.clone();
         
-        // Add a subscription for streaming chunks if we're streaming
+        // Add a subscription for streaming chunks if we're streaming, even while the window is hidden,
+        // since dropping it would drop the receiver and lose the rest of the response
         if self.is_streaming {
             let chunk_stream = iced::subscription::unfold(
                 "chunk_stream",
@@ -789,3 +3065,43 @@ impl Application for App {
         Subscription::batch(subscriptions)
     }
 }
+
+/// An image attached to the next message
+struct PendingImage {
+    /// Base64-encoded image, as sent with the message
+    data: String,
+    /// Image shown in the attachment bar
+    thumbnail: iced::widget::image::Handle,
+}
+
+/// Where the download of a missing model is up to
+enum PullState {
+    /// The download hasn't been requested yet
+    Starting(OllamaClient, String),
+    /// Progress is being streamed
+    Pulling(BoxStream<'static, anyhow::Result<PullProgress>>),
+    /// The download succeeded or failed, so there is nothing more to report
+    Finished,
+}
+
+/// Report the next step of a model download
+async fn pull_step(state: PullState) -> (Message, PullState) {
+    let mut stream = match state {
+        PullState::Starting(client, model) => match client.pull_model(&model).await {
+            Ok(stream) => stream.boxed(),
+            Err(e) => return (Message::ModelPullFinished(Err(e.to_string())), PullState::Finished),
+        },
+        PullState::Pulling(stream) => stream,
+        // The subscription is dropped once the result is handled
+        PullState::Finished => futures::future::pending().await,
+    };
+    match stream.next().await {
+        Some(Ok(progress)) if progress.is_success() => (Message::ModelPullFinished(Ok(())), PullState::Finished),
+        Some(Ok(progress)) => (Message::ModelPullProgress(progress), PullState::Pulling(stream)),
+        Some(Err(e)) => (Message::ModelPullFinished(Err(e.to_string())), PullState::Finished),
+        None => {
+            let error = "the download ended before it finished".to_string();
+            (Message::ModelPullFinished(Err(error)), PullState::Finished)
+        }
+    }
+}
@@ -26,7 +26,7 @@ pub fn load_config() -> Result<Config> {
     info!("Using config file: {}", config_path.display());
     
     // Load config from file or use default
-    let mut config = if config_path.exists() {
+    let config = if config_path.exists() {
         let content = fs::read_to_string(&config_path)
             .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
         
@@ -37,22 +37,10 @@ pub fn load_config() -> Result<Config> {
         Config::default()
     };
     
-    // Override with command line arguments
-    if let Some(model) = args.model {
-        config.ollama.default_model = model;
-    }
-    
-    if let Some(api_url) = args.api_url {
-        config.ollama.api_url = api_url;
-    }
-    
-    if let Some(opacity) = args.opacity {
-        config.window.opacity = opacity;
-    }
-    
-    if let Some(log_level) = args.log_level {
-        config.logging.level = log_level;
-    }
+    // Override with command line arguments, for this run only
+    let mut config = CliOverrides::from(&args).apply(config);
+    config.dry_run = args.dry_run;
+    config.open = args.open;
     
     // Validate configuration
     config.validate()?;
@@ -71,7 +59,8 @@ pub fn save_config(config: &Config, path: Option<PathBuf>) -> Result<()> {
     }
     
     // Serialize and save config
-    let content = toml::to_string(config)
+    // Command-line overrides apply to a single run, so the file keeps its own values
+    let content = toml::to_string(&config.to_saved())
         .context("Failed to serialize config")?;
     
     fs::write(&config_path, content)
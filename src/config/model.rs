@@ -1,7 +1,13 @@
 use anyhow::{bail, Result};
 use clap::Parser;
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// Largest window corner radius in pixels
+pub const MAX_CORNER_RADIUS: f32 = 40.0;
 
 /// Command line arguments
 #[derive(Parser, Debug)]
@@ -26,6 +32,163 @@ pub struct CliArgs {
     /// Log level (error, warn, info, debug, trace)
     #[clap(long)]
     pub log_level: Option<String>,
+    
+    /// Show the request that would be sent instead of sending it
+    #[clap(long)]
+    pub dry_run: bool,
+    
+    /// Wait for each response to complete instead of streaming it
+    #[clap(long)]
+    pub no_stream: bool,
+    
+    /// Seed for reproducible responses
+    #[clap(long)]
+    pub seed: Option<u64>,
+    
+    /// Size of the model's context window in tokens
+    #[clap(long)]
+    pub num_ctx: Option<u32>,
+    
+    /// Sequence that ends the response when generated (repeat for more)
+    #[clap(long = "stop", value_name = "SEQUENCE")]
+    pub stop: Vec<String>,
+    
+    /// Presence penalty (-2.0-2.0)
+    #[clap(long, allow_hyphen_values = true)]
+    pub presence_penalty: Option<f32>,
+    
+    /// Frequency penalty (-2.0-2.0)
+    #[clap(long, allow_hyphen_values = true)]
+    pub frequency_penalty: Option<f32>,
+    
+    /// Seconds to wait for a response that isn't streamed, 0 for no limit
+    #[clap(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+    
+    /// Open the conversation with this ID or title instead of the most recent one
+    #[clap(long, value_name = "ID_OR_TITLE")]
+    pub open: Option<String>,
+}
+
+/// Settings given on the command line, which apply to a single run and are never saved
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliOverrides {
+    /// `--model`, replacing `ollama.default_model`
+    pub model: Option<String>,
+    /// `--api-url`, replacing `ollama.api_url`
+    pub api_url: Option<String>,
+    /// `--opacity`, replacing `window.opacity`
+    pub opacity: Option<f32>,
+    /// `--log-level`, replacing `logging.level`
+    pub log_level: Option<String>,
+    /// `--no-stream`, turning off `ollama.stream`
+    pub no_stream: bool,
+    /// `--seed`, replacing `ollama.seed`
+    pub seed: Option<u64>,
+    /// `--num-ctx`, replacing `ollama.num_ctx`
+    pub num_ctx: Option<u32>,
+    /// `--stop`, replacing `ollama.stop` when given at least once
+    pub stop: Vec<String>,
+    /// `--presence-penalty`, replacing `ollama.presence_penalty`
+    pub presence_penalty: Option<f32>,
+    /// `--frequency-penalty`, replacing `ollama.frequency_penalty`
+    pub frequency_penalty: Option<f32>,
+    /// `--timeout`, replacing `ollama.request_timeout_secs`
+    pub timeout: Option<u64>,
+}
+
+impl From<&CliArgs> for CliOverrides {
+    fn from(args: &CliArgs) -> Self {
+        Self {
+            model: args.model.clone(),
+            api_url: args.api_url.clone(),
+            opacity: args.opacity,
+            log_level: args.log_level.clone(),
+            no_stream: args.no_stream,
+            seed: args.seed,
+            num_ctx: args.num_ctx,
+            stop: args.stop.clone(),
+            presence_penalty: args.presence_penalty,
+            frequency_penalty: args.frequency_penalty,
+            timeout: args.timeout,
+        }
+    }
+}
+
+/// Command-line overrides in effect, with the configuration they were applied on top of
+#[derive(Debug, Clone)]
+pub struct AppliedOverrides {
+    /// The overrides from the command line
+    pub overrides: CliOverrides,
+    /// The configuration as read from the file
+    pub file: Config,
+}
+
+/// Put `value` in `field`, if there is one
+fn set_override<T>(field: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *field = value;
+    }
+}
+
+/// Put back the file's value of a setting that still holds the override `value`
+fn restore_override<T: PartialEq + Clone>(field: &mut T, file_value: &T, value: Option<T>) {
+    if value.is_some_and(|value| *field == value) {
+        *field = file_value.clone();
+    }
+}
+
+impl CliOverrides {
+    /// Apply the overrides on top of `config`, remembering its values so they are the ones saved
+    pub fn apply(self, mut config: Config) -> Config {
+        let file = config.clone();
+        set_override(&mut config.ollama.default_model, self.model.clone());
+        set_override(&mut config.ollama.api_url, self.api_url.clone());
+        set_override(&mut config.window.opacity, self.opacity);
+        set_override(&mut config.logging.level, self.log_level.clone());
+        set_override(&mut config.ollama.stream, self.no_stream.then_some(false));
+        set_override(&mut config.ollama.seed, self.seed.map(Some));
+        set_override(&mut config.ollama.num_ctx, self.num_ctx.map(Some));
+        set_override(&mut config.ollama.stop, (!self.stop.is_empty()).then(|| self.stop.clone()));
+        set_override(&mut config.ollama.presence_penalty, self.presence_penalty);
+        set_override(&mut config.ollama.frequency_penalty, self.frequency_penalty);
+        set_override(&mut config.ollama.request_timeout_secs, self.timeout);
+        config.overrides = Some(Box::new(AppliedOverrides { overrides: self, file }));
+        config
+    }
+
+    /// Put back the file's values of the settings in `config` that still hold their override
+    ///
+    /// Settings changed since, like a model picked in the UI, keep their new value.
+    fn restore(&self, config: &mut Config, file: &Config) {
+        restore_override(&mut config.ollama.default_model, &file.ollama.default_model, self.model.clone());
+        restore_override(&mut config.ollama.api_url, &file.ollama.api_url, self.api_url.clone());
+        restore_override(&mut config.window.opacity, &file.window.opacity, self.opacity);
+        restore_override(&mut config.logging.level, &file.logging.level, self.log_level.clone());
+        restore_override(&mut config.ollama.stream, &file.ollama.stream, self.no_stream.then_some(false));
+        restore_override(&mut config.ollama.seed, &file.ollama.seed, self.seed.map(Some));
+        restore_override(&mut config.ollama.num_ctx, &file.ollama.num_ctx, self.num_ctx.map(Some));
+        restore_override(
+            &mut config.ollama.stop,
+            &file.ollama.stop,
+            (!self.stop.is_empty()).then(|| self.stop.clone()),
+        );
+        restore_override(
+            &mut config.ollama.presence_penalty,
+            &file.ollama.presence_penalty,
+            self.presence_penalty,
+        );
+        restore_override(
+            &mut config.ollama.frequency_penalty,
+            &file.ollama.frequency_penalty,
+            self.frequency_penalty,
+        );
+        restore_override(
+            &mut config.ollama.request_timeout_secs,
+            &file.ollama.request_timeout_secs,
+            self.timeout,
+        );
+    }
 }
 
 /// Application configuration
@@ -40,6 +203,30 @@ pub struct Config {
     pub conversation: ConversationConfig,
     /// Logging configuration
     pub logging: LoggingConfig,
+    /// User interface configuration
+    #[serde(default)]
+    pub ui: UiConfig,
+    /// Theme configuration
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Privacy configuration
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    /// Shell hooks configuration
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Prompt templates that can wrap the typed message before it is sent
+    #[serde(default)]
+    pub templates: Vec<Template>,
+    /// Whether to preview requests as JSON instead of sending them, from the command line
+    #[serde(skip)]
+    pub dry_run: bool,
+    /// ID or title of the conversation to open at startup, from the command line
+    #[serde(skip)]
+    pub open: Option<String>,
+    /// Command-line overrides applied to this configuration, which aren't saved
+    #[serde(skip)]
+    pub overrides: Option<Box<AppliedOverrides>>,
 }
 
 /// Window configuration
@@ -51,12 +238,69 @@ pub struct WindowConfig {
     pub height: u32,
     /// Window opacity
     pub opacity: f32,
-    /// Window always on top
+    /// Whether to keep the window above other windows, unless `level` is floating
     pub always_on_top: bool,
     /// Window position X coordinate
     pub position_x: Option<i32>,
     /// Window position Y coordinate
     pub position_y: Option<i32>,
+    /// Stacking level of the window relative to other apps
+    #[serde(default)]
+    pub level: WindowLevel,
+    /// Radius of the window corners in pixels
+    #[serde(default = "default_corner_radius")]
+    pub corner_radius: f32,
+    /// Strength of the shadow drawn along the window edge (0.0 = none, 1.0 = darkest)
+    #[serde(default = "default_shadow")]
+    pub shadow: f32,
+    /// Opacities stepped through by the cycle opacity shortcut, in order
+    #[serde(default = "default_opacity_presets")]
+    pub opacity_presets: Vec<f32>,
+}
+
+/// Stacking level of the window relative to other apps
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowLevel {
+    /// Stacked like any other window
+    #[default]
+    Normal,
+    /// Kept above other windows
+    AlwaysOnTop,
+    /// Kept above other windows only while ScreenSage is frontmost
+    FloatingWhenFocused,
+}
+
+impl WindowLevel {
+    /// Get the level to use given `window.always_on_top`, which raises a normal window above others
+    ///
+    /// A floating level is more specific, so it is kept as it is.
+    pub fn with_always_on_top(self, always_on_top: bool) -> Self {
+        match self {
+            WindowLevel::Normal if always_on_top => WindowLevel::AlwaysOnTop,
+            level => level,
+        }
+    }
+}
+
+/// API a chat server speaks
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApiStyle {
+    /// Ollama's `/api/chat`, streamed as newline-delimited JSON
+    #[default]
+    Ollama,
+    /// OpenAI-compatible `/v1/chat/completions`, streamed as Server-Sent Events
+    #[serde(rename = "OpenAI")]
+    OpenAi,
+}
+
+/// Endpoint requested to check that the API is reachable
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProbeEndpoint {
+    /// `/api/tags`, which lists every installed model
+    #[default]
+    Models,
+    /// `/api/version`, which stays fast however many models are installed
+    Version,
 }
 
 /// Ollama API configuration
@@ -64,11 +308,20 @@ pub struct WindowConfig {
 pub struct OllamaConfig {
     /// API base URL
     pub api_url: String,
+    /// API used for chat requests
+    #[serde(default)]
+    pub api_style: ApiStyle,
+    /// Bearer token sent to servers behind an authenticating proxy; `OLLAMA_API_KEY` takes precedence
+    #[serde(default)]
+    pub api_key: Option<String>,
     /// Default model
     pub default_model: String,
     /// Temperature for sampling (higher = more random)
     #[serde(default = "default_temperature")]
     pub temperature: f32,
+    /// Amount the temperature is raised by when regenerating a response to be more creative
+    #[serde(default = "default_temperature_bump")]
+    pub temperature_bump: f32,
     /// Top-p sampling (nucleus sampling)
     #[serde(default = "default_top_p")]
     pub top_p: f32,
@@ -78,15 +331,119 @@ pub struct OllamaConfig {
     /// Maximum tokens to generate
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
+    /// Whether to stream responses as they are generated rather than wait for them to complete
+    #[serde(default = "default_stream")]
+    pub stream: bool,
+    /// Seconds to wait for a response that isn't streamed, 0 for no limit
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Seconds to wait for a connection to the API, 0 for no limit
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Longest `Retry-After` delay in seconds waited for before retrying a rate limited request
+    #[serde(default = "default_max_retry_after_secs")]
+    pub max_retry_after_secs: u64,
+    /// Timeout for the startup connection probe in seconds
+    #[serde(default = "default_probe_timeout_secs")]
+    pub probe_timeout_secs: u64,
+    /// Endpoint requested by the startup connection probe
+    #[serde(default)]
+    pub probe_endpoint: ProbeEndpoint,
+    /// Whether to load the default model once connected, so the first response starts sooner
+    #[serde(default)]
+    pub prewarm: bool,
+    /// Seconds to wait for the first content of a response, which includes loading the model
+    #[serde(default = "default_first_token_timeout_secs")]
+    pub first_token_timeout_secs: u64,
+    /// Seconds a response may go without a new chunk once content is streaming
+    #[serde(default = "default_stream_idle_timeout_secs")]
+    pub stream_idle_timeout_secs: u64,
+    /// Maximum number of requests to the API in flight at once
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Failed requests within the window after which requests fail fast for a while, 0 to never
+    #[serde(default = "default_circuit_breaker_failures")]
+    pub circuit_breaker_failures: usize,
+    /// Seconds over which failed requests are counted
+    #[serde(default = "default_circuit_breaker_window_secs")]
+    pub circuit_breaker_window_secs: u64,
+    /// Seconds requests fail fast for before one is let through to test whether the API has recovered
+    #[serde(default = "default_circuit_breaker_cooldown_secs")]
+    pub circuit_breaker_cooldown_secs: u64,
+    /// Pattern that stops the response client-side once the streamed content matches it
+    #[serde(default)]
+    pub client_stop_regex: Option<String>,
+    /// Whether reasoning models should think before answering (unset = model default)
+    #[serde(default)]
+    pub think: Option<bool>,
+    /// Seed that makes responses reproducible for the same prompt (unset = random)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Whether to ask for responses that are a single JSON object
+    #[serde(default)]
+    pub json_mode: bool,
+    /// Whether to continue conversations from the context Ollama's generate endpoint returns,
+    /// instead of sending the whole conversation with each message
+    #[serde(default)]
+    pub reuse_context: bool,
+    /// Size of the model's context window in tokens (unset = model default)
+    #[serde(default)]
+    pub num_ctx: Option<u32>,
+    /// Sequences that end the response when the model generates them
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// Penalty for tokens that have appeared at all, encouraging new topics (0.0 = not sent)
+    #[serde(default)]
+    pub presence_penalty: f32,
+    /// Penalty for tokens by how often they have appeared, discouraging repetition (0.0 = not sent)
+    #[serde(default)]
+    pub frequency_penalty: f32,
+    /// Base system prompt sent before any conversation-specific one
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Text prepended to user messages in outgoing requests
+    #[serde(default)]
+    pub user_prefix: String,
+    /// Text appended to user messages in outgoing requests
+    #[serde(default)]
+    pub user_suffix: String,
+    /// Text prepended to assistant messages in outgoing requests
+    #[serde(default)]
+    pub assistant_prefix: String,
+    /// Estimated tokens in a conversation above which the context indicator is highlighted, 0 to never
+    #[serde(default = "default_context_warning_tokens")]
+    pub context_warning_tokens: usize,
+    /// Extra HTTP headers sent with every request, such as `X-Tenant-Id` for a gateway
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
 }
 
 /// Conversation configuration
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConversationConfig {
-    /// Maximum conversation length in characters
-    pub max_length: usize,
+    /// Maximum characters of message content kept in a conversation before the oldest messages are dropped
+    #[serde(alias = "max_length")]
+    pub max_chars: usize,
     /// Whether to save conversations automatically
     pub auto_save: bool,
+    /// Maximum messages per conversation before it is archived and continued (0 = unlimited)
+    #[serde(default)]
+    pub max_messages: usize,
+    /// Number of recent conversations kept on startup before older ones are moved to the trash (0 = unlimited)
+    #[serde(default)]
+    pub max_conversations: usize,
+    /// Maximum characters of the last message shown in conversation summaries
+    #[serde(default = "default_summary_preview_len")]
+    pub summary_preview_len: usize,
+    /// Seconds between auto-saves of unsaved changes (0 = disabled)
+    #[serde(default = "default_autosave_interval_secs")]
+    pub autosave_interval_secs: u64,
+    /// Plain-text file that streamed responses are also appended to
+    #[serde(default)]
+    pub tee_transcript: Option<PathBuf>,
+    /// Whether clearing the conversation also deletes its saved file, instead of keeping it in the sidebar
+    #[serde(default)]
+    pub clear_deletes_file: bool,
 }
 
 /// Logging configuration
@@ -100,8 +457,170 @@ pub struct LoggingConfig {
     pub max_file_size: u32,
     /// Number of log files to keep
     pub max_files: u32,
+    /// Whether to write each API request/response turn to api-trace.jsonl
+    #[serde(default)]
+    pub trace_api: bool,
+    /// Whether to keep the failed requests listed in the Issues panel across restarts
+    #[serde(default)]
+    pub persist_failed_requests: bool,
 }
 
+/// User interface configuration
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UiConfig {
+    /// Whether to offer reading responses aloud with the OS text-to-speech
+    #[serde(default)]
+    pub text_to_speech: bool,
+    /// Whether to offer a details disclosure with the API statistics of each response
+    #[serde(default)]
+    pub show_response_metadata: bool,
+    /// Maximum number of characters accepted in the input, 0 for no limit
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize,
+    /// Text shown while connecting and while waiting for a response
+    #[serde(default)]
+    pub loading_text: LoadingText,
+    /// Whether to animate scrolling to the bottom of the conversation instead of jumping
+    #[serde(default)]
+    pub smooth_scroll: bool,
+    /// IDs of the conversations opened with Cmd+1 to Cmd+9, by number
+    #[serde(default, with = "quick_switch_slots")]
+    pub quick_switch: BTreeMap<u8, String>,
+}
+
+/// Reads and writes quick-switch slots, which TOML table keys can only hold as strings
+mod quick_switch_slots {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(slots: &BTreeMap<u8, String>, serializer: S) -> Result<S::Ok, S::Error> {
+        let keyed: BTreeMap<String, &String> = slots.iter().map(|(slot, id)| (slot.to_string(), id)).collect();
+        keyed.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<u8, String>, D::Error> {
+        BTreeMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(slot, id)| match slot.parse() {
+                Ok(slot) => Ok((slot, id)),
+                Err(_) => Err(D::Error::custom(format!("Invalid quick switch slot: {}", slot))),
+            })
+            .collect()
+    }
+}
+
+/// Text of the in-progress indicators
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoadingText {
+    /// Shown while connecting to the Ollama API
+    pub connecting: String,
+    /// Shown in the conversation while a response is being generated
+    pub generating: String,
+}
+
+/// Privacy configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PrivacyConfig {
+    /// Patterns replaced with `[REDACTED]` in outgoing messages before they are sent or stored
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+}
+
+/// Shell hooks configuration
+///
+/// Hooks run through `sh -c` with the user's own permissions and aren't sandboxed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HooksConfig {
+    /// Command that receives each completed response on stdin and prints the text to show instead
+    #[serde(default)]
+    pub on_response: Option<String>,
+    /// Seconds a hook may run before it is killed and the original text shown
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// A prompt template, such as "Translate to {lang}: {input}"
+///
+/// `{input}` is replaced with the typed message and other `{name}` placeholders
+/// with values entered when the template is selected. `{{` and `}}` are literal braces.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Template {
+    /// Name shown in the template picker
+    pub name: String,
+    /// Text sent in place of the typed message
+    pub body: String,
+}
+
+/// Theme configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ThemeConfig {
+    /// Whether the UI is light, dark or follows the OS appearance
+    #[serde(default)]
+    pub mode: ThemeMode,
+    /// Colors used for the conversation
+    #[serde(default)]
+    pub colors: ColorScheme,
+}
+
+/// Light or dark appearance of the UI
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Dark text on light backgrounds
+    #[default]
+    Light,
+    /// Light text on dark backgrounds
+    Dark,
+    /// Follow the OS appearance, on macOS; light elsewhere
+    System,
+}
+
+impl ThemeMode {
+    /// Get the mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        match self {
+            ThemeMode::Light => ThemeMode::Dark,
+            ThemeMode::Dark => ThemeMode::System,
+            ThemeMode::System => ThemeMode::Light,
+        }
+    }
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ThemeMode::Light => "Light",
+            ThemeMode::Dark => "Dark",
+            ThemeMode::System => "System",
+        })
+    }
+}
+
+/// Conversation colors as `#RRGGBB` or `#RRGGBBAA` hex strings
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ColorScheme {
+    /// Background of the conversation area
+    pub background: String,
+    /// Background of user message bubbles
+    pub user_background: String,
+    /// Text color of user message bubbles
+    pub user_text: String,
+    /// Background of assistant message bubbles
+    pub assistant_background: String,
+    /// Text color of assistant message bubbles
+    pub assistant_text: String,
+    /// Background of error message bubbles
+    pub error_background: String,
+    /// Text color of error message bubbles
+    pub error_text: String,
+    /// Background of warning message bubbles
+    pub warning_background: String,
+    /// Text color of warning message bubbles
+    pub warning_text: String,
+}
 
 impl Default for WindowConfig {
     fn default() -> Self {
@@ -112,28 +631,131 @@ impl Default for WindowConfig {
             always_on_top: true,
             position_x: None,
             position_y: None,
+            level: WindowLevel::default(),
+            corner_radius: default_corner_radius(),
+            shadow: default_shadow(),
+            opacity_presets: default_opacity_presets(),
         }
     }
 }
 
+impl WindowConfig {
+    /// Get the preset after the current opacity, wrapping around at the end
+    ///
+    /// An opacity that isn't a preset moves to the first preset above it.
+    pub fn next_opacity(&self) -> f32 {
+        let presets = &self.opacity_presets;
+        let Some(&first) = presets.first() else {
+            return self.opacity;
+        };
+        let next = match presets.iter().position(|preset| (preset - self.opacity).abs() < 0.005) {
+            Some(index) => presets.get(index + 1).copied(),
+            None => presets.iter().copied().find(|&preset| preset > self.opacity),
+        };
+        next.unwrap_or(first)
+    }
+}
+
+/// Check whether `name` is a valid HTTP header name, a token of letters, digits and ``!#$%&'*+-.^_`|~``
+pub fn is_valid_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// Environment variable holding the API key, which takes precedence over `ollama.api_key`
+pub const API_KEY_ENV: &str = "OLLAMA_API_KEY";
+
+impl OllamaConfig {
+    /// Get the API key to authenticate with, from `OLLAMA_API_KEY` if it is set or else the configuration
+    pub fn resolved_api_key(&self) -> Option<String> {
+        std::env::var(API_KEY_ENV)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .or_else(|| self.api_key.clone().filter(|key| !key.is_empty()))
+    }
+
+    /// Get the timeout for responses that aren't streamed, `None` if there is no limit
+    pub fn request_timeout(&self) -> Option<Duration> {
+        (self.request_timeout_secs > 0).then(|| Duration::from_secs(self.request_timeout_secs))
+    }
+
+    /// Get the timeout for connecting to the API, `None` if there is no limit
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        (self.connect_timeout_secs > 0).then(|| Duration::from_secs(self.connect_timeout_secs))
+    }
+}
+
 impl Default for OllamaConfig {
     fn default() -> Self {
         Self {
             api_url: "http://localhost:11434".to_string(),
+            api_style: ApiStyle::default(),
+            api_key: None,
             default_model: "llama3.2".to_string(),
             temperature: default_temperature(),
+            temperature_bump: default_temperature_bump(),
             top_p: default_top_p(),
             top_k: default_top_k(),
             max_tokens: default_max_tokens(),
+            stream: default_stream(),
+            request_timeout_secs: default_request_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            max_retry_after_secs: default_max_retry_after_secs(),
+            probe_timeout_secs: default_probe_timeout_secs(),
+            probe_endpoint: ProbeEndpoint::default(),
+            prewarm: false,
+            first_token_timeout_secs: default_first_token_timeout_secs(),
+            stream_idle_timeout_secs: default_stream_idle_timeout_secs(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            circuit_breaker_failures: default_circuit_breaker_failures(),
+            circuit_breaker_window_secs: default_circuit_breaker_window_secs(),
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown_secs(),
+            client_stop_regex: None,
+            think: None,
+            seed: None,
+            json_mode: false,
+            reuse_context: false,
+            num_ctx: None,
+            stop: Vec::new(),
+            presence_penalty: 0.0,
+            frequency_penalty: 0.0,
+            system_prompt: None,
+            user_prefix: String::new(),
+            user_suffix: String::new(),
+            assistant_prefix: String::new(),
+            context_warning_tokens: default_context_warning_tokens(),
+            headers: BTreeMap::new(),
         }
     }
 }
 
+/// Default window corner radius in pixels
+fn default_corner_radius() -> f32 {
+    12.0
+}
+
+/// Default window edge shadow strength
+fn default_shadow() -> f32 {
+    0.3
+}
+
+/// Default opacities stepped through by the cycle opacity shortcut
+fn default_opacity_presets() -> Vec<f32> {
+    vec![0.3, 0.6, 0.9, 1.0]
+}
+
 /// Default temperature value
 fn default_temperature() -> f32 {
     0.7
 }
 
+/// Default temperature increase for more creative regenerations
+fn default_temperature_bump() -> f32 {
+    0.3
+}
+
 /// Default top-p value
 fn default_top_p() -> f32 {
     0.9
@@ -149,11 +771,131 @@ fn default_max_tokens() -> u32 {
     2048
 }
 
+/// Default context warning threshold, three quarters of a 4096-token context
+fn default_context_warning_tokens() -> usize {
+    3072
+}
+
+/// Responses are streamed by default
+fn default_stream() -> bool {
+    true
+}
+
+/// Default timeout for responses that aren't streamed in seconds
+fn default_request_timeout_secs() -> u64 {
+    120
+}
+
+/// Default connect timeout in seconds
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+/// Default longest `Retry-After` delay honored in seconds
+fn default_max_retry_after_secs() -> u64 {
+    30
+}
+
+/// Default connection probe timeout in seconds
+fn default_probe_timeout_secs() -> u64 {
+    5
+}
+
+/// Default first-token timeout in seconds
+fn default_first_token_timeout_secs() -> u64 {
+    120
+}
+
+/// Default stream idle timeout in seconds
+fn default_stream_idle_timeout_secs() -> u64 {
+    30
+}
+
+/// Default number of concurrent API requests
+fn default_max_concurrent_requests() -> usize {
+    2
+}
+
+/// Default number of failed requests that trips the circuit breaker
+fn default_circuit_breaker_failures() -> usize {
+    3
+}
+
+/// Default period over which failed requests are counted in seconds
+fn default_circuit_breaker_window_secs() -> u64 {
+    60
+}
+
+/// Default time requests fail fast for once the circuit breaker trips in seconds
+fn default_circuit_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+/// Default summary preview length in characters
+fn default_summary_preview_len() -> usize {
+    50
+}
+
+/// Default auto-save interval in seconds
+fn default_autosave_interval_secs() -> u64 {
+    30
+}
+
+/// Numbers that conversations can be assigned to for quick switching with Cmd+number
+pub const QUICK_SWITCH_SLOTS: std::ops::RangeInclusive<u8> = 1..=9;
+
+/// Default time limit for hooks in seconds
+fn default_hook_timeout_secs() -> u64 {
+    5
+}
+
+/// Default maximum input length in characters
+fn default_max_input_chars() -> usize {
+    100_000
+}
+
 impl Default for ConversationConfig {
     fn default() -> Self {
         Self {
-            max_length: 10000,
+            max_chars: 10000,
             auto_save: true,
+            max_messages: 0,
+            max_conversations: 0,
+            summary_preview_len: default_summary_preview_len(),
+            autosave_interval_secs: default_autosave_interval_secs(),
+            tee_transcript: None,
+            clear_deletes_file: false,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            text_to_speech: false,
+            show_response_metadata: false,
+            max_input_chars: default_max_input_chars(),
+            loading_text: LoadingText::default(),
+            smooth_scroll: false,
+            quick_switch: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            on_response: None,
+            timeout_secs: default_hook_timeout_secs(),
+        }
+    }
+}
+
+impl Default for LoadingText {
+    fn default() -> Self {
+        Self {
+            connecting: "Connecting to Ollama API...".to_string(),
+            generating: "Waiting for response...".to_string(),
         }
     }
 }
@@ -165,11 +907,55 @@ impl Default for LoggingConfig {
             log_to_file: true,
             max_file_size: 10,
             max_files: 5,
+            trace_api: false,
+            persist_failed_requests: false,
+        }
+    }
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            background: "#F2F2F2".to_string(),
+            user_background: "#0066CC".to_string(),
+            user_text: "#FFFFFF".to_string(),
+            assistant_background: "#E6E6E6".to_string(),
+            assistant_text: "#1A1A1A".to_string(),
+            error_background: "#CC0000".to_string(),
+            error_text: "#FFFFFF".to_string(),
+            warning_background: "#F2C94C".to_string(),
+            warning_text: "#1A1A1A".to_string(),
+        }
+    }
+}
+
+impl ColorScheme {
+    /// Default colors for the dark theme
+    pub fn dark() -> Self {
+        Self {
+            background: "#1E1E1E".to_string(),
+            user_background: "#0A5AA8".to_string(),
+            user_text: "#FFFFFF".to_string(),
+            assistant_background: "#2D2D2D".to_string(),
+            assistant_text: "#E6E6E6".to_string(),
+            error_background: "#A32020".to_string(),
+            error_text: "#FFFFFF".to_string(),
+            warning_background: "#8A6D1F".to_string(),
+            warning_text: "#FFFFFF".to_string(),
         }
     }
 }
 
 impl Config {
+    /// Get the configuration to write to the file, without the command-line overrides still in effect
+    pub fn to_saved(&self) -> Config {
+        let mut saved = self.clone();
+        if let Some(applied) = saved.overrides.take() {
+            applied.overrides.restore(&mut saved, &applied.file);
+        }
+        saved
+    }
+
     /// Validate configuration values
     pub fn validate(&self) -> Result<()> {
         // Validate window opacity
@@ -177,6 +963,23 @@ impl Config {
             bail!("Window opacity must be between 0.0 and 1.0");
         }
         
+        // Validate window framing
+        if !(0.0..=MAX_CORNER_RADIUS).contains(&self.window.corner_radius) {
+            bail!("Window corner radius must be between 0 and {} pixels", MAX_CORNER_RADIUS);
+        }
+        if !(0.0..=1.0).contains(&self.window.shadow) {
+            bail!("Window shadow must be between 0.0 and 1.0");
+        }
+        
+        // Validate opacity presets, which must leave the window visible
+        if self.window.opacity_presets.is_empty() {
+            bail!("At least one window opacity preset is required");
+        }
+        let invalid_preset = self.window.opacity_presets.iter().find(|&&preset| !(preset > 0.0 && preset <= 1.0));
+        if let Some(preset) = invalid_preset {
+            bail!("Window opacity presets must be greater than 0.0 and at most 1.0, got {}", preset);
+        }
+        
         // Validate window dimensions
         if self.window.width < 200 || self.window.height < 200 {
             bail!("Window dimensions must be at least 200x200");
@@ -189,13 +992,133 @@ impl Config {
         }
         
         // Validate max conversation length
-        if self.conversation.max_length < 1000 {
+        if self.conversation.max_chars < 1000 {
             bail!("Maximum conversation length must be at least 1000 characters");
         }
         
+        // Validate summary preview length
+        if self.conversation.summary_preview_len < 10 {
+            bail!("Summary preview length must be at least 10 characters");
+        }
+        
         // Validate Ollama API URL
-        if !self.ollama.api_url.starts_with("http://") && !self.ollama.api_url.starts_with("https://") {
-            bail!("Ollama API URL must start with http:// or https://");
+        if let Err(e) = crate::ollama::endpoint::Endpoint::parse(&self.ollama.api_url) {
+            bail!("Invalid Ollama API URL: {}", e);
+        }
+        
+        // Validate the model and sampling options
+        if self.ollama.default_model.trim().is_empty() {
+            bail!("Default model must not be empty");
+        }
+        if !(0.0..=2.0).contains(&self.ollama.temperature) {
+            bail!("ollama.temperature must be between 0.0 and 2.0, got {}", self.ollama.temperature);
+        }
+        if !(0.0..=1.0).contains(&self.ollama.top_p) {
+            bail!("ollama.top_p must be between 0.0 and 1.0, got {}", self.ollama.top_p);
+        }
+        if self.ollama.top_k < 1 {
+            bail!("ollama.top_k must be at least 1, got {}", self.ollama.top_k);
+        }
+        if self.ollama.max_tokens < 1 {
+            bail!("ollama.max_tokens must be at least 1, got {}", self.ollama.max_tokens);
+        }
+        if self.ollama.num_ctx == Some(0) {
+            bail!("ollama.num_ctx must be at least 1 when set");
+        }
+        if self.ollama.stop.iter().any(String::is_empty) {
+            bail!("ollama.stop sequences must not be empty");
+        }
+        if !(-2.0..=2.0).contains(&self.ollama.presence_penalty) {
+            bail!("ollama.presence_penalty must be between -2.0 and 2.0, got {}", self.ollama.presence_penalty);
+        }
+        if !(-2.0..=2.0).contains(&self.ollama.frequency_penalty) {
+            bail!("ollama.frequency_penalty must be between -2.0 and 2.0, got {}", self.ollama.frequency_penalty);
+        }
+        
+        // Validate the regeneration temperature increase
+        if !(self.ollama.temperature_bump > 0.0 && self.ollama.temperature_bump <= 2.0) {
+            bail!("Temperature bump must be greater than 0.0 and at most 2.0");
+        }
+        
+        // Validate connection probe timeout
+        if self.ollama.probe_timeout_secs == 0 {
+            bail!("Connection probe timeout must be at least 1 second");
+        }
+        
+        // Validate streaming timeouts
+        if self.ollama.first_token_timeout_secs == 0 {
+            bail!("First token timeout must be at least 1 second");
+        }
+        if self.ollama.stream_idle_timeout_secs == 0 {
+            bail!("Stream idle timeout must be at least 1 second");
+        }
+        
+        // Validate the response mode, since only Ollama's API is read without streaming
+        if !self.ollama.stream && self.ollama.api_style != ApiStyle::Ollama {
+            bail!("Non-streaming responses (stream = false) need api_style = \"ollama\"");
+        }
+        if self.ollama.reuse_context && self.ollama.api_style != ApiStyle::Ollama {
+            bail!("Reusing the context (reuse_context = true) needs api_style = \"ollama\"");
+        }
+        
+        // Validate client-side stop pattern
+        if let Some(pattern) = &self.ollama.client_stop_regex {
+            if let Err(e) = regex::Regex::new(pattern) {
+                bail!("Invalid client stop regex: {}", e);
+            }
+        }
+        
+        // Validate redact patterns
+        if let Err(e) = crate::utils::redact::Redactor::new(&self.privacy.redact_patterns) {
+            bail!("{}", e);
+        }
+        
+        // Validate request concurrency
+        if self.ollama.max_concurrent_requests == 0 {
+            bail!("Maximum concurrent requests must be at least 1");
+        }
+        
+        // Validate the circuit breaker periods
+        if self.ollama.circuit_breaker_failures > 0
+            && (self.ollama.circuit_breaker_window_secs == 0 || self.ollama.circuit_breaker_cooldown_secs == 0)
+        {
+            bail!("Circuit breaker window and cooldown must be at least 1 second");
+        }
+        
+        // Validate prompt templates, which are picked by name
+        for (index, template) in self.templates.iter().enumerate() {
+            if template.name.trim().is_empty() {
+                bail!("Template names must not be empty");
+            }
+            if self.templates[..index].iter().any(|other| other.name == template.name) {
+                bail!("Duplicate template name: {}", template.name);
+            }
+            if let Err(e) = crate::utils::template::template_variables(&template.body) {
+                bail!("Invalid template {}: {}", template.name, e);
+            }
+        }
+        
+        // Validate the hook time limit
+        if self.hooks.timeout_secs == 0 {
+            bail!("Hook timeout must be at least 1 second");
+        }
+        
+        // Validate quick switch slots, which map to the number keys
+        if let Some(slot) = self.ui.quick_switch.keys().find(|slot| !QUICK_SWITCH_SLOTS.contains(slot)) {
+            bail!("Quick switch slots must be between 1 and 9, got {}", slot);
+        }
+        
+        // The client sets these headers itself, so they would be silently dropped from the table
+        if let Some(name) = self.ollama.headers.keys().find(|name| name.eq_ignore_ascii_case("authorization")) {
+            bail!("ollama.headers must not set {}; set ollama.api_key to send a bearer token", name);
+        }
+        if let Some(name) = self.ollama.headers.keys().find(|name| name.eq_ignore_ascii_case("content-type")) {
+            bail!("ollama.headers must not set {}; requests are always sent as JSON", name);
+        }
+        
+        // Invalid custom headers are left out of requests rather than stopping the app from starting
+        for name in self.ollama.headers.keys().filter(|name| !is_valid_header_name(name)) {
+            warn!("Ignoring invalid header name in ollama.headers: {:?}", name);
         }
         
         Ok(())
@@ -1,77 +1,474 @@
-#[cfg(test)]
-mod tests {
-    use crate::config::{Config, save_config};
-    use tempfile::tempdir;
-
-    #[test]
-    fn test_default_config() {
-        let config = Config::default();
-        
-        // Test default values
-        assert_eq!(config.window.width, 400);
-        assert_eq!(config.window.height, 600);
-        assert_eq!(config.window.opacity, 0.9);
-        assert!(config.window.always_on_top);
-        assert_eq!(config.ollama.api_url, "http://localhost:11434");
-        assert_eq!(config.ollama.default_model, "llama3.2");
-        assert_eq!(config.conversation.max_length, 10000);
-        assert_eq!(config.logging.level, "info");
-        
-        // Test validation
-        assert!(config.validate().is_ok());
+use crate::config::{
+    ApiStyle, CliOverrides, Config, ConversationConfig, OllamaConfig, ProbeEndpoint, Template, ThemeConfig, ThemeMode, UiConfig,
+    WindowLevel, is_valid_header_name, save_config,
+};
+use tempfile::tempdir;
+
+#[test]
+fn test_default_config() {
+    let config = Config::default();
+    
+    // Test default values
+    assert_eq!(config.window.width, 400);
+    assert_eq!(config.window.height, 600);
+    assert_eq!(config.window.opacity, 0.9);
+    assert!(config.window.always_on_top);
+    assert_eq!(config.window.level, WindowLevel::Normal);
+    assert_eq!(config.ollama.api_url, "http://localhost:11434");
+    assert_eq!(config.ollama.default_model, "llama3.2");
+    assert_eq!(config.ollama.probe_endpoint, ProbeEndpoint::Models);
+    assert!(config.ollama.stream);
+    assert_eq!(config.ollama.context_warning_tokens, 3072);
+    assert!(!config.ollama.prewarm);
+    assert_eq!(config.conversation.max_chars, 10000);
+    assert_eq!(config.logging.level, "info");
+    assert!(!config.logging.persist_failed_requests);
+    assert_eq!(config.ui.max_input_chars, 100_000);
+    assert_eq!(config.ui.loading_text.connecting, "Connecting to Ollama API...");
+    assert_eq!(config.ui.loading_text.generating, "Waiting for response...");
+    assert!(!config.ui.smooth_scroll);
+    assert_eq!(config.hooks.on_response, None);
+    assert_eq!(config.hooks.timeout_secs, 5);
+    
+    // Test validation
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_config_validation() {
+    // Test invalid opacity
+    let mut config = Config::default();
+    config.window.opacity = 1.5;
+    assert!(config.validate().is_err());
+    
+    // Test invalid window dimensions
+    config = Config::default();
+    config.window.width = 100;
+    assert!(config.validate().is_err());
+    
+    // Test invalid log level
+    config = Config::default();
+    config.logging.level = "invalid".to_string();
+    assert!(config.validate().is_err());
+    
+    // Test invalid max conversation length
+    config = Config::default();
+    config.conversation.max_chars = 500;
+    assert!(config.validate().is_err());
+    
+    // Test invalid model
+    config = Config::default();
+    config.ollama.default_model = " ".to_string();
+    assert!(config.validate().is_err());
+    
+    // Test empty stop sequences
+    config = Config::default();
+    config.ollama.stop = vec!["User:".to_string()];
+    assert!(config.validate().is_ok());
+    config.ollama.stop.push(String::new());
+    assert!(config.validate().is_err());
+    
+    // Test invalid summary preview length
+    config = Config::default();
+    config.conversation.summary_preview_len = 5;
+    assert!(config.validate().is_err());
+    
+    // Test invalid API URL
+    config = Config::default();
+    config.ollama.api_url = "localhost:11434".to_string();
+    assert!(config.validate().is_err());
+    
+    // Test invalid connection probe timeout
+    config = Config::default();
+    config.ollama.probe_timeout_secs = 0;
+    assert!(config.validate().is_err());
+    
+    // Test invalid streaming timeouts
+    config = Config::default();
+    config.ollama.first_token_timeout_secs = 0;
+    assert!(config.validate().is_err());
+    config = Config::default();
+    config.ollama.stream_idle_timeout_secs = 0;
+    assert!(config.validate().is_err());
+    
+    // Test invalid temperature bump
+    config = Config::default();
+    config.ollama.temperature_bump = 0.0;
+    assert!(config.validate().is_err());
+    config.ollama.temperature_bump = 2.5;
+    assert!(config.validate().is_err());
+    
+    // Test invalid window framing
+    config = Config::default();
+    config.window.corner_radius = -1.0;
+    assert!(config.validate().is_err());
+    config.window.corner_radius = 100.0;
+    assert!(config.validate().is_err());
+    config = Config::default();
+    config.window.shadow = 1.5;
+    assert!(config.validate().is_err());
+    
+    // Test invalid opacity presets
+    config = Config::default();
+    config.window.opacity_presets.clear();
+    assert!(config.validate().is_err());
+    config.window.opacity_presets = vec![0.5, 0.0];
+    assert!(config.validate().is_err());
+    
+    // Test invalid request concurrency
+    config = Config::default();
+    config.ollama.max_concurrent_requests = 0;
+    assert!(config.validate().is_err());
+    
+    // Test invalid circuit breaker periods, which only matter while it is enabled
+    config = Config::default();
+    config.ollama.circuit_breaker_cooldown_secs = 0;
+    assert!(config.validate().is_err());
+    config.ollama.circuit_breaker_failures = 0;
+    assert!(config.validate().is_ok());
+    
+    // Test non-streaming responses, which only Ollama's API supports
+    config = Config::default();
+    config.ollama.stream = false;
+    assert!(config.validate().is_ok());
+    config.ollama.api_style = ApiStyle::OpenAi;
+    assert!(config.validate().is_err());
+    
+    // Test reusing the context, which only Ollama's generate endpoint returns
+    config = Config::default();
+    config.ollama.reuse_context = true;
+    assert!(config.validate().is_ok());
+    config.ollama.api_style = ApiStyle::OpenAi;
+    assert!(config.validate().is_err());
+    
+    // Test invalid hook timeout
+    config = Config::default();
+    config.hooks.timeout_secs = 0;
+    assert!(config.validate().is_err());
+    
+    // Test invalid redact pattern
+    config = Config::default();
+    config.privacy.redact_patterns = vec!["sk-\\w+".to_string(), "[unclosed".to_string()];
+    assert!(config.validate().is_err());
+    config.privacy.redact_patterns.pop();
+    assert!(config.validate().is_ok());
+    
+    // Test invalid client stop regex
+    config = Config::default();
+    config.ollama.client_stop_regex = Some("(unclosed".to_string());
+    assert!(config.validate().is_err());
+    config.ollama.client_stop_regex = Some("```\\s*$".to_string());
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_command_line_overrides_are_not_saved() {
+    let temp_dir = tempdir().unwrap();
+    let config_path = temp_dir.path().join("config.toml");
+    let mut file = Config::default();
+    file.ollama.default_model = "mistral".to_string();
+
+    let overrides = CliOverrides {
+        model: Some("llava".to_string()),
+        seed: Some(42),
+        no_stream: true,
+        timeout: Some(5),
+        ..Default::default()
+    };
+    let mut config = overrides.apply(file);
+    config.dry_run = true;
+    assert_eq!(config.ollama.default_model, "llava");
+    assert_eq!(config.ollama.seed, Some(42));
+    assert!(!config.ollama.stream);
+    assert_eq!(config.ollama.request_timeout_secs, 5);
+
+    // Settings changed in the app are saved, the overrides are not
+    config.window.opacity = 0.5;
+    save_config(&config, Some(config_path.clone())).unwrap();
+    let content = std::fs::read_to_string(&config_path).unwrap();
+    assert!(!content.contains("dry_run"));
+    let saved: Config = toml::from_str(&content).unwrap();
+    assert_eq!(saved.ollama.default_model, "mistral");
+    assert_eq!(saved.ollama.seed, None);
+    assert!(saved.ollama.stream);
+    assert_eq!(saved.ollama.request_timeout_secs, 120);
+    assert_eq!(saved.window.opacity, 0.5);
+    assert!(!saved.dry_run);
+
+    // An overridden setting changed since startup, like a model picked in the UI, is saved
+    config.ollama.default_model = "phi3".to_string();
+    assert_eq!(config.to_saved().ollama.default_model, "phi3");
+}
+
+#[test]
+fn test_save_load_config() {
+    let temp_dir = tempdir().unwrap();
+    let config_path = temp_dir.path().join("config.toml");
+    
+    // Create a config with non-default values
+    let mut config = Config::default();
+    config.window.width = 500;
+    config.window.height = 700;
+    config.ollama.default_model = "mistral".to_string();
+    
+    // Save the config
+    save_config(&config, Some(config_path.clone())).unwrap();
+    
+    // Verify the file exists
+    assert!(config_path.exists());
+    
+    // Load the config using the module function instead of a method
+    let loaded_config = toml::from_str::<Config>(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+    
+    // Verify the loaded values match the saved values
+    assert_eq!(loaded_config.window.width, 500);
+    assert_eq!(loaded_config.window.height, 700);
+    assert_eq!(loaded_config.ollama.default_model, "mistral");
+}
+
+#[test]
+fn test_templates_round_trip() {
+    let mut config = Config::default();
+    config.templates.push(Template {
+        name: "Translate".to_string(),
+        body: "Translate to {lang}: {input}".to_string(),
+    });
+    let saved = toml::to_string(&config).unwrap();
+    let reloaded: Config = toml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.templates, config.templates);
+    assert!(reloaded.validate().is_ok());
+
+    // Templates are picked by name, so names must be unique, and bodies must parse
+    config.templates.push(config.templates[0].clone());
+    assert!(config.validate().is_err());
+    config.templates[1] = Template {
+        name: "Broken".to_string(),
+        body: "Unclosed {lang".to_string(),
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_quick_switch_round_trip() {
+    let ui: UiConfig = toml::from_str("[quick_switch]\n1 = \"abc\"\n9 = \"xyz\"\n").unwrap();
+    assert_eq!(ui.quick_switch.get(&1).map(String::as_str), Some("abc"));
+    assert_eq!(ui.quick_switch.get(&9).map(String::as_str), Some("xyz"));
+
+    let saved = toml::to_string(&ui).unwrap();
+    let reloaded: UiConfig = toml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.quick_switch, ui.quick_switch);
+
+    // Keys must be numbers, and only the number keys can be assigned
+    assert!(toml::from_str::<UiConfig>("[quick_switch]\nwork = \"abc\"\n").is_err());
+    let mut config = Config::default();
+    config.ui.quick_switch.insert(0, "abc".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_partial_loading_text_keeps_other_defaults() {
+    let ui: UiConfig = toml::from_str("[loading_text]\ngenerating = \"Thinking...\"\n").unwrap();
+    assert_eq!(ui.loading_text.generating, "Thinking...");
+    assert_eq!(ui.loading_text.connecting, "Connecting to Ollama API...");
+}
+
+#[test]
+fn test_old_max_length_is_read_as_max_chars() {
+    let conversation: ConversationConfig = toml::from_str("max_length = 5000\nauto_save = true\n").unwrap();
+    assert_eq!(conversation.max_chars, 5000);
+    // Clearing keeps the saved conversation unless asked otherwise
+    assert!(!conversation.clear_deletes_file);
+}
+
+#[test]
+fn test_next_opacity_cycles_through_presets() {
+    let mut config = Config::default();
+    config.window.opacity_presets = vec![0.3, 0.6, 0.9, 1.0];
+    
+    let mut seen = Vec::new();
+    config.window.opacity = 0.3;
+    for _ in 0..5 {
+        config.window.opacity = config.window.next_opacity();
+        seen.push(config.window.opacity);
     }
+    assert_eq!(seen, [0.6, 0.9, 1.0, 0.3, 0.6]);
+    
+    // Opacities between presets move up to the next one, and past the last wrap around
+    config.window.opacity = 0.75;
+    assert_eq!(config.window.next_opacity(), 0.9);
+    config.window.opacity = 0.2;
+    assert_eq!(config.window.next_opacity(), 0.3);
+    config.window.opacity_presets = vec![0.5, 0.8];
+    config.window.opacity = 0.95;
+    assert_eq!(config.window.next_opacity(), 0.5);
+    
+    // Presets are followed in the configured order
+    config.window.opacity_presets = vec![1.0, 0.4];
+    config.window.opacity = 1.0;
+    assert_eq!(config.window.next_opacity(), 0.4);
+    assert_eq!(Config::default().window.opacity_presets, [0.3, 0.6, 0.9, 1.0]);
+}
+
+#[test]
+fn test_window_framing_round_trip() {
+    let mut config = Config::default();
+    config.window.corner_radius = 20.0;
+    config.window.shadow = 0.6;
+    
+    let serialized = toml::to_string(&config).unwrap();
+    let loaded: Config = toml::from_str(&serialized).unwrap();
+    assert_eq!(loaded.window.corner_radius, 20.0);
+    assert_eq!(loaded.window.shadow, 0.6);
+    
+    // Configurations written before framing existed get the defaults
+    let serialized: String = serialized
+        .lines()
+        .filter(|line| !line.starts_with("corner_radius =") && !line.starts_with("shadow ="))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let loaded: Config = toml::from_str(&serialized).unwrap();
+    assert_eq!(loaded.window.corner_radius, Config::default().window.corner_radius);
+    assert_eq!(loaded.window.shadow, Config::default().window.shadow);
+}
 
-    #[test]
-    fn test_config_validation() {
-        // Test invalid opacity
+#[test]
+fn test_window_level_round_trip() {
+    let mut config = Config::default();
+    config.window.level = WindowLevel::FloatingWhenFocused;
+    
+    let serialized = toml::to_string(&config).unwrap();
+    assert!(serialized.contains("level = \"FloatingWhenFocused\""));
+    let loaded: Config = toml::from_str(&serialized).unwrap();
+    assert_eq!(loaded.window.level, WindowLevel::FloatingWhenFocused);
+    
+    // Configurations written before the level existed still load
+    let serialized = serialized.replace("level = \"FloatingWhenFocused\"\n", "");
+    let loaded: Config = toml::from_str(&serialized).unwrap();
+    assert_eq!(loaded.window.level, WindowLevel::Normal);
+}
+
+#[test]
+fn test_always_on_top_raises_normal_level() {
+    assert_eq!(WindowLevel::Normal.with_always_on_top(true), WindowLevel::AlwaysOnTop);
+    assert_eq!(WindowLevel::Normal.with_always_on_top(false), WindowLevel::Normal);
+    assert_eq!(WindowLevel::AlwaysOnTop.with_always_on_top(false), WindowLevel::AlwaysOnTop);
+    assert_eq!(
+        WindowLevel::FloatingWhenFocused.with_always_on_top(true),
+        WindowLevel::FloatingWhenFocused
+    );
+}
+
+#[test]
+fn test_theme_mode_round_trip() {
+    let theme: ThemeConfig = toml::from_str("mode = \"system\"\n").unwrap();
+    assert_eq!(theme.mode, ThemeMode::System);
+    assert!(toml::to_string(&theme).unwrap().contains("mode = \"system\""));
+
+    // Configurations written before the mode existed use the light theme
+    assert_eq!(toml::from_str::<ThemeConfig>("").unwrap().mode, ThemeMode::Light);
+    assert!(toml::from_str::<ThemeConfig>("mode = \"blue\"\n").is_err());
+    assert_eq!(ThemeMode::System.next(), ThemeMode::Light);
+}
+
+#[test]
+fn test_sampling_option_boundaries() {
+    let validate = |update: &dyn Fn(&mut Config)| {
         let mut config = Config::default();
-        config.window.opacity = 1.5;
-        assert!(config.validate().is_err());
-        
-        // Test invalid window dimensions
-        config = Config::default();
-        config.window.width = 100;
-        assert!(config.validate().is_err());
-        
-        // Test invalid log level
-        config = Config::default();
-        config.logging.level = "invalid".to_string();
-        assert!(config.validate().is_err());
-        
-        // Test invalid max conversation length
-        config = Config::default();
-        config.conversation.max_length = 500;
-        assert!(config.validate().is_err());
-        
-        // Test invalid API URL
-        config = Config::default();
-        config.ollama.api_url = "localhost:11434".to_string();
-        assert!(config.validate().is_err());
+        update(&mut config);
+        config.validate()
+    };
+
+    for temperature in [0.0, 2.0] {
+        assert!(validate(&|config| config.ollama.temperature = temperature).is_ok());
+    }
+    for temperature in [-0.01, 2.01, 50.0] {
+        let error = validate(&|config| config.ollama.temperature = temperature).unwrap_err();
+        assert!(error.to_string().contains("ollama.temperature must be between 0.0 and 2.0"));
     }
 
-    #[test]
-    fn test_save_load_config() {
-        let temp_dir = tempdir().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
-        
-        // Create a config with non-default values
-        let mut config = Config::default();
-        config.window.width = 500;
-        config.window.height = 700;
-        config.ollama.default_model = "mistral".to_string();
-        
-        // Save the config
-        save_config(&config, Some(config_path.clone())).unwrap();
-        
-        // Verify the file exists
-        assert!(config_path.exists());
-        
-        // Load the config using the module function instead of a method
-        let loaded_config = toml::from_str::<Config>(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
-        
-        // Verify the loaded values match the saved values
-        assert_eq!(loaded_config.window.width, 500);
-        assert_eq!(loaded_config.window.height, 700);
-        assert_eq!(loaded_config.ollama.default_model, "mistral");
+    for top_p in [0.0, 1.0] {
+        assert!(validate(&|config| config.ollama.top_p = top_p).is_ok());
+    }
+    for top_p in [-0.01, 1.01] {
+        let error = validate(&|config| config.ollama.top_p = top_p).unwrap_err();
+        assert!(error.to_string().contains("ollama.top_p must be between 0.0 and 1.0"));
+    }
+
+    assert!(validate(&|config| config.ollama.top_k = 1).is_ok());
+    let error = validate(&|config| config.ollama.top_k = 0).unwrap_err();
+    assert!(error.to_string().contains("ollama.top_k must be at least 1"));
+
+    assert!(validate(&|config| config.ollama.max_tokens = 1).is_ok());
+    let error = validate(&|config| config.ollama.max_tokens = 0).unwrap_err();
+    assert!(error.to_string().contains("ollama.max_tokens must be at least 1"));
+
+    for penalty in [-2.0, 2.0] {
+        assert!(validate(&|config| config.ollama.presence_penalty = penalty).is_ok());
+        assert!(validate(&|config| config.ollama.frequency_penalty = penalty).is_ok());
     }
+    for penalty in [-2.01, 2.01] {
+        assert!(validate(&|config| config.ollama.presence_penalty = penalty).is_err());
+        assert!(validate(&|config| config.ollama.frequency_penalty = penalty).is_err());
+    }
+}
+
+#[test]
+fn test_headers_table_and_invalid_names() {
+    let ollama: OllamaConfig = toml::from_str(concat!(
+        "api_url = \"http://localhost:11434\"\n",
+        "default_model = \"llama3.2\"\n",
+        "[headers]\n",
+        "X-Tenant-Id = \"acme\"\n",
+        "\"Not a header\" = \"ignored\"\n",
+    ))
+    .unwrap();
+    assert_eq!(ollama.headers["X-Tenant-Id"], "acme");
+
+    // Invalid names are warned about and skipped rather than failing startup
+    let config = Config { ollama, ..Config::default() };
+    assert!(config.validate().is_ok());
+    let reloaded: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(reloaded.ollama.headers, config.ollama.headers);
+    assert!(is_valid_header_name("X-Tenant-Id"));
+    assert!(!is_valid_header_name("Not a header"));
+    assert!(!is_valid_header_name(""));
+}
+
+#[test]
+fn test_headers_the_client_sets_are_refused() {
+    let mut config = Config::default();
+    config.ollama.headers.insert("authorization".to_string(), "Bearer abc".to_string());
+    let error = config.validate().unwrap_err().to_string();
+    assert!(error.contains("ollama.api_key"));
+
+    let mut config = Config::default();
+    config.ollama.headers.insert("Content-Type".to_string(), "text/plain".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_request_and_connect_timeouts() {
+    let ollama = OllamaConfig::default();
+    assert_eq!(ollama.request_timeout(), Some(std::time::Duration::from_secs(120)));
+    assert_eq!(ollama.connect_timeout(), Some(std::time::Duration::from_secs(10)));
+
+    // 0 means no limit
+    let ollama: OllamaConfig = toml::from_str(concat!(
+        "api_url = \"http://localhost:11434\"\n",
+        "default_model = \"llama3.2\"\n",
+        "request_timeout_secs = 0\n",
+        "connect_timeout_secs = 3\n",
+    ))
+    .unwrap();
+    assert_eq!(ollama.request_timeout(), None);
+    assert_eq!(ollama.connect_timeout(), Some(std::time::Duration::from_secs(3)));
+    assert!(Config { ollama, ..Config::default() }.validate().is_ok());
+
+    // Negative timeouts are rejected when the file is read
+    let negative = toml::from_str::<OllamaConfig>(concat!(
+        "api_url = \"http://localhost:11434\"\n",
+        "default_model = \"llama3.2\"\n",
+        "request_timeout_secs = -1\n",
+    ));
+    assert!(negative.is_err());
 }
@@ -0,0 +1,41 @@
+/// Tracks unsaved changes so periodic auto-saves only write when needed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirtyFlag {
+    /// Whether there are changes that have not been saved
+    dirty: bool,
+}
+
+impl DirtyFlag {
+    /// Record that there are unsaved changes
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Check whether there are unsaved changes
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Clear the flag, returning whether a save is needed
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_reports_each_change_once() {
+        let mut flag = DirtyFlag::default();
+        assert!(!flag.take());
+
+        flag.mark();
+        flag.mark();
+        assert!(flag.is_dirty());
+        assert!(flag.take());
+        assert!(!flag.is_dirty());
+        assert!(!flag.take());
+    }
+}
@@ -1,10 +1,17 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::data::clock::{system_clock, Clock};
+use crate::ollama::models::GenerationParameters;
+use crate::utils::format::format_count;
+use crate::utils::{grapheme_count, grapheme_truncate, word_count};
 
 /// Role of a message sender
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +20,8 @@ pub enum MessageRole {
     User,
     /// Assistant (LLM) message
     Assistant,
+    /// Instructions that steer the model's behavior
+    System,
 }
 
 impl MessageRole {
@@ -21,19 +30,95 @@ impl MessageRole {
         match self {
             MessageRole::User => "user",
             MessageRole::Assistant => "assistant",
+            MessageRole::System => "system",
         }
     }
 
     /// Convert from string representation
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn parse(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "user" => Some(MessageRole::User),
             "assistant" => Some(MessageRole::Assistant),
+            "system" => Some(MessageRole::System),
             _ => None,
         }
     }
 }
 
+/// Rough number of characters per token, used to estimate token counts
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimate the number of tokens in a piece of text
+pub fn approx_tokens(text: &str) -> usize {
+    grapheme_count(text).div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Average reading speed, used to estimate reading time
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Number of recent messages quoted in a continuation summary
+const SUMMARY_MESSAGES: usize = 3;
+
+/// Maximum characters of each message quoted in a continuation summary
+const SUMMARY_PREVIEW_CHARS: usize = 120;
+
+/// Default maximum characters of the last message shown in a summary
+pub const DEFAULT_PREVIEW_LEN: usize = 50;
+
+/// Subdirectory of the conversations directory that pruned conversations are moved to
+const TRASH_DIR: &str = "trash";
+
+/// Statistics reported by Ollama with the final chunk of a response
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ResponseMetadata {
+    /// Model that generated the response
+    pub model: String,
+    /// Timestamp reported by the API
+    pub created_at: String,
+    /// Why generation stopped
+    pub done_reason: Option<String>,
+    /// Total time spent generating the response in nanoseconds
+    pub total_duration: Option<u64>,
+    /// Time spent loading the model in nanoseconds
+    pub load_duration: Option<u64>,
+    /// Number of tokens in the prompt
+    pub prompt_eval_count: Option<u64>,
+    /// Time spent evaluating the prompt in nanoseconds
+    pub prompt_eval_duration: Option<u64>,
+    /// Number of tokens in the response
+    pub eval_count: Option<u64>,
+    /// Time spent generating the response in nanoseconds
+    pub eval_duration: Option<u64>,
+}
+
+impl ResponseMetadata {
+    /// Check whether generation stopped at the token limit, leaving the response unfinished
+    pub fn hit_length_limit(&self) -> bool {
+        self.done_reason.as_deref() == Some("length")
+    }
+
+    /// Get how many tokens per second the response was generated at, if the API reported it
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let tokens = self.eval_count?;
+        let nanos = self.eval_duration.filter(|&nanos| nanos > 0)?;
+        Some(tokens as f64 / (nanos as f64 / 1_000_000_000.0))
+    }
+
+    /// Describe the response's size and speed, like `128 tokens at 31.5 tokens/s`
+    pub fn speed_summary(&self) -> Option<String> {
+        let tokens_per_second = self.tokens_per_second()?;
+        let mut summary = format!(
+            "{} tokens at {:.1} tokens/s",
+            format_count(self.eval_count.unwrap_or_default()),
+            tokens_per_second
+        );
+        if let Some(prompt_tokens) = self.prompt_eval_count {
+            summary.push_str(&format!(", {} prompt tokens", format_count(prompt_tokens)));
+        }
+        Some(summary)
+    }
+}
+
 /// A message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -43,24 +128,97 @@ pub struct Message {
     pub content: String,
     /// Timestamp when the message was created
     pub timestamp: DateTime<Utc>,
+    /// Whether the message has been bookmarked
+    #[serde(default)]
+    pub bookmarked: bool,
+    /// Statistics reported with the response, for assistant messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ResponseMetadata>,
+    /// Temperature the response was generated with, when it differs from the configured one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Base64-encoded images attached to a user message or returned with a response
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    /// Generation parameters the response was requested with, for assistant messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<GenerationParameters>,
+    /// Text shown instead of the content, as rewritten by the response hook; never saved
+    #[serde(skip)]
+    pub display_content: Option<String>,
 }
 
 impl Message {
     /// Create a new message
     pub fn new(role: MessageRole, content: &str) -> Self {
+        Self::with_clock(role, content, &*system_clock())
+    }
+
+    /// Create a new message timestamped by the given clock
+    pub fn with_clock(role: MessageRole, content: &str, clock: &dyn Clock) -> Self {
         Self {
             role,
             content: content.to_string(),
-            timestamp: Utc::now(),
+            timestamp: clock.now(),
+            bookmarked: false,
+            metadata: None,
+            temperature: None,
+            images: Vec::new(),
+            params: None,
+            display_content: None,
         }
     }
 
+    /// Get the text to show for the message, which a response hook may have rewritten
+    pub fn displayed(&self) -> &str {
+        self.display_content.as_deref().unwrap_or(&self.content)
+    }
+
     /// Format the message for display
     pub fn format(&self) -> String {
         format!("{}: {}", self.role.as_str(), self.content)
     }
 }
 
+/// Summary statistics about a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConversationStats {
+    /// Total number of messages
+    pub message_count: usize,
+    /// Number of messages sent by the user
+    pub user_messages: usize,
+    /// Number of messages sent by the assistant
+    pub assistant_messages: usize,
+    /// Total number of characters (grapheme clusters) across all messages
+    pub characters: usize,
+    /// Estimated number of tokens across all messages
+    pub estimated_tokens: usize,
+    /// Total number of words across all messages
+    pub word_count: usize,
+    /// Number of words in the user's messages
+    pub user_words: usize,
+    /// Number of words in the assistant's messages
+    pub assistant_words: usize,
+}
+
+impl ConversationStats {
+    /// Estimate the minutes it takes to read every message, rounded up so any text takes at least one
+    pub fn reading_minutes(&self) -> usize {
+        self.word_count.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+/// Context Ollama's generate endpoint returned for a conversation, to continue it without sending it again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateContext {
+    /// Model that returned the context, since no other model can continue from it
+    pub model: String,
+    /// Number of messages of the conversation the context covers
+    pub messages: usize,
+    /// The context's tokens
+    pub tokens: Vec<i64>,
+}
+
 /// A conversation between a user and an LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
@@ -76,12 +234,41 @@ pub struct Conversation {
     pub created_at: DateTime<Utc>,
     /// The last update timestamp
     pub updated_at: DateTime<Utc>,
+    /// Whether the conversation has been archived after reaching its message limit
+    #[serde(default)]
+    pub archived: bool,
+    /// ID of the conversation this one continues, if any
+    #[serde(default)]
+    pub continued_from: Option<String>,
+    /// ID of the conversation this one was forked from, if any
+    #[serde(default)]
+    pub forked_from: Option<String>,
+    /// System prompt sent with this conversation after the global one, if any
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Whether reasoning models should think in this conversation, overriding the configured default
+    #[serde(default)]
+    pub think: Option<bool>,
+    /// Whether the conversation is pinned and kept when old conversations are pruned
+    #[serde(default)]
+    pub pinned: bool,
+    /// Context returned with the last response, for `ollama.reuse_context`; never saved
+    #[serde(skip)]
+    pub context: Option<GenerateContext>,
+    /// Clock used to timestamp changes
+    #[serde(skip, default = "system_clock")]
+    clock: Arc<dyn Clock>,
 }
 
 impl Conversation {
     /// Create a new conversation
     pub fn new(title: &str, model: &str) -> Self {
-        let now = Utc::now();
+        Self::with_clock(title, model, system_clock())
+    }
+
+    /// Create a new conversation that takes its timestamps from the given clock
+    pub fn with_clock(title: &str, model: &str, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             title: title.to_string(),
@@ -89,14 +276,222 @@ impl Conversation {
             model: model.to_string(),
             created_at: now,
             updated_at: now,
+            archived: false,
+            continued_from: None,
+            forked_from: None,
+            system_prompt: None,
+            think: None,
+            pinned: false,
+            context: None,
+            clock,
+        }
+    }
+
+    /// Start a new conversation that continues this one
+    ///
+    /// The new conversation is seeded with a summary of the most recent
+    /// messages so the model keeps the context of the previous thread.
+    pub fn continue_from(&self) -> Self {
+        let mut continuation = Self::with_clock(
+            &format!("{} (continued)", self.title),
+            &self.model,
+            self.clock.clone(),
+        );
+        continuation.continued_from = Some(self.id.clone());
+        continuation.system_prompt = self.system_prompt.clone();
+        continuation.think = self.think;
+        continuation.add_message(MessageRole::Assistant, &self.continuation_summary());
+        continuation
+    }
+
+    /// Fork a new conversation containing the messages up to and including `index`
+    ///
+    /// Returns None if the index is out of range. This conversation is left unchanged.
+    pub fn fork_from(&self, index: usize) -> Option<Self> {
+        if index >= self.messages.len() {
+            return None;
         }
+
+        let mut fork = Self::with_clock(&format!("{} (fork)", self.title), &self.model, self.clock.clone());
+        fork.forked_from = Some(self.id.clone());
+        fork.system_prompt = self.system_prompt.clone();
+        fork.think = self.think;
+        fork.messages = self.messages[..=index].to_vec();
+        Some(fork)
+    }
+
+    /// Build the summary used to seed a continuation of this conversation
+    fn continuation_summary(&self) -> String {
+        let mut summary = format!("Continuing from \"{}\".", self.title);
+        let start = self.messages.len().saturating_sub(SUMMARY_MESSAGES);
+        if start < self.messages.len() {
+            summary.push_str(" Recent messages:");
+        }
+        for message in &self.messages[start..] {
+            let preview = truncate_preview(&message.content, SUMMARY_PREVIEW_CHARS);
+            summary.push_str(&format!("\n- {}: {}", message.role.as_str(), preview));
+        }
+        summary
+    }
+
+    /// Add a message, rolling over to a continuation once `max_messages` is reached
+    ///
+    /// When the conversation already holds `max_messages` messages it is replaced
+    /// by a continuation before the message is added, and the archived
+    /// conversation is returned so it can be saved. A limit of 0 disables rollover.
+    pub fn add_message_capped(
+        &mut self,
+        role: MessageRole,
+        content: &str,
+        max_messages: usize,
+    ) -> Option<Conversation> {
+        let archived = if max_messages > 0 && self.messages.len() >= max_messages {
+            let continuation = self.continue_from();
+            let mut archived = std::mem::replace(self, continuation);
+            archived.archived = true;
+            info!(
+                "Conversation {} reached {} messages, continuing in {}",
+                archived.id, max_messages, self.id
+            );
+            Some(archived)
+        } else {
+            None
+        };
+
+        self.add_message(role, content);
+        archived
     }
 
     /// Add a message to the conversation
     pub fn add_message(&mut self, role: MessageRole, content: &str) {
-        let message = Message::new(role, content);
+        let message = Message::with_clock(role, content, &*self.clock);
         self.messages.push(message);
-        self.updated_at = Utc::now();
+        self.updated_at = self.clock.now();
+    }
+
+    /// Replace the content of the message at the given index
+    ///
+    /// Returns false if the index is out of range.
+    pub fn edit_message(&mut self, index: usize, new_content: &str) -> bool {
+        match self.messages.get_mut(index) {
+            Some(message) => {
+                message.content = new_content.to_string();
+                message.display_content = None;
+                self.updated_at = self.clock.now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop every message after the given index
+    pub fn truncate_after(&mut self, index: usize) {
+        if index + 1 < self.messages.len() {
+            self.messages.truncate(index + 1);
+            self.updated_at = self.clock.now();
+        }
+    }
+
+    /// Toggle the bookmark on the message at the given index
+    ///
+    /// Returns false if the index is out of range.
+    pub fn toggle_bookmark(&mut self, index: usize) -> bool {
+        match self.messages.get_mut(index) {
+            Some(message) => {
+                message.bookmarked = !message.bookmarked;
+                self.updated_at = self.clock.now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move the message at `from` so that it ends up at index `to`
+    ///
+    /// Returns false without changing anything if either index is out of range.
+    pub fn move_message(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.messages.len() || to >= self.messages.len() {
+            return false;
+        }
+        if from != to {
+            let message = self.messages.remove(from);
+            self.messages.insert(to, message);
+            self.updated_at = self.clock.now();
+        }
+        true
+    }
+
+    /// Attach response statistics to the message at the given index
+    ///
+    /// Returns false if the index is out of range.
+    pub fn attach_metadata(&mut self, index: usize, metadata: ResponseMetadata) -> bool {
+        match self.messages.get_mut(index) {
+            Some(message) => {
+                message.metadata = Some(metadata);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the indices of the messages containing `query`, ignoring case
+    ///
+    /// A blank query matches nothing. The query is compiled once and matched
+    /// against each message in place, so no message is copied or lowercased.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let Some(pattern) = crate::data::search::query_pattern(query) else {
+            return Vec::new();
+        };
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| pattern.is_match(&message.content))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Store the context returned with the response that is now the last message
+    pub fn set_context(&mut self, model: &str, tokens: Vec<i64>) {
+        self.context = Some(GenerateContext {
+            model: model.to_string(),
+            messages: self.messages.len(),
+            tokens,
+        });
+    }
+
+    /// Get the stored context if `model` can continue from it to answer the last message
+    ///
+    /// That is only the case when a single user message was added since the
+    /// context was returned; any other change, like an edit or a regenerated
+    /// response, means the full conversation has to be sent again.
+    pub fn reusable_context(&self, model: &str) -> Option<&[i64]> {
+        let context = self.context.as_ref()?;
+        let follows = context.messages + 1 == self.messages.len()
+            && self.messages.last().is_some_and(|message| message.role == MessageRole::User);
+        (context.model == model && follows).then_some(context.tokens.as_slice())
+    }
+
+    /// Attach images to the message at the given index, replacing any it has
+    ///
+    /// Returns false if the index is out of range.
+    pub fn attach_images(&mut self, index: usize, images: Vec<String>) -> bool {
+        match self.messages.get_mut(index) {
+            Some(message) => {
+                message.images = images;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the indices of all bookmarked messages
+    pub fn bookmarks(&self) -> Vec<usize> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.bookmarked)
+            .map(|(index, _)| index)
+            .collect()
     }
 
     /// Get the conversation directory path
@@ -109,14 +504,22 @@ impl Conversation {
 
     /// Get the file path for this conversation
     pub fn get_file_path(&self) -> PathBuf {
-        let mut path = Self::get_conversations_dir();
-        path.push(format!("{}.json", self.id));
-        path
+        self.file_path_in(&Self::get_conversations_dir())
+    }
+
+    /// Get the file path for this conversation within `dir`
+    fn file_path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.id))
     }
 
     /// Save the conversation to a file
     pub fn save(&self) -> Result<()> {
-        let path = self.get_file_path();
+        self.save_in(&Self::get_conversations_dir())
+    }
+
+    /// Save the conversation to a file in `dir`
+    pub fn save_in(&self, dir: &Path) -> Result<()> {
+        let path = self.file_path_in(dir);
         
         // Create parent directories if they don't exist
         if let Some(parent) = path.parent() {
@@ -136,6 +539,26 @@ impl Conversation {
         Ok(())
     }
 
+    /// Delete the conversation's file
+    pub fn delete(&self) -> Result<()> {
+        self.delete_in(&Self::get_conversations_dir())
+    }
+
+    /// Delete the conversation's file in `dir`, succeeding if it was never saved
+    pub fn delete_in(&self, dir: &Path) -> Result<()> {
+        let path = self.file_path_in(dir);
+        match fs::remove_file(&path) {
+            Ok(()) => info!("Deleted conversation {}", self.id),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("Conversation {} was never saved", self.id);
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to delete conversation: {}", path.display()));
+            }
+        }
+        Ok(())
+    }
+
     /// Load a conversation from a file
     pub fn load(path: &Path) -> Result<Self> {
         let file = File::open(path)
@@ -151,11 +574,14 @@ impl Conversation {
 
     /// Load all conversations from the conversations directory
     pub fn load_all() -> Result<Vec<Self>> {
-        let dir = Self::get_conversations_dir();
-        
+        Self::load_all_in(&Self::get_conversations_dir())
+    }
+
+    /// Load all conversations from `dir`, newest first
+    pub fn load_all_in(dir: &Path) -> Result<Vec<Self>> {
         // Create directory if it doesn't exist
         if !dir.exists() {
-            fs::create_dir_all(&dir)
+            fs::create_dir_all(dir)
                 .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
             return Ok(Vec::new());
         }
@@ -163,7 +589,7 @@ impl Conversation {
         let mut conversations = Vec::new();
         
         // Read all JSON files in the directory
-        for entry in fs::read_dir(&dir)
+        for entry in fs::read_dir(dir)
             .with_context(|| format!("Failed to read directory: {}", dir.display()))? {
             
             let entry = entry?;
@@ -181,12 +607,114 @@ impl Conversation {
         }
         
         // Sort conversations by updated_at (newest first)
-        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
         
         info!("Loaded {} conversations from {}", conversations.len(), dir.display());
         Ok(conversations)
     }
 
+    /// Find a saved conversation by ID or title
+    pub fn find(query: &str) -> Result<Option<Self>> {
+        Self::find_in(&Self::get_conversations_dir(), query)
+    }
+
+    /// Find a conversation saved in `dir` by ID or title
+    pub fn find_in(dir: &Path, query: &str) -> Result<Option<Self>> {
+        let conversations = Self::load_all_in(dir)?;
+        Ok(Self::find_among(&conversations, query)?.cloned())
+    }
+
+    /// Find a conversation by ID or title
+    ///
+    /// An exact ID wins over an exact title, which wins over a title containing
+    /// the query ignoring case. Fails if the first kind of match found is shared
+    /// by more than one conversation.
+    pub fn find_among<'a>(conversations: &'a [Self], query: &str) -> Result<Option<&'a Self>> {
+        if let Some(conversation) = conversations.iter().find(|conversation| conversation.id == query) {
+            return Ok(Some(conversation));
+        }
+
+        let lowercase_query = query.to_lowercase();
+        let exact: Vec<&Self> = conversations.iter().filter(|conversation| conversation.title == query).collect();
+        let matches = if exact.is_empty() {
+            conversations
+                .iter()
+                .filter(|conversation| conversation.title.to_lowercase().contains(&lowercase_query))
+                .collect()
+        } else {
+            exact
+        };
+
+        match matches.as_slice() {
+            [] => Ok(None),
+            [conversation] => Ok(Some(conversation)),
+            _ => {
+                let candidates: Vec<String> = matches
+                    .iter()
+                    .map(|conversation| format!("{} ({})", conversation.title, conversation.id))
+                    .collect();
+                bail!("\"{}\" matches several conversations: {}", query, candidates.join(", "))
+            }
+        }
+    }
+
+    /// Find the conversation assigned to quick-switch `slot`
+    ///
+    /// Returns None if the slot is unassigned, or with a warning if its
+    /// conversation no longer exists.
+    pub fn find_quick_switch<'a>(
+        conversations: &'a [Self],
+        slots: &BTreeMap<u8, String>,
+        slot: u8,
+    ) -> Option<&'a Self> {
+        let id = slots.get(&slot)?;
+        let conversation = conversations.iter().find(|conversation| &conversation.id == id);
+        if conversation.is_none() {
+            warn!("Quick switch {} is assigned to conversation {}, which no longer exists", slot, id);
+        }
+        conversation
+    }
+
+    /// Check whether the conversation is pinned, archived or bookmarked
+    pub fn is_kept(&self) -> bool {
+        self.pinned || self.archived || self.messages.iter().any(|message| message.bookmarked)
+    }
+
+    /// Move old conversations to the trash, keeping the `max` most recent ones
+    ///
+    /// Conversations for which `keep` returns true are never pruned. A limit of 0
+    /// disables pruning. Returns the number of conversations moved to the trash.
+    pub fn prune_old(max: usize, keep: &dyn Fn(&Conversation) -> bool) -> Result<usize> {
+        Self::prune_old_in(&Self::get_conversations_dir(), max, keep)
+    }
+
+    /// Move old conversations in `dir` to its `trash` subdirectory, keeping the `max` most recent ones
+    pub fn prune_old_in(dir: &Path, max: usize, keep: &dyn Fn(&Conversation) -> bool) -> Result<usize> {
+        if max == 0 {
+            return Ok(0);
+        }
+        
+        let trash = dir.join(TRASH_DIR);
+        let mut pruned = 0;
+        for conversation in Self::load_all_in(dir)?.iter().skip(max) {
+            if keep(conversation) {
+                continue;
+            }
+            
+            fs::create_dir_all(&trash)
+                .with_context(|| format!("Failed to create directory: {}", trash.display()))?;
+            let path = conversation.file_path_in(dir);
+            fs::rename(&path, conversation.file_path_in(&trash))
+                .with_context(|| format!("Failed to move conversation to trash: {}", path.display()))?;
+            pruned += 1;
+        }
+        
+        if pruned > 0 {
+            info!("Moved {} old conversations to {}", pruned, trash.display());
+        }
+        Ok(pruned)
+    }
+
     /// Truncate the conversation to the specified maximum number of messages
     pub fn truncate(&mut self, max_messages: usize) {
         if self.messages.len() > max_messages {
@@ -196,6 +724,27 @@ impl Conversation {
         }
     }
 
+    /// Drop the oldest messages until the conversation has at most `max_chars` characters of content
+    ///
+    /// The last user message and everything after it, or at least the last two messages,
+    /// are always kept, even if they alone are longer than `max_chars`. Returns how many
+    /// messages were dropped, by which the indices of the remaining ones moved down.
+    pub fn truncate_to_chars(&mut self, max_chars: usize) -> usize {
+        let last_user = self.messages.iter().rposition(|message| message.role == MessageRole::User);
+        let keep_from = self.messages.len().saturating_sub(2).min(last_user.unwrap_or(usize::MAX));
+        let mut total = self.char_count();
+        let mut to_remove = 0;
+        while total > max_chars && to_remove < keep_from {
+            total -= grapheme_count(&self.messages[to_remove].content);
+            to_remove += 1;
+        }
+        if to_remove > 0 {
+            self.messages.drain(0..to_remove);
+            debug!("Truncated conversation {} to {} characters", self.id, total);
+        }
+        to_remove
+    }
+
     /// Get the last message in the conversation
     pub fn last_message(&self) -> Option<&Message> {
         self.messages.last()
@@ -211,18 +760,55 @@ impl Conversation {
         self.messages.is_empty()
     }
 
+    /// Get the number of characters (grapheme clusters) across all messages
+    pub fn char_count(&self) -> usize {
+        self.messages.iter().map(|message| grapheme_count(&message.content)).sum()
+    }
+
+    /// Estimate the number of tokens across all messages, as in `stats`
+    pub fn approx_token_count(&self) -> usize {
+        self.messages.iter().map(|message| approx_tokens(&message.content)).sum()
+    }
+
+    /// Get message and token statistics for the conversation
+    ///
+    /// Token counts are an estimate based on character count, not the model's tokenizer.
+    pub fn stats(&self) -> ConversationStats {
+        self.messages.iter().fold(ConversationStats::default(), |mut stats, message| {
+            let characters = grapheme_count(&message.content);
+            let words = word_count(&message.content);
+            stats.message_count += 1;
+            match message.role {
+                MessageRole::User => {
+                    stats.user_messages += 1;
+                    stats.user_words += words;
+                }
+                MessageRole::Assistant => {
+                    stats.assistant_messages += 1;
+                    stats.assistant_words += words;
+                }
+                MessageRole::System => {}
+            }
+            stats.word_count += words;
+            stats.characters += characters;
+            stats.estimated_tokens += characters.div_ceil(CHARS_PER_TOKEN);
+            stats
+        })
+    }
+
     /// Get a summary of the conversation
     pub fn summary(&self) -> String {
+        self.summary_with_preview_len(DEFAULT_PREVIEW_LEN)
+    }
+
+    /// Get a summary of the conversation, previewing at most `preview_len` characters
+    pub fn summary_with_preview_len(&self, preview_len: usize) -> String {
         if self.messages.is_empty() {
             return format!("{} (empty)", self.title);
         }
         
         let last_message = self.last_message().unwrap();
-        let preview = if last_message.content.len() > 50 {
-            format!("{}...", &last_message.content[..47])
-        } else {
-            last_message.content.clone()
-        };
+        let preview = truncate_preview(&last_message.content, preview_len);
         
         format!(
             "{} - {} messages - Last: {}",
@@ -233,9 +819,21 @@ impl Conversation {
     }
 }
 
+/// Shorten content to at most `max_chars` characters, ending with "..." when cut
+///
+/// Characters are grapheme clusters, so emoji and combining marks are never split.
+fn truncate_preview(content: &str, max_chars: usize) -> String {
+    if grapheme_count(content) <= max_chars {
+        return content.to_string();
+    }
+    format!("{}...", grapheme_truncate(content, max_chars.saturating_sub(3)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::clock::FakeClock;
+    use chrono::{Duration, TimeZone};
     use tempfile::tempdir;
 
     #[test]
@@ -243,49 +841,90 @@ mod tests {
         assert_eq!(MessageRole::User.as_str(), "user");
         assert_eq!(MessageRole::Assistant.as_str(), "assistant");
         
-        assert_eq!(MessageRole::from_str("user"), Some(MessageRole::User));
-        assert_eq!(MessageRole::from_str("USER"), Some(MessageRole::User));
-        assert_eq!(MessageRole::from_str("assistant"), Some(MessageRole::Assistant));
-        assert_eq!(MessageRole::from_str("ASSISTANT"), Some(MessageRole::Assistant));
-        assert_eq!(MessageRole::from_str("unknown"), None);
+        assert_eq!(MessageRole::parse("user"), Some(MessageRole::User));
+        assert_eq!(MessageRole::parse("USER"), Some(MessageRole::User));
+        assert_eq!(MessageRole::parse("assistant"), Some(MessageRole::Assistant));
+        assert_eq!(MessageRole::parse("ASSISTANT"), Some(MessageRole::Assistant));
+        assert_eq!(MessageRole::System.as_str(), "system");
+        assert_eq!(MessageRole::parse("System"), Some(MessageRole::System));
+        assert_eq!(MessageRole::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_system_messages_are_sent_with_the_system_role() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::System, "Answer in French.");
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let config = crate::config::OllamaConfig {
+            user_prefix: "> ".to_string(),
+            ..crate::config::OllamaConfig::default()
+        };
+        let chat_messages = crate::ollama::request::build_chat_messages(&conversation.messages, &config);
+        assert_eq!(chat_messages[0].role, "system");
+        assert_eq!(chat_messages[0].content, "Answer in French.");
+        assert_eq!(chat_messages[1].content, "> Hello");
+
+        // System messages count as messages, but neither side's
+        let stats = conversation.stats();
+        assert_eq!(stats.message_count, 2);
+        assert_eq!(stats.user_messages, 1);
+        assert_eq!(stats.assistant_messages, 0);
+
+        // Conversations saved before system prompts existed still load
+        let mut value = serde_json::to_value(&conversation).unwrap();
+        value.as_object_mut().unwrap().remove("system_prompt");
+        let loaded: Conversation = serde_json::from_value(value).unwrap();
+        assert_eq!(loaded.system_prompt, None);
+        assert_eq!(loaded.messages[0].role, MessageRole::System);
+    }
+
+    fn fake_clock() -> Arc<FakeClock> {
+        Arc::new(FakeClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()))
     }
 
     #[test]
     fn test_message_creation() {
-        let message = Message::new(MessageRole::User, "Hello");
+        let clock = fake_clock();
+        let message = Message::with_clock(MessageRole::User, "Hello", &*clock);
         assert_eq!(message.role, MessageRole::User);
         assert_eq!(message.content, "Hello");
-        assert!(message.timestamp <= Utc::now());
+        assert_eq!(message.timestamp, clock.now());
     }
 
     #[test]
     fn test_conversation_creation() {
-        let conversation = Conversation::new("Test Conversation", "gpt-3.5-turbo");
+        let clock = fake_clock();
+        let conversation = Conversation::with_clock("Test Conversation", "gpt-3.5-turbo", clock.clone());
         assert_eq!(conversation.title, "Test Conversation");
         assert_eq!(conversation.model, "gpt-3.5-turbo");
         assert!(conversation.messages.is_empty());
-        assert!(conversation.created_at <= Utc::now());
+        assert_eq!(conversation.created_at, clock.now());
         assert_eq!(conversation.created_at, conversation.updated_at);
     }
 
     #[test]
     fn test_add_message() {
-        let mut conversation = Conversation::new("Test", "model");
-        let before_update = conversation.updated_at;
-        
-        // Wait a moment to ensure timestamp difference
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        let clock = fake_clock();
+        let mut conversation = Conversation::with_clock("Test", "model", clock.clone());
+        let created_at = conversation.created_at;
         
+        clock.advance(Duration::seconds(1));
         conversation.add_message(MessageRole::User, "Hello");
         assert_eq!(conversation.messages.len(), 1);
         assert_eq!(conversation.messages[0].role, MessageRole::User);
         assert_eq!(conversation.messages[0].content, "Hello");
-        assert!(conversation.updated_at > before_update);
+        assert_eq!(conversation.messages[0].timestamp, created_at + Duration::seconds(1));
+        assert_eq!(conversation.updated_at, created_at + Duration::seconds(1));
         
+        clock.advance(Duration::seconds(1));
         conversation.add_message(MessageRole::Assistant, "Hi there");
         assert_eq!(conversation.messages.len(), 2);
         assert_eq!(conversation.messages[1].role, MessageRole::Assistant);
         assert_eq!(conversation.messages[1].content, "Hi there");
+        assert_eq!(conversation.messages[1].timestamp, created_at + Duration::seconds(2));
+        assert_eq!(conversation.created_at, created_at);
+        assert_eq!(conversation.updated_at, created_at + Duration::seconds(2));
     }
 
     #[test]
@@ -309,28 +948,62 @@ mod tests {
         assert_eq!(conversation.messages[2].content, "Message 4");
     }
 
+    #[test]
+    fn test_truncate_to_chars() {
+        let mut conversation = Conversation::new("Test", "model");
+        for i in 0..3 {
+            conversation.add_message(MessageRole::User, &format!("Question {}", i));
+            conversation.add_message(MessageRole::Assistant, &format!("Response {}", i));
+        }
+
+        // Each message is 10 characters, so exactly 40 fit without dropping the one after them
+        assert_eq!(conversation.truncate_to_chars(40), 2);
+        assert_eq!(conversation.messages.len(), 4);
+        assert_eq!(conversation.messages[0].content, "Question 1");
+        assert_eq!(conversation.truncate_to_chars(39), 1);
+        assert_eq!(conversation.messages.len(), 3);
+        assert_eq!(conversation.messages[0].content, "Response 1");
+    }
+
+    #[test]
+    fn test_truncate_to_chars_keeps_last_pair_over_limit() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there");
+        conversation.add_message(MessageRole::User, &"x".repeat(100));
+        conversation.add_message(MessageRole::Assistant, "Too long");
+
+        // A single message over the limit drops everything before the last exchange, but not the exchange
+        conversation.truncate_to_chars(50);
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].content.len(), 100);
+
+        // A long question awaiting its answer keeps the answer before it
+        conversation.add_message(MessageRole::User, &"y".repeat(100));
+        conversation.truncate_to_chars(50);
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].content, "Too long");
+    }
+
     #[test]
     fn test_save_and_load() {
         // Create a temporary directory for the test
         let temp_dir = tempdir().unwrap();
-        let file_path = temp_dir.path().join("conversation.json");
         
         // Create a conversation with messages
         let mut conversation = Conversation::new("Test Save Load", "test-model");
         conversation.add_message(MessageRole::User, "Hello");
         conversation.add_message(MessageRole::Assistant, "Hi there");
-        
-        // Override the file path method for testing
-        let original_get_file_path = conversation.get_file_path();
         let conversation_id = conversation.id.clone();
         
-        // Save the conversation to the temporary file
-        let save_result = conversation.save();
+        // Save the conversation to the temporary directory
+        let save_result = conversation.save_in(temp_dir.path());
         assert!(save_result.is_ok());
-        assert!(original_get_file_path.exists());
+        let file_path = conversation.file_path_in(temp_dir.path());
+        assert!(file_path.exists());
         
         // Load the conversation from the file
-        let loaded = Conversation::load(&original_get_file_path);
+        let loaded = Conversation::load(&file_path);
         assert!(loaded.is_ok());
         
         let loaded_conversation = loaded.unwrap();
@@ -344,6 +1017,439 @@ mod tests {
         assert_eq!(loaded_conversation.messages[1].content, "Hi there");
     }
 
+    #[test]
+    fn test_find_prefers_id_then_exact_title_then_substring() {
+        let temp_dir = tempdir().unwrap();
+        let rust = Conversation::new("Rust lifetimes", "llama3.2");
+        let exact = Conversation::new("Rust", "llama3.2");
+        let mut named_like_id = Conversation::new("Trust issues", "llama3.2");
+        named_like_id.title = rust.id.clone();
+        for conversation in [&rust, &exact, &named_like_id] {
+            conversation.save_in(temp_dir.path()).unwrap();
+        }
+
+        // An ID beats a title equal to it
+        let found = Conversation::find_in(temp_dir.path(), &rust.id).unwrap().unwrap();
+        assert_eq!(found.id, rust.id);
+
+        // An exact title beats the titles containing it
+        let found = Conversation::find_in(temp_dir.path(), "Rust").unwrap().unwrap();
+        assert_eq!(found.id, exact.id);
+
+        // Substrings match ignoring case
+        let found = Conversation::find_in(temp_dir.path(), "LIFETIME").unwrap().unwrap();
+        assert_eq!(found.id, rust.id);
+
+        assert!(Conversation::find_in(temp_dir.path(), "Python").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_fails_on_ambiguous_title() {
+        let conversations = [
+            Conversation::new("Trip to Rome", "llama3.2"),
+            Conversation::new("Trip to Oslo", "llama3.2"),
+        ];
+
+        let error = Conversation::find_among(&conversations, "trip").unwrap_err();
+        assert!(error.to_string().contains("Trip to Rome"));
+        assert!(error.to_string().contains("Trip to Oslo"));
+
+        let found = Conversation::find_among(&conversations, "oslo").unwrap().unwrap();
+        assert_eq!(found.id, conversations[1].id);
+    }
+
+    #[test]
+    fn test_find_quick_switch_ignores_stale_slots() {
+        let conversations = [
+            Conversation::new("Work", "llama3.2"),
+            Conversation::new("Side project", "llama3.2"),
+        ];
+        let slots = BTreeMap::from([
+            (1, conversations[1].id.clone()),
+            (2, conversations[0].id.clone()),
+            (3, "deleted-conversation".to_string()),
+        ]);
+
+        let found = |slot| Conversation::find_quick_switch(&conversations, &slots, slot).map(|c| c.title.as_str());
+        assert_eq!(found(1), Some("Side project"));
+        assert_eq!(found(2), Some("Work"));
+        assert_eq!(found(3), None);
+        assert_eq!(found(4), None);
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("conversation.json");
+
+        let mut conversation = Conversation::new("Test Bookmarks", "model");
+        for i in 0..4 {
+            conversation.add_message(MessageRole::User, &format!("Message {}", i));
+        }
+        assert!(conversation.bookmarks().is_empty());
+
+        assert!(conversation.toggle_bookmark(1));
+        assert!(conversation.toggle_bookmark(3));
+        assert!(!conversation.toggle_bookmark(10));
+        assert_eq!(conversation.bookmarks(), vec![1, 3]);
+
+        // Bookmarks survive a save/load round trip
+        serde_json::to_writer(File::create(&file_path).unwrap(), &conversation).unwrap();
+        let loaded = Conversation::load(&file_path).unwrap();
+        assert!(loaded.messages[1].bookmarked);
+        assert!(!loaded.messages[2].bookmarked);
+        assert_eq!(loaded.bookmarks(), vec![1, 3]);
+
+        // Toggling again removes the bookmark
+        conversation.toggle_bookmark(1);
+        assert_eq!(conversation.bookmarks(), vec![3]);
+    }
+
+    #[test]
+    fn test_edit_message_and_truncate_after() {
+        let clock = fake_clock();
+        let mut conversation = Conversation::with_clock("Test", "model", clock.clone());
+        conversation.add_message(MessageRole::User, "What is 2+2?");
+        conversation.add_message(MessageRole::Assistant, "4");
+        conversation.add_message(MessageRole::User, "Waht about 3+3?");
+        conversation.add_message(MessageRole::Assistant, "I'm not sure what you mean");
+        conversation.add_message(MessageRole::User, "Thanks");
+
+        clock.advance(Duration::minutes(1));
+        assert!(conversation.edit_message(2, "What about 3+3?"));
+        assert_eq!(conversation.updated_at, clock.now());
+        conversation.truncate_after(2);
+
+        // The replies to the edited message are gone, and everything before it is kept
+        assert_eq!(conversation.messages.len(), 3);
+        assert_eq!(conversation.messages[1].content, "4");
+        assert_eq!(conversation.messages[2].content, "What about 3+3?");
+        assert!(conversation.messages.iter().all(|message| !message.content.contains("not sure")));
+
+        // Truncating after the last message, or editing past the end, changes nothing
+        conversation.truncate_after(2);
+        assert_eq!(conversation.messages.len(), 3);
+        conversation.truncate_after(10);
+        assert_eq!(conversation.messages.len(), 3);
+        assert!(!conversation.edit_message(3, "Out of range"));
+    }
+
+    #[test]
+    fn test_delete_in() {
+        let temp_dir = tempdir().unwrap();
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.save_in(temp_dir.path()).unwrap();
+        assert_eq!(Conversation::load_all_in(temp_dir.path()).unwrap().len(), 1);
+
+        conversation.delete_in(temp_dir.path()).unwrap();
+        assert!(Conversation::load_all_in(temp_dir.path()).unwrap().is_empty());
+
+        // Deleting again, or a conversation that was never saved, isn't an error
+        assert!(conversation.delete_in(temp_dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_prune_old_spares_kept_conversations() {
+        let temp_dir = tempdir().unwrap();
+        let clock = fake_clock();
+
+        // Oldest first: plain, pinned, bookmarked, plain, newest
+        let mut ids = Vec::new();
+        for title in ["old", "pinned", "bookmarked", "plain", "newest"] {
+            let mut conversation = Conversation::with_clock(title, "model", clock.clone());
+            conversation.add_message(MessageRole::User, title);
+            match title {
+                "pinned" => conversation.pinned = true,
+                "bookmarked" => {
+                    conversation.toggle_bookmark(0);
+                }
+                _ => {}
+            }
+            conversation.save_in(temp_dir.path()).unwrap();
+            ids.push(conversation.id.clone());
+            clock.advance(Duration::minutes(1));
+        }
+
+        let pruned = Conversation::prune_old_in(temp_dir.path(), 1, &Conversation::is_kept).unwrap();
+        assert_eq!(pruned, 2);
+
+        let remaining: Vec<String> = Conversation::load_all_in(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|conversation| conversation.title)
+            .collect();
+        assert_eq!(remaining, ["newest", "bookmarked", "pinned"]);
+
+        // Pruned conversations are moved to the trash rather than deleted
+        let trash = temp_dir.path().join(TRASH_DIR);
+        assert!(trash.join(format!("{}.json", ids[0])).exists());
+        assert!(trash.join(format!("{}.json", ids[3])).exists());
+    }
+
+    #[test]
+    fn test_prune_old_within_limit_or_disabled() {
+        let temp_dir = tempdir().unwrap();
+        for title in ["first", "second"] {
+            Conversation::new(title, "model").save_in(temp_dir.path()).unwrap();
+        }
+
+        assert_eq!(Conversation::prune_old_in(temp_dir.path(), 2, &Conversation::is_kept).unwrap(), 0);
+        assert_eq!(Conversation::prune_old_in(temp_dir.path(), 0, &Conversation::is_kept).unwrap(), 0);
+        assert_eq!(Conversation::load_all_in(temp_dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_message_without_bookmark_field_deserializes() {
+        let json = r#"{"role":"User","content":"Hello","timestamp":"2024-01-01T00:00:00Z"}"#;
+        let message: Message = serde_json::from_str(json).unwrap();
+        assert!(!message.bookmarked);
+    }
+
+    #[test]
+    fn test_move_message() {
+        let clock = fake_clock();
+        let mut conversation = Conversation::with_clock("Test", "model", clock.clone());
+        for i in 0..5 {
+            conversation.add_message(MessageRole::User, &format!("Message {}", i));
+        }
+        let contents = |conversation: &Conversation| {
+            conversation.messages.iter().map(|m| m.content.clone()).collect::<Vec<_>>()
+        };
+
+        // Moving down shifts the messages in between up
+        clock.advance(Duration::seconds(1));
+        assert!(conversation.move_message(1, 3));
+        assert_eq!(contents(&conversation), ["Message 0", "Message 2", "Message 3", "Message 1", "Message 4"]);
+        assert_eq!(conversation.updated_at, clock.now());
+
+        // Moving up shifts them down
+        assert!(conversation.move_message(4, 0));
+        assert_eq!(contents(&conversation), ["Message 4", "Message 0", "Message 2", "Message 3", "Message 1"]);
+
+        // Out-of-range indices are a no-op
+        clock.advance(Duration::seconds(1));
+        assert!(!conversation.move_message(5, 0));
+        assert!(!conversation.move_message(0, 5));
+        assert_eq!(contents(&conversation), ["Message 4", "Message 0", "Message 2", "Message 3", "Message 1"]);
+        assert_ne!(conversation.updated_at, clock.now());
+    }
+
+    #[test]
+    fn test_context_is_reused_only_for_the_next_message() {
+        let mut conversation = Conversation::new("Test", "llama3.2");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there");
+        conversation.set_context("llama3.2", vec![1, 2, 3]);
+        assert_eq!(conversation.reusable_context("llama3.2"), None);
+
+        conversation.add_message(MessageRole::User, "How are you?");
+        assert_eq!(conversation.reusable_context("llama3.2"), Some([1, 2, 3].as_slice()));
+        assert_eq!(conversation.reusable_context("mistral"), None);
+
+        // Editing the conversation means the context no longer matches it
+        conversation.truncate_after(0);
+        assert_eq!(conversation.reusable_context("llama3.2"), None);
+    }
+
+    #[test]
+    fn test_search_messages() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "How do I parse JSON in Rust?");
+        conversation.add_message(MessageRole::Assistant, "Use serde_json.");
+        conversation.add_message(MessageRole::User, "And json5?");
+
+        assert_eq!(conversation.search("json"), [0, 1, 2]);
+        assert_eq!(conversation.search("RUST"), [0]);
+        assert_eq!(conversation.search("serde_json."), [1]);
+        assert!(conversation.search("yaml").is_empty());
+        assert!(conversation.search("  ").is_empty());
+    }
+
+    #[test]
+    fn test_response_speed() {
+        let metadata = ResponseMetadata {
+            prompt_eval_count: Some(1200),
+            eval_count: Some(128),
+            eval_duration: Some(4_000_000_000),
+            ..ResponseMetadata::default()
+        };
+        assert_eq!(metadata.tokens_per_second(), Some(32.0));
+        assert_eq!(
+            metadata.speed_summary().as_deref(),
+            Some("128 tokens at 32.0 tokens/s, 1,200 prompt tokens")
+        );
+
+        // Older servers and OpenAI-compatible ones may not report timings
+        let metadata = ResponseMetadata { eval_duration: Some(0), ..metadata };
+        assert_eq!(metadata.tokens_per_second(), None);
+        assert_eq!(ResponseMetadata::default().speed_summary(), None);
+    }
+
+    #[test]
+    fn test_attach_metadata() {
+        let mut conversation = Conversation::new("Test Metadata", "model");
+        conversation.add_message(MessageRole::User, "First question");
+        conversation.add_message(MessageRole::Assistant, "First answer");
+        conversation.add_message(MessageRole::User, "Second question");
+        conversation.add_message(MessageRole::Assistant, "Second answer");
+
+        let metadata = ResponseMetadata {
+            model: "model".to_string(),
+            done_reason: Some("stop".to_string()),
+            eval_count: Some(42),
+            ..ResponseMetadata::default()
+        };
+        assert!(conversation.attach_metadata(3, metadata.clone()));
+        assert!(!conversation.attach_metadata(10, metadata.clone()));
+
+        assert!(conversation.messages[1].metadata.is_none());
+        assert_eq!(conversation.messages[3].metadata.as_ref(), Some(&metadata));
+
+        // Metadata survives serialization, and is omitted when absent
+        let json = serde_json::to_string(&conversation).unwrap();
+        let loaded: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.messages[3].metadata, Some(metadata));
+        assert!(!serde_json::to_string(&conversation.messages[1]).unwrap().contains("metadata"));
+    }
+
+    #[test]
+    fn test_final_chunk_done_reason_marks_truncated_response() {
+        let chunk: crate::ollama::models::ChatCompletionChunk = serde_json::from_str(
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":""},"done":true,"done_reason":"length","eval_count":128}"#,
+        )
+        .unwrap();
+
+        let mut conversation = Conversation::new("Test", "llama3.2");
+        conversation.add_message(MessageRole::User, "Tell me a long story");
+        conversation.add_message(MessageRole::Assistant, "Once upon a time");
+        assert!(conversation.attach_metadata(1, chunk.metadata()));
+
+        let json = serde_json::to_string(&conversation).unwrap();
+        let loaded: Conversation = serde_json::from_str(&json).unwrap();
+        let metadata = loaded.messages[1].metadata.as_ref().unwrap();
+        assert_eq!(metadata.done_reason.as_deref(), Some("length"));
+        assert!(metadata.hit_length_limit());
+
+        // Responses that finished on their own aren't truncated
+        let finished = ResponseMetadata {
+            done_reason: Some("stop".to_string()),
+            ..ResponseMetadata::default()
+        };
+        assert!(!finished.hit_length_limit());
+        assert!(!ResponseMetadata::default().hit_length_limit());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut conversation = Conversation::new("Test Stats", "model");
+        assert_eq!(conversation.stats(), ConversationStats::default());
+
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there, how are you?");
+
+        let stats = conversation.stats();
+        assert_eq!(stats.message_count, 2);
+        assert_eq!(stats.user_messages, 1);
+        assert_eq!(stats.assistant_messages, 1);
+        assert_eq!(stats.characters, 27);
+        assert_eq!(stats.estimated_tokens, 2 + 6);
+        assert_eq!(stats.word_count, 6);
+        assert_eq!(stats.user_words, 1);
+        assert_eq!(stats.assistant_words, 5);
+
+        // The context indicator's counts agree with the stats
+        assert_eq!(conversation.char_count(), stats.characters);
+        assert_eq!(conversation.approx_token_count(), stats.estimated_tokens);
+    }
+
+    #[test]
+    fn test_stats_word_counts_per_role() {
+        let mut conversation = Conversation::new("Words", "model");
+        conversation.add_message(MessageRole::User, "Write   a haiku -- please!");
+        conversation.add_message(MessageRole::Assistant, "");
+        conversation.add_message(MessageRole::Assistant, "Old pond,\nfrog jumps in...\nsplash.");
+
+        let stats = conversation.stats();
+        assert_eq!(stats.user_words, 4);
+        assert_eq!(stats.assistant_words, 6);
+        assert_eq!(stats.word_count, 10);
+    }
+
+    #[test]
+    fn test_reading_minutes_round_up() {
+        let minutes = |word_count| ConversationStats { word_count, ..Default::default() }.reading_minutes();
+        assert_eq!(minutes(0), 0);
+        assert_eq!(minutes(1), 1);
+        assert_eq!(minutes(200), 1);
+        assert_eq!(minutes(201), 2);
+        assert_eq!(minutes(1000), 5);
+    }
+
+    #[test]
+    fn test_fork_from() {
+        let mut conversation = Conversation::new("Original", "model");
+        for i in 0..4 {
+            conversation.add_message(MessageRole::User, &format!("Message {}", i));
+        }
+
+        let fork = conversation.fork_from(1).unwrap();
+        assert_ne!(fork.id, conversation.id);
+        assert_eq!(fork.title, "Original (fork)");
+        assert_eq!(fork.forked_from.as_deref(), Some(conversation.id.as_str()));
+        assert_eq!(fork.messages.len(), 2);
+        assert_eq!(fork.messages[1].content, "Message 1");
+        assert_eq!(conversation.messages.len(), 4);
+
+        assert!(conversation.fork_from(4).is_none());
+    }
+
+    #[test]
+    fn test_max_messages_rollover() {
+        let mut conversation = Conversation::new("Long Thread", "model");
+        let original_id = conversation.id.clone();
+
+        let mut archived = Vec::new();
+        for i in 0..5 {
+            let role = if i % 2 == 0 { MessageRole::User } else { MessageRole::Assistant };
+            if let Some(old) = conversation.add_message_capped(role, &format!("Message {}", i), 4) {
+                archived.push(old);
+            }
+        }
+
+        // Crossing the threshold rolls over exactly once
+        assert_eq!(archived.len(), 1);
+        let old = &archived[0];
+        assert_eq!(old.id, original_id);
+        assert!(old.archived);
+        assert_eq!(old.messages.len(), 4);
+
+        // The continuation links back and carries a summary of the old thread
+        assert_ne!(conversation.id, original_id);
+        assert!(!conversation.archived);
+        assert_eq!(conversation.continued_from.as_deref(), Some(original_id.as_str()));
+        assert_eq!(conversation.title, "Long Thread (continued)");
+        assert_eq!(conversation.model, "model");
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].role, MessageRole::Assistant);
+        assert!(conversation.messages[0].content.contains("Long Thread"));
+        assert!(conversation.messages[0].content.contains("assistant: Message 3"));
+        assert!(!conversation.messages[0].content.contains("Message 0"));
+        assert_eq!(conversation.messages[1].content, "Message 4");
+    }
+
+    #[test]
+    fn test_max_messages_disabled() {
+        let mut conversation = Conversation::new("Test", "model");
+        for i in 0..10 {
+            assert!(conversation
+                .add_message_capped(MessageRole::User, &format!("Message {}", i), 0)
+                .is_none());
+        }
+        assert_eq!(conversation.messages.len(), 10);
+        assert!(conversation.continued_from.is_none());
+    }
+
     #[test]
     fn test_summary() {
         let mut conversation = Conversation::new("Test Summary", "model");
@@ -360,4 +1466,44 @@ mod tests {
         conversation.add_message(MessageRole::Assistant, long_message);
         assert_eq!(conversation.summary(), "Test Summary - 2 messages - Last: This is a very long message that should be trun...");
     }
+
+    #[test]
+    fn test_summary_preview_len() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Short");
+        assert_eq!(conversation.summary_with_preview_len(10), "Test - 1 messages - Last: Short");
+
+        // Long ASCII content is cut to the configured length
+        conversation.add_message(MessageRole::Assistant, "abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(conversation.summary_with_preview_len(10), "Test - 2 messages - Last: abcdefg...");
+        assert_eq!(conversation.summary_with_preview_len(26), "Test - 2 messages - Last: abcdefghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    fn test_summary_multibyte_boundary() {
+        let mut conversation = Conversation::new("Test", "model");
+
+        // The old 47-byte cut-off falls inside the first two-byte character
+        let content = format!("{}éééé 日本語のテキスト 🎉🎉", "a".repeat(46));
+        conversation.add_message(MessageRole::User, &content);
+        let summary = conversation.summary();
+        assert!(summary.ends_with(&format!("{}é...", "a".repeat(46))));
+
+        // Exactly at the limit nothing is cut
+        let content = "é".repeat(10);
+        conversation.add_message(MessageRole::User, &content);
+        assert!(conversation.summary_with_preview_len(10).ends_with(&content));
+        assert!(conversation.summary_with_preview_len(9).ends_with("éééééé..."));
+    }
+
+    #[test]
+    fn test_summary_keeps_grapheme_clusters() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, &"👍🏽".repeat(20));
+        assert!(conversation.summary_with_preview_len(10).ends_with(&format!("{}...", "👍🏽".repeat(7))));
+
+        conversation.add_message(MessageRole::User, &"e\u{301}".repeat(20));
+        assert!(conversation.summary_with_preview_len(10).ends_with(&format!("{}...", "e\u{301}".repeat(7))));
+        assert_eq!(conversation.stats().characters, 40);
+    }
 }
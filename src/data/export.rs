@@ -0,0 +1,358 @@
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data::conversation::{Conversation, Message, MessageRole};
+use crate::utils::grapheme_truncate;
+use crate::utils::image::{decode_base64_image, image_extension};
+
+/// Maximum length of an exported file name, in grapheme clusters, before the extension
+const MAX_FILE_NAME_LEN: usize = 80;
+
+/// Characters that are not allowed in file names on at least one platform
+const RESERVED_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// File names reserved by Windows, regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Format of an exported conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Readable Markdown with a heading per message
+    Markdown,
+    /// The conversation as saved by the app
+    Json,
+}
+
+impl ExportFormat {
+    /// Get the file extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Turn a title into a file name that is safe on every platform, or None if nothing is left
+pub fn sanitize_file_name(title: &str) -> Option<String> {
+    let replaced: String = title
+        .chars()
+        .map(|c| if RESERVED_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+
+    // Leading dots hide files and trailing dots and spaces are dropped by Windows
+    let trimmed = replaced.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    let name = grapheme_truncate(trimmed, MAX_FILE_NAME_LEN)
+        .trim_end_matches(|c: char| c == '.' || c.is_whitespace());
+    if name.is_empty() {
+        return None;
+    }
+
+    let stem = name.split('.').next().unwrap_or(name);
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        return Some(format!("{}_{}", stem, &name[stem.len()..]));
+    }
+    Some(name.to_string())
+}
+
+/// Render a message as a Markdown section headed by its role
+fn markdown_section(message: &Message) -> String {
+    let role = match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+    };
+    format!("\n## {}\n\n{}\n", role, message.content)
+}
+
+impl Conversation {
+    /// Get the default directory conversations are exported to
+    pub fn get_export_dir() -> PathBuf {
+        let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push("screensage");
+        path.push("export");
+        path
+    }
+
+    /// Get the path the conversation is exported to under `root`, organized by creation month
+    pub fn export_path(&self, root: &Path, format: ExportFormat) -> PathBuf {
+        let name = sanitize_file_name(&self.title).unwrap_or_else(|| self.id.clone());
+        root.join(self.created_at.format("%Y").to_string())
+            .join(self.created_at.format("%m").to_string())
+            .join(format!("{}.{}", name, format.extension()))
+    }
+
+    /// Render the conversation in an export format
+    pub fn render_export(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Markdown => {
+                let mut markdown = format!(
+                    "# {}\n\n- Model: {}\n- Created: {}\n",
+                    self.title,
+                    self.model,
+                    self.created_at.format("%Y-%m-%d %H:%M UTC")
+                );
+                for message in &self.messages {
+                    markdown.push_str(&markdown_section(message));
+                }
+                Ok(markdown)
+            }
+            ExportFormat::Json => serde_json::to_string_pretty(self).context("Failed to serialize conversation"),
+        }
+    }
+
+    /// Render the conversation as `You:` and `Assistant:` paragraphs for pasting into a chat or email
+    ///
+    /// Messages with no text, like a response that hasn't started streaming, are left out.
+    pub fn to_plaintext(&self) -> String {
+        self.messages
+            .iter()
+            .filter(|message| !message.content.trim().is_empty())
+            .map(|message| {
+                let role = match message.role {
+                    MessageRole::User => "You",
+                    MessageRole::Assistant => "Assistant",
+                    MessageRole::System => "System",
+                };
+                format!("{}: {}", role, message.content.trim())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Render the messages at `indices` in an export format, in conversation order
+    ///
+    /// Duplicate indices are rendered once. Fails if nothing is selected or an
+    /// index is out of range.
+    pub fn export_messages(&self, indices: &[usize], format: ExportFormat) -> Result<String> {
+        if indices.is_empty() {
+            bail!("No messages selected");
+        }
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        let messages = indices
+            .iter()
+            .map(|&index| {
+                self.messages.get(index).with_context(|| {
+                    format!("Message {} is out of range ({} messages)", index, self.messages.len())
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        match format {
+            ExportFormat::Markdown => {
+                let mut markdown = format!("# {}\n", self.title);
+                for message in messages {
+                    markdown.push_str(&markdown_section(message));
+                }
+                Ok(markdown)
+            }
+            ExportFormat::Json => serde_json::to_string_pretty(&messages).context("Failed to serialize messages"),
+        }
+    }
+
+    /// Save the messages at `indices` as a Markdown snippet under `root`, returning the written path
+    pub fn export_snippet(&self, indices: &[usize], root: &Path) -> Result<PathBuf> {
+        let content = self.export_messages(indices, ExportFormat::Markdown)?;
+        let name = sanitize_file_name(&self.title).unwrap_or_else(|| self.id.clone());
+        let path = root
+            .join("snippets")
+            .join(format!("{}-{}.md", name, chrono::Utc::now().format("%Y%m%d-%H%M%S")));
+        write_file(&path, &content)?;
+
+        debug!("Exported {} messages of {} to {}", indices.len(), self.id, path.display());
+        Ok(path)
+    }
+
+    /// Save image `image_index` of the message at `message_index` under `root`, returning the written path
+    pub fn export_image(&self, message_index: usize, image_index: usize, root: &Path) -> Result<PathBuf> {
+        let Some(data) = self
+            .messages
+            .get(message_index)
+            .and_then(|message| message.images.get(image_index))
+        else {
+            bail!("No image {} in message {}", image_index, message_index);
+        };
+        let bytes = decode_base64_image(data)?;
+        let extension = image_extension(&bytes).unwrap_or("bin");
+        let name = sanitize_file_name(&self.title).unwrap_or_else(|| self.id.clone());
+        let path = root.join("images").join(format!(
+            "{}-{}-{}.{}",
+            name,
+            chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+            image_index + 1,
+            extension
+        ));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(&path, bytes).with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+        debug!("Saved image {} of message {} to {}", image_index, message_index, path.display());
+        Ok(path)
+    }
+
+    /// Export the conversation under `root` in `YYYY/MM/<title-or-id>.<ext>`, returning the written path
+    pub fn export_dated(&self, root: &Path, format: ExportFormat) -> Result<PathBuf> {
+        let path = self.export_path(root, format);
+        self.export_to(&path, format)?;
+        Ok(path)
+    }
+
+    /// Export every conversation under `root`, returning the written paths in order
+    ///
+    /// Conversations from the same month with the same title get their ID appended
+    /// so they don't overwrite each other.
+    pub fn export_all_dated(
+        conversations: &[Conversation],
+        root: &Path,
+        format: ExportFormat,
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = HashSet::new();
+        let mut paths = Vec::with_capacity(conversations.len());
+        for conversation in conversations {
+            let mut path = conversation.export_path(root, format);
+            if written.contains(&path) {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+                path.set_file_name(format!("{}-{}.{}", stem, conversation.id, format.extension()));
+            }
+            conversation.export_to(&path, format)?;
+            written.insert(path.clone());
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Write the conversation to `path`, creating its parent directories
+    fn export_to(&self, path: &Path, format: ExportFormat) -> Result<()> {
+        write_file(path, &self.render_export(format)?)?;
+
+        debug!("Exported conversation {} to {}", self.id, path.display());
+        Ok(())
+    }
+}
+
+/// Write an export to `path`, creating its parent directories
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write export: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::clock::FakeClock;
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn conversation(title: &str) -> Conversation {
+        let clock = Arc::new(FakeClock::new(Utc.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap()));
+        let mut conversation = Conversation::with_clock(title, "llama3.2", clock);
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there");
+        conversation
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("Plain title").as_deref(), Some("Plain title"));
+        assert_eq!(sanitize_file_name("a/b\\c:d*e?f\"g<h>i|j").as_deref(), Some("a_b_c_d_e_f_g_h_i_j"));
+        assert_eq!(sanitize_file_name("../secret").as_deref(), Some("_secret"));
+        assert_eq!(sanitize_file_name("tabs\tand\nnewlines").as_deref(), Some("tabs_and_newlines"));
+        assert_eq!(sanitize_file_name(" trailing dots... ").as_deref(), Some("trailing dots"));
+        assert_eq!(sanitize_file_name("con").as_deref(), Some("con_"));
+        assert_eq!(sanitize_file_name("NUL.txt").as_deref(), Some("NUL_.txt"));
+        assert_eq!(sanitize_file_name("Console").as_deref(), Some("Console"));
+        assert_eq!(sanitize_file_name(" . "), None);
+        assert_eq!(sanitize_file_name(&"é".repeat(200)).unwrap().chars().count(), MAX_FILE_NAME_LEN);
+    }
+
+    #[test]
+    fn test_export_path_is_organized_by_creation_month() {
+        let root = Path::new("export");
+        let titled = conversation("Rust / lifetimes?");
+        assert_eq!(
+            titled.export_path(root, ExportFormat::Markdown),
+            root.join("2024").join("03").join("Rust _ lifetimes_.md")
+        );
+
+        let untitled = conversation("...");
+        assert_eq!(
+            untitled.export_path(root, ExportFormat::Json),
+            root.join("2024").join("03").join(format!("{}.json", untitled.id))
+        );
+    }
+
+    #[test]
+    fn test_export_dated_writes_markdown() {
+        let temp_dir = tempdir().unwrap();
+        let path = conversation("Greeting").export_dated(temp_dir.path(), ExportFormat::Markdown).unwrap();
+
+        assert_eq!(path, temp_dir.path().join("2024/03/Greeting.md"));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Greeting\n"));
+        assert!(content.contains("\n## User\n\nHello\n"));
+        assert!(content.contains("\n## Assistant\n\nHi there\n"));
+    }
+
+    #[test]
+    fn test_to_plaintext() {
+        let mut titled = conversation("Plain");
+        titled.add_message(MessageRole::User, "Two lines\nplease\n");
+        // A response still waiting for its first chunk
+        titled.add_message(MessageRole::Assistant, "");
+
+        assert_eq!(
+            titled.to_plaintext(),
+            "You: Hello\n\nAssistant: Hi there\n\nYou: Two lines\nplease"
+        );
+        assert_eq!(Conversation::new("Empty", "model").to_plaintext(), "");
+    }
+
+    #[test]
+    fn test_export_messages_keeps_only_selected_in_order() {
+        let mut titled = conversation("Snippets");
+        titled.add_message(MessageRole::User, "How do I borrow?");
+        titled.add_message(MessageRole::Assistant, "Use a reference.");
+
+        let markdown = titled.export_messages(&[3, 0, 3], ExportFormat::Markdown).unwrap();
+        assert_eq!(
+            markdown,
+            "# Snippets\n\n## User\n\nHello\n\n## Assistant\n\nUse a reference.\n"
+        );
+
+        let json = titled.export_messages(&[2, 1], ExportFormat::Json).unwrap();
+        let messages: Vec<Message> = serde_json::from_str(&json).unwrap();
+        let contents: Vec<&str> = messages.iter().map(|message| message.content.as_str()).collect();
+        assert_eq!(contents, ["Hi there", "How do I borrow?"]);
+
+        assert!(titled.export_messages(&[], ExportFormat::Markdown).is_err());
+        assert!(titled.export_messages(&[1, 4], ExportFormat::Markdown).is_err());
+    }
+
+    #[test]
+    fn test_export_all_dated_keeps_duplicate_titles_apart() {
+        let temp_dir = tempdir().unwrap();
+        let first = conversation("Same");
+        let second = conversation("Same");
+        let paths =
+            Conversation::export_all_dated(&[first, second.clone()], temp_dir.path(), ExportFormat::Json).unwrap();
+
+        assert_eq!(paths[0], temp_dir.path().join("2024/03/Same.json"));
+        assert_eq!(paths[1], temp_dir.path().join(format!("2024/03/Same-{}.json", second.id)));
+        let exported = Conversation::load(&paths[1]).unwrap();
+        assert_eq!(exported.id, second.id);
+    }
+}
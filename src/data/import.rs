@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::fs;
+use std::path::Path;
+
+use crate::data::conversation::{Conversation, MessageRole};
+
+/// Get the text of a file's bytes, or None if they look binary
+fn text_content(bytes: Vec<u8>) -> Option<String> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+impl Conversation {
+    /// Create a conversation for each text file in `dir` and save them
+    ///
+    /// See `import_dir_into`.
+    pub fn import_dir(dir: &Path, model: &str) -> Result<Vec<Conversation>> {
+        Self::import_dir_into(dir, model, &Self::get_conversations_dir())
+    }
+
+    /// Create a conversation for each text file in `dir` and save them in `conversations_dir`
+    ///
+    /// Each conversation is titled after the file name and starts with the file's
+    /// content as a user message. Binary and empty files and subdirectories are
+    /// skipped. Files are imported in name order.
+    pub fn import_dir_into(dir: &Path, model: &str, conversations_dir: &Path) -> Result<Vec<Conversation>> {
+        let mut paths = Vec::new();
+        let entries = fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut conversations = Vec::new();
+        for path in paths {
+            let bytes =
+                fs::read(&path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+            let Some(content) = text_content(bytes) else {
+                debug!("Skipping binary file {}", path.display());
+                continue;
+            };
+            if content.trim().is_empty() {
+                debug!("Skipping empty file {}", path.display());
+                continue;
+            }
+
+            let title = path.file_stem().unwrap_or_default().to_string_lossy();
+            let mut conversation = Conversation::new(&title, model);
+            conversation.add_message(MessageRole::User, content.trim_end());
+            conversation.save_in(conversations_dir)?;
+            conversations.push(conversation);
+        }
+
+        info!("Imported {} conversations from {}", conversations.len(), dir.display());
+        Ok(conversations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_import_dir_creates_a_conversation_per_text_file() {
+        let notes = tempdir().unwrap();
+        let saved = tempdir().unwrap();
+        fs::write(notes.path().join("groceries.txt"), "Eggs\nMilk\n").unwrap();
+        fs::write(notes.path().join("ideas.md"), "# Ideas\nA floating chat window").unwrap();
+        fs::write(notes.path().join("photo.png"), [0x89, b'P', b'N', b'G', 0x00, 0x1a]).unwrap();
+        fs::write(notes.path().join("latin1.txt"), [b'c', b'a', b'f', 0xe9]).unwrap();
+        fs::write(notes.path().join("blank.txt"), "  \n").unwrap();
+        fs::create_dir(notes.path().join("nested")).unwrap();
+
+        let imported = Conversation::import_dir_into(notes.path(), "llama3.2", saved.path()).unwrap();
+
+        let titles: Vec<&str> = imported.iter().map(|conversation| conversation.title.as_str()).collect();
+        assert_eq!(titles, ["groceries", "ideas"]);
+        assert_eq!(imported[0].messages.len(), 1);
+        assert_eq!(imported[0].messages[0].role, MessageRole::User);
+        assert_eq!(imported[0].messages[0].content, "Eggs\nMilk");
+        assert_eq!(imported[1].model, "llama3.2");
+        assert_ne!(imported[0].id, imported[1].id);
+
+        // Both conversations were saved
+        let loaded = Conversation::load_all_in(saved.path()).unwrap();
+        assert_eq!(loaded.len(), 2);
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data::logger::get_log_dir;
+use crate::ollama::error::ResponseError;
+use crate::utils::text::truncate_chars;
+
+/// File name of the persisted failed requests, kept next to the regular log files
+const ISSUES_FILE_NAME: &str = "failed-requests.json";
+
+/// Number of failed requests kept before the oldest are dropped
+pub const MAX_FAILED_REQUESTS: usize = 50;
+
+/// Number of characters of the prompt shown in the Issues panel
+const PROMPT_PREVIEW_CHARS: usize = 80;
+
+/// A request to Ollama that failed, kept for later inspection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedRequest {
+    /// When the request failed
+    pub timestamp: DateTime<Utc>,
+    /// Model the request was sent to
+    pub model: String,
+    /// Why the request failed
+    pub error: ResponseError,
+    /// The user message the request answered
+    pub prompt: String,
+}
+
+impl FailedRequest {
+    /// Get the start of the prompt on a single line
+    pub fn prompt_preview(&self) -> String {
+        let mut preview = self.prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        if truncate_chars(&mut preview, PROMPT_PREVIEW_CHARS) {
+            preview.push('…');
+        }
+        preview
+    }
+}
+
+/// Log of the most recent failed requests, newest last
+#[derive(Debug, Clone)]
+pub struct FailedRequestLog {
+    /// Failed requests, oldest first
+    entries: VecDeque<FailedRequest>,
+    /// Number of entries kept
+    capacity: usize,
+    /// File the log is saved to after each change, if it persists across restarts
+    path: Option<PathBuf>,
+}
+
+impl FailedRequestLog {
+    /// Create an empty log kept in memory only
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+            path: None,
+        }
+    }
+
+    /// Create a log saved to `path`, starting with the entries already saved there
+    pub fn persistent(path: PathBuf, capacity: usize) -> Self {
+        let mut log = Self::new(capacity);
+        match load_entries(&path) {
+            Ok(entries) => {
+                log.entries = entries.into();
+                log.evict();
+            }
+            Err(e) => debug!("Starting a new failed request log: {}", e),
+        }
+        log.path = Some(path);
+        log
+    }
+
+    /// Create a log saved to the default location in the log directory
+    pub fn in_log_dir(capacity: usize) -> Self {
+        Self::persistent(get_log_dir().join(ISSUES_FILE_NAME), capacity)
+    }
+
+    /// Get the failed requests, oldest first
+    pub fn entries(&self) -> &VecDeque<FailedRequest> {
+        &self.entries
+    }
+
+    /// Get the failed request at `index`
+    pub fn get(&self, index: usize) -> Option<&FailedRequest> {
+        self.entries.get(index)
+    }
+
+    /// Add a failed request, dropping the oldest ones past the capacity
+    pub fn record(&mut self, model: &str, error: ResponseError, prompt: &str) -> Result<()> {
+        self.entries.push_back(FailedRequest {
+            timestamp: Utc::now(),
+            model: model.to_string(),
+            error,
+            prompt: prompt.to_string(),
+        });
+        self.evict();
+        self.save()
+    }
+
+    /// Remove the failed request at `index`, returning it
+    pub fn remove(&mut self, index: usize) -> Result<Option<FailedRequest>> {
+        let removed = self.entries.remove(index);
+        self.save()?;
+        Ok(removed)
+    }
+
+    /// Remove every failed request
+    pub fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        self.save()
+    }
+
+    /// Drop the oldest entries past the capacity
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Save the entries, if the log persists
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let json = serde_json::to_string_pretty(&self.entries).context("Failed to serialize failed requests")?;
+        fs::write(path, json).with_context(|| format!("Failed to write failed requests: {}", path.display()))
+    }
+}
+
+/// Read the failed requests saved at `path`
+fn load_entries(path: &Path) -> Result<Vec<FailedRequest>> {
+    let json =
+        fs::read_to_string(path).with_context(|| format!("Failed to read failed requests: {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Failed to parse failed requests: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_failures_are_recorded_and_capped() {
+        let mut log = FailedRequestLog::new(2);
+        log.record("llama3.2", ResponseError::Empty, "Hello there").unwrap();
+        log.record("mistral", ResponseError::Failed("Stream error".to_string()), "What is\nRust?")
+            .unwrap();
+
+        assert_eq!(log.entries().len(), 2);
+        assert_eq!(log.entries()[0].model, "llama3.2");
+        assert_eq!(log.entries()[0].error, ResponseError::Empty);
+        assert_eq!(log.entries()[0].prompt, "Hello there");
+        assert_eq!(log.entries()[1].model, "mistral");
+        assert_eq!(log.entries()[1].error, ResponseError::Failed("Stream error".to_string()));
+        assert_eq!(log.entries()[1].prompt_preview(), "What is Rust?");
+        assert!(log.entries()[0].timestamp <= log.entries()[1].timestamp);
+
+        // A third failure evicts the oldest
+        log.record("phi3", ResponseError::Empty, "Third").unwrap();
+        let models: Vec<&str> = log.entries().iter().map(|entry| entry.model.as_str()).collect();
+        assert_eq!(models, ["mistral", "phi3"]);
+    }
+
+    #[test]
+    fn test_persistent_log_survives_restart() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(ISSUES_FILE_NAME);
+
+        let mut log = FailedRequestLog::persistent(path.clone(), 10);
+        log.record("llama3.2", ResponseError::Empty, "Hello").unwrap();
+        log.record("llama3.2", ResponseError::Empty, "Again").unwrap();
+        log.remove(0).unwrap();
+
+        let reloaded = FailedRequestLog::persistent(path, 10);
+        assert_eq!(reloaded.entries(), log.entries());
+        assert_eq!(reloaded.entries()[0].prompt, "Again");
+    }
+}
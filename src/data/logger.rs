@@ -1,14 +1,21 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local, Utc};
 use fern::colors::{Color, ColoredLevelConfig};
-use log::LevelFilter;
+use log::{warn, Level, LevelFilter};
 use std::env;
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use crate::config::Config;
 
+/// Size of the blocks read backwards from the end of a log file
+const TAIL_BLOCK_SIZE: u64 = 8 * 1024;
+
+/// Log file written by the installed logger
+static ACTIVE_LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
 /// Logger configuration
 #[derive(Debug, Clone)]
 pub struct Logger {
@@ -40,8 +47,7 @@ impl Logger {
         };
 
         // Determine log directory
-        let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
-        let log_dir = current_dir.join(".local").join("share").join("screensage").join("logs");
+        let log_dir = get_log_dir();
 
         Self {
             level,
@@ -88,6 +94,7 @@ impl Logger {
             // Generate log file path with current date
             let log_file_path = self.get_log_file_path()?;
             self.current_log_file = Some(log_file_path.clone());
+            let _ = ACTIVE_LOG_FILE.set(log_file_path.clone());
 
             // Create file logger with detailed format
             let file_dispatch = fern::Dispatch::new()
@@ -106,8 +113,10 @@ impl Logger {
             dispatch = dispatch.chain(file_dispatch);
         }
 
-        // Apply the logger configuration
-        dispatch.apply();
+        // Apply the logger configuration, keeping the one already installed, e.g. by another test
+        if let Err(e) = dispatch.apply() {
+            warn!("Not installing the logger: {}", e);
+        }
 
         // Perform log rotation if needed
         if self.log_to_file {
@@ -117,6 +126,11 @@ impl Logger {
         Ok(())
     }
 
+    /// Get the path of the file being logged to, if file logging is enabled
+    pub fn current_log_path(&self) -> Option<&Path> {
+        self.current_log_file.as_deref()
+    }
+
     /// Get the path for the current log file
     fn get_log_file_path(&self) -> Result<PathBuf> {
         let today = Local::now().format("%Y-%m-%d").to_string();
@@ -157,7 +171,7 @@ impl Logger {
         }
         
         // Sort by modification time (oldest first)
-        log_files.sort_by(|a, b| a.1.cmp(&b.1));
+        log_files.sort_by_key(|file| file.1);
         
         // Remove oldest files if we have more than max_files
         if log_files.len() > self.max_files as usize {
@@ -189,6 +203,52 @@ impl Logger {
     }
 }
 
+/// Get the directory where log files are written
+pub fn get_log_dir() -> PathBuf {
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    current_dir.join(".local").join("share").join("screensage").join("logs")
+}
+
+/// Get the file written by the logger installed with `init`, if file logging is enabled
+pub fn active_log_path() -> Option<&'static Path> {
+    ACTIVE_LOG_FILE.get().map(PathBuf::as_path)
+}
+
+/// Read the last `count` lines of a file without loading all of it
+pub fn tail_lines(path: &Path, count: usize) -> Result<Vec<String>> {
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+    let len = file
+        .metadata()
+        .with_context(|| format!("Failed to get metadata for log file: {}", path.display()))?
+        .len();
+
+    // Read blocks from the end until there are enough line breaks, ignoring a trailing one
+    let mut start = len;
+    let mut buffer = Vec::new();
+    while start > 0 && buffer.iter().filter(|&&b| b == b'\n').count() <= count {
+        let block = TAIL_BLOCK_SIZE.min(start);
+        start -= block;
+        let mut chunk = vec![0; block as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)
+            .with_context(|| format!("Failed to read log file: {}", path.display()))?;
+        chunk.extend_from_slice(&buffer);
+        buffer = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buffer);
+    let lines: Vec<&str> = text.lines().collect();
+    // A partial first line only appears when reading stopped before the start of the file
+    let skip = lines.len().saturating_sub(count);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+/// Get the level of a line written by the file logger
+pub fn line_level(line: &str) -> Option<Level> {
+    line.split(',').nth(1)?.parse().ok()
+}
+
 /// Initialize the logger with the given configuration
 pub fn init_logger(config: &Config) -> Result<Logger> {
     let mut logger = Logger::new(config);
@@ -311,4 +371,32 @@ mod tests {
         
         assert_eq!(log_files.len(), 3);
     }
+
+    #[test]
+    fn test_tail_lines() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tail.log");
+
+        // Enough lines to span several blocks
+        let content: String = (1..=5000).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, &content).unwrap();
+        assert_eq!(tail_lines(&path, 3).unwrap(), ["line 4998", "line 4999", "line 5000"]);
+        assert_eq!(tail_lines(&path, 2000).unwrap().first().unwrap(), "line 3001");
+        assert!(tail_lines(&path, 0).unwrap().is_empty());
+
+        // Fewer lines than requested, without a trailing newline
+        fs::write(&path, "first\nsecond").unwrap();
+        assert_eq!(tail_lines(&path, 10).unwrap(), ["first", "second"]);
+
+        fs::write(&path, "").unwrap();
+        assert!(tail_lines(&path, 10).unwrap().is_empty());
+        assert!(tail_lines(&temp_dir.path().join("missing.log"), 10).is_err());
+    }
+
+    #[test]
+    fn test_line_level() {
+        assert_eq!(line_level("2024-01-01 00:00:00.000,WARN,screensage::app,42,Slow"), Some(Level::Warn));
+        assert_eq!(line_level("2024-01-01 00:00:00.000,ERROR,screensage,0,a, b"), Some(Level::Error));
+        assert_eq!(line_level("continuation of a multi-line message"), None);
+    }
 }
@@ -1,3 +1,12 @@
+pub mod autosave;
+pub mod clock;
 pub mod conversation;
+pub mod export;
+pub mod import;
+pub mod issues;
 pub mod logger;
-
+pub mod outline;
+pub mod search;
+pub mod storage;
+pub mod trace;
+pub mod transcript;
@@ -0,0 +1,105 @@
+use crate::data::conversation::{Conversation, MessageRole};
+use crate::utils::markdown::parse_heading;
+use crate::utils::text::strip_markdown;
+
+/// A heading of an assistant response, listed in the conversation outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    /// Index of the message containing the heading
+    pub message_index: usize,
+    /// Heading level, from 1 for `#` to 6 for `######`
+    pub depth: usize,
+    /// Heading text without Markdown syntax
+    pub title: String,
+}
+
+/// Get the headings of Markdown content as levels and plain text, skipping fenced code blocks
+pub fn markdown_headings(content: &str) -> Vec<(usize, String)> {
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        if let Some((depth, title)) = parse_heading(line) {
+            let title = strip_markdown(title);
+            if !title.is_empty() {
+                headings.push((depth, title));
+            }
+        }
+    }
+
+    headings
+}
+
+impl Conversation {
+    /// Get the headings of the assistant responses, in conversation order
+    pub fn outline(&self) -> Vec<OutlineEntry> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.role == MessageRole::Assistant)
+            .flat_map(|(message_index, message)| {
+                markdown_headings(&message.content)
+                    .into_iter()
+                    .map(move |(depth, title)| OutlineEntry {
+                        message_index,
+                        depth,
+                        title,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_headings_of_varying_depth() {
+        let content = concat!(
+            "# Setup\nIntro\n## Install *the* tools ##\n  ### Configure `cargo`\n",
+            "#### \n###### Deep\n####### Too deep\n#hashtag\n    # Indented code",
+        );
+        assert_eq!(
+            markdown_headings(content),
+            [
+                (1, "Setup".to_string()),
+                (2, "Install the tools".to_string()),
+                (3, "Configure cargo".to_string()),
+                (6, "Deep".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_headings_skip_code_fences() {
+        let content = "## Example\n```bash\n# install\ncargo build\n```\n## C# tips #\n```\n# unclosed";
+        assert_eq!(
+            markdown_headings(content),
+            [(2, "Example".to_string()), (2, "C# tips".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_outline_lists_assistant_headings_by_message() {
+        let mut conversation = Conversation::new("Outline", "llama3.2");
+        conversation.add_message(MessageRole::User, "# Not an answer");
+        conversation.add_message(MessageRole::Assistant, "# Overview\n## Details");
+        conversation.add_message(MessageRole::User, "More?");
+        conversation.add_message(MessageRole::Assistant, "### Extra");
+
+        let outline = conversation.outline();
+        let entries: Vec<(usize, usize, &str)> = outline
+            .iter()
+            .map(|entry| (entry.message_index, entry.depth, entry.title.as_str()))
+            .collect();
+        assert_eq!(entries, [(1, 1, "Overview"), (1, 2, "Details"), (3, 3, "Extra")]);
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::{Context, Result};
+use log::error;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::data::conversation::Conversation;
+
+/// Maximum number of hits returned by a search; later matches are not looked for
+pub const MAX_SEARCH_HITS: usize = 200;
+
+/// Number of words of context kept on each side of a match
+const CONTEXT_WORDS: usize = 5;
+
+/// Maximum characters of context kept on each side of a match
+const MAX_CONTEXT_CHARS: usize = 60;
+
+/// A match of a search query within a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// ID of the conversation containing the match
+    pub conversation_id: String,
+    /// Title of the conversation containing the match
+    pub conversation_title: String,
+    /// Index of the matching message within the conversation
+    pub message_index: usize,
+    /// Byte range of the match within the message content
+    pub match_range: Range<usize>,
+    /// A few words of the message leading up to the match
+    pub context_before: String,
+    /// The matched text as it appears in the message
+    pub matched: String,
+    /// A few words of the message following the match
+    pub context_after: String,
+}
+
+impl SearchHit {
+    /// Get the snippet shown for the hit and the byte range of the match within it
+    pub fn snippet(&self) -> (String, Range<usize>) {
+        let start = self.context_before.len();
+        let snippet = format!("{}{}{}", self.context_before, self.matched, self.context_after);
+        (snippet, start..start + self.matched.len())
+    }
+}
+
+/// Search the messages of every conversation for `query`, ignoring case
+///
+/// Returns up to `MAX_SEARCH_HITS` hits in conversation order, then message
+/// order, then match order.
+pub fn search_all(conversations: &[Conversation], query: &str) -> Vec<SearchHit> {
+    let Some(pattern) = query_pattern(query) else {
+        return Vec::new();
+    };
+
+    conversations
+        .iter()
+        .flat_map(|conversation| search_conversation(conversation, &pattern))
+        .take(MAX_SEARCH_HITS)
+        .collect()
+}
+
+/// Put the hits of the conversation being shown first, found in `current` rather than its saved file
+///
+/// The shown conversation may have changes that aren't saved yet, so its saved hits are replaced.
+pub fn with_current_conversation(saved: Vec<SearchHit>, current: &Conversation, query: &str) -> Vec<SearchHit> {
+    let mut hits = search_all(std::slice::from_ref(current), query);
+    hits.extend(saved.into_iter().filter(|hit| hit.conversation_id != current.id));
+    hits.truncate(MAX_SEARCH_HITS);
+    hits
+}
+
+/// Search the conversations saved in `dir` for `query`, ignoring case, without loading them all
+///
+/// Files are read one at a time, and only those whose text contains the query
+/// are parsed. Reading stops once `MAX_SEARCH_HITS` hits are found, so hits
+/// come in directory order. Files that can't be read or parsed are skipped.
+pub fn search_saved_in(dir: &Path, query: &str) -> Result<Vec<SearchHit>> {
+    let Some(pattern) = query_pattern(query) else {
+        return Ok(Vec::new());
+    };
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    // Queries with quotes, backslashes or control characters are escaped in the file,
+    // so a file can only be skipped unparsed when the query is written as typed
+    let written_as_typed = serde_json::to_string(query).is_ok_and(|json| json[1..json.len() - 1] == *query);
+
+    let mut hits = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                error!("Failed to read an entry of {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("Failed to read conversation from {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if written_as_typed && !pattern.is_match(&text) {
+            continue;
+        }
+        match serde_json::from_str::<Conversation>(&text) {
+            Ok(conversation) => hits.extend(search_conversation(&conversation, &pattern)),
+            Err(e) => error!("Failed to parse conversation from {}: {}", path.display(), e),
+        }
+        if hits.len() >= MAX_SEARCH_HITS {
+            hits.truncate(MAX_SEARCH_HITS);
+            break;
+        }
+    }
+    Ok(hits)
+}
+
+/// Build a case-insensitive pattern matching the query literally
+pub(crate) fn query_pattern(query: &str) -> Option<Regex> {
+    if query.trim().is_empty() {
+        return None;
+    }
+    RegexBuilder::new(&regex::escape(query)).case_insensitive(true).build().ok()
+}
+
+/// Find every match of `pattern` in the messages of a conversation
+fn search_conversation(conversation: &Conversation, pattern: &Regex) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    for (message_index, message) in conversation.messages.iter().enumerate() {
+        let content = &message.content;
+        for found in pattern.find_iter(content) {
+            hits.push(SearchHit {
+                conversation_id: conversation.id.clone(),
+                conversation_title: conversation.title.clone(),
+                message_index,
+                match_range: found.range(),
+                context_before: context_before(content, found.start()).to_string(),
+                matched: found.as_str().to_string(),
+                context_after: context_after(content, found.end()).to_string(),
+            });
+        }
+    }
+    hits
+}
+
+/// Get up to `CONTEXT_WORDS` words of `text` ending at byte `end`
+fn context_before(text: &str, end: usize) -> &str {
+    let before = &text[..end];
+    let mut words = 0;
+    let mut in_word = false;
+    let mut start = 0;
+    for (index, c) in before.char_indices().rev() {
+        if !c.is_whitespace() {
+            in_word = true;
+            continue;
+        }
+        if in_word {
+            words += 1;
+            in_word = false;
+            if words == CONTEXT_WORDS {
+                start = index + c.len_utf8();
+                break;
+            }
+        }
+    }
+
+    // Keep very long words from swamping the snippet
+    let context = &before[start..];
+    match context.char_indices().rev().nth(MAX_CONTEXT_CHARS - 1) {
+        Some((index, _)) => &context[index..],
+        None => context,
+    }
+}
+
+/// Get up to `CONTEXT_WORDS` words of `text` starting at byte `start`
+fn context_after(text: &str, start: usize) -> &str {
+    let after = &text[start..];
+    let mut words = 0;
+    let mut in_word = false;
+    let mut end = after.len();
+    for (index, c) in after.char_indices() {
+        if !c.is_whitespace() {
+            in_word = true;
+            continue;
+        }
+        if in_word {
+            words += 1;
+            in_word = false;
+            if words == CONTEXT_WORDS {
+                end = index;
+                break;
+            }
+        }
+    }
+
+    // Keep very long words from swamping the snippet
+    let context = &after[..end];
+    match context.char_indices().nth(MAX_CONTEXT_CHARS) {
+        Some((index, _)) => &context[..index],
+        None => context,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::conversation::MessageRole;
+
+    fn conversation(messages: &[&str]) -> Conversation {
+        let mut conversation = Conversation::new("Search Test", "model");
+        for message in messages {
+            conversation.add_message(MessageRole::User, message);
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_context_in_the_middle_of_a_long_message() {
+        let content = "one two three four five six seven eight NEEDLE nine ten eleven twelve thirteen fourteen fifteen";
+        let hits = search_all(&[conversation(&[content])], "needle");
+
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.context_before, "four five six seven eight ");
+        assert_eq!(hit.matched, "NEEDLE");
+        assert_eq!(hit.context_after, " nine ten eleven twelve thirteen");
+        assert_eq!(&content[hit.match_range.clone()], "NEEDLE");
+
+        let (snippet, range) = hit.snippet();
+        assert_eq!(snippet, "four five six seven eight NEEDLE nine ten eleven twelve thirteen");
+        assert_eq!(&snippet[range], "NEEDLE");
+    }
+
+    #[test]
+    fn test_context_near_the_start_and_end() {
+        let hits = search_all(&[conversation(&["Needle at the start", "ends with the needle"])], "needle");
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].context_before, "");
+        assert_eq!(hits[0].context_after, " at the start");
+        assert_eq!(hits[1].message_index, 1);
+        assert_eq!(hits[1].context_before, "ends with the ");
+        assert_eq!(hits[1].context_after, "");
+    }
+
+    #[test]
+    fn test_context_is_char_boundary_safe() {
+        let content = "Ünïcödé wörds 日本語 ahead of the café 🎉 and more émoji 👍🏽 trailing wörds here too";
+        let hits = search_all(&[conversation(&[content])], "CAFÉ");
+
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.matched, "café");
+        assert_eq!(&content[hit.match_range.clone()], "café");
+        assert_eq!(hit.context_before, "wörds 日本語 ahead of the ");
+        assert_eq!(hit.context_after, " 🎉 and more émoji 👍🏽");
+    }
+
+    #[test]
+    fn test_long_words_are_capped() {
+        let long_word = "é".repeat(200);
+        let content = format!("{}needle{}", long_word, long_word);
+        let hits = search_all(&[conversation(&[&content])], "needle");
+
+        assert_eq!(hits[0].context_before.chars().count(), MAX_CONTEXT_CHARS);
+        assert_eq!(hits[0].context_after.chars().count(), MAX_CONTEXT_CHARS);
+    }
+
+    #[test]
+    fn test_search_all_across_conversations() {
+        let first = conversation(&["a needle", "no match", "needle and needle"]);
+        let second = conversation(&["another NEEDLE"]);
+        let hits = search_all(&[first.clone(), second.clone()], "Needle");
+
+        let locations: Vec<(&str, usize)> = hits
+            .iter()
+            .map(|hit| (hit.conversation_id.as_str(), hit.message_index))
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                (first.id.as_str(), 0),
+                (first.id.as_str(), 2),
+                (first.id.as_str(), 2),
+                (second.id.as_str(), 0),
+            ]
+        );
+        assert!(search_all(&[first], "  ").is_empty());
+    }
+
+    #[test]
+    fn test_search_saved_conversations() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = conversation(&["Where is the needle?", "In the haystack"]);
+        let second = conversation(&["Nothing here", "A NEEDLE, \"quoted\""]);
+        first.save_in(dir.path()).unwrap();
+        second.save_in(dir.path()).unwrap();
+        // Files without the query are skipped before parsing, so this one isn't an error
+        fs::write(dir.path().join("broken.json"), "{ not json").unwrap();
+        // Files that can't be read are skipped too
+        fs::create_dir(dir.path().join("unreadable.json")).unwrap();
+
+        let mut locations: Vec<(String, usize)> = search_saved_in(dir.path(), "needle")
+            .unwrap()
+            .into_iter()
+            .map(|hit| (hit.conversation_id, hit.message_index))
+            .collect();
+        locations.sort();
+        let mut expected = vec![(first.id.clone(), 0), (second.id.clone(), 1)];
+        expected.sort();
+        assert_eq!(locations, expected);
+
+        // Quotes are escaped in the file, and still found
+        let hits = search_saved_in(dir.path(), "\"quoted\"").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, second.id);
+
+        assert!(search_saved_in(dir.path(), "yaml").unwrap().is_empty());
+        assert!(search_saved_in(&dir.path().join("missing"), "needle").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_current_conversation_hits_come_from_memory() {
+        let saved = conversation(&["An old needle"]);
+        let mut current = conversation(&["Another needle"]);
+        let stale = search_all(&[saved.clone(), current.clone()], "needle");
+        current.add_message(MessageRole::User, "A needle not saved yet");
+
+        let hits = with_current_conversation(stale, &current, "needle");
+        let locations: Vec<(&str, usize)> =
+            hits.iter().map(|hit| (hit.conversation_id.as_str(), hit.message_index)).collect();
+        assert_eq!(
+            locations,
+            [(current.id.as_str(), 0), (current.id.as_str(), 1), (saved.id.as_str(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_search_stops_at_the_hit_limit() {
+        let many = conversation(&[&"needle ".repeat(MAX_SEARCH_HITS + 1)]);
+        assert_eq!(search_all(&[many.clone(), many.clone()], "needle").len(), MAX_SEARCH_HITS);
+
+        let dir = tempfile::tempdir().unwrap();
+        many.save_in(dir.path()).unwrap();
+        assert_eq!(search_saved_in(dir.path(), "needle").unwrap().len(), MAX_SEARCH_HITS);
+    }
+}
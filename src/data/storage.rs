@@ -0,0 +1,209 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{get_config_path, save_config, Config};
+use crate::data::conversation::Conversation;
+use crate::data::search::{search_saved_in, SearchHit};
+
+/// Name of the file written and removed to check that a directory is writable
+const PROBE_FILE_NAME: &str = ".screensage-write-test";
+
+/// Where conversations and the configuration are saved, if they can be
+///
+/// On locked-down systems the data or config directory may not be writable.
+/// That is detected once at startup, and saves are then skipped quietly so
+/// the app keeps working in memory instead of failing on every message.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    /// Directory conversations are saved in
+    conversations_dir: PathBuf,
+    /// File the configuration is saved to
+    config_path: PathBuf,
+    /// Whether both can be written; when false nothing is saved
+    persistent: bool,
+}
+
+impl Storage {
+    /// Check whether the default conversation and configuration locations are writable
+    pub fn detect() -> Self {
+        Self::probe(Conversation::get_conversations_dir(), get_config_path())
+    }
+
+    /// Keep conversations and the configuration under `dir`, for running against a scratch directory
+    pub fn in_dir(dir: &Path) -> Self {
+        Self::probe(dir.join("conversations"), dir.join("config.toml"))
+    }
+
+    /// Check whether `conversations_dir` and the directory of `config_path` are writable
+    ///
+    /// Warns once if either isn't, rather than on each save.
+    pub fn probe(conversations_dir: PathBuf, config_path: PathBuf) -> Self {
+        let config_dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let unwritable = [&conversations_dir, &config_dir]
+            .into_iter()
+            .find_map(|dir| check_writable(dir).err());
+        if let Some(e) = &unwritable {
+            warn!("Storage isn't writable, keeping conversations in memory only: {:#}", e);
+        }
+        Self {
+            conversations_dir,
+            config_path,
+            persistent: unwritable.is_none(),
+        }
+    }
+
+    /// Check whether changes are saved to disk
+    pub fn is_persistent(&self) -> bool {
+        self.persistent
+    }
+
+    /// Load the saved conversations, most recently updated first
+    pub fn load_conversations(&self) -> Result<Vec<Conversation>> {
+        // Loading creates a missing directory, which an unwritable location can't have
+        if !self.persistent && !self.conversations_dir.exists() {
+            return Ok(Vec::new());
+        }
+        Conversation::load_all_in(&self.conversations_dir)
+    }
+
+    /// Search the saved conversations for `query`, ignoring case, reading their files one at a time
+    pub fn search_conversations(&self, query: &str) -> Result<Vec<SearchHit>> {
+        search_saved_in(&self.conversations_dir, query)
+    }
+
+    /// Move old conversations to the trash, keeping the `max` most recent ones, unless storage isn't writable
+    pub fn prune_conversations(&self, max: usize, keep: &dyn Fn(&Conversation) -> bool) -> Result<usize> {
+        if !self.persistent {
+            debug!("Not pruning conversations, storage isn't writable");
+            return Ok(0);
+        }
+        Conversation::prune_old_in(&self.conversations_dir, max, keep)
+    }
+
+    /// Save a conversation, unless storage isn't writable
+    pub fn save_conversation(&self, conversation: &Conversation) -> Result<()> {
+        if !self.persistent {
+            debug!("Not saving conversation {}, storage isn't writable", conversation.id);
+            return Ok(());
+        }
+        conversation.save_in(&self.conversations_dir)
+    }
+
+    /// Delete a saved conversation, unless storage isn't writable
+    pub fn delete_conversation(&self, conversation: &Conversation) -> Result<()> {
+        if !self.persistent {
+            debug!("Not deleting conversation {}, storage isn't writable", conversation.id);
+            return Ok(());
+        }
+        conversation.delete_in(&self.conversations_dir)
+    }
+
+    /// Save the configuration, unless storage isn't writable
+    pub fn save_config(&self, config: &Config) -> Result<()> {
+        if !self.persistent {
+            debug!("Not saving configuration, storage isn't writable");
+            return Ok(());
+        }
+        save_config(config, Some(self.config_path.clone()))
+    }
+}
+
+/// Check that files can be created in `dir`, creating it if needed
+fn check_writable(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let probe = dir.join(PROBE_FILE_NAME);
+    fs::write(&probe, b"").with_context(|| format!("Failed to write in directory: {}", dir.display()))?;
+    fs::remove_file(&probe).with_context(|| format!("Failed to remove file: {}", probe.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::conversation::MessageRole;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_writable_storage_saves() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::probe(dir.path().join("conversations"), dir.path().join("config.toml"));
+        assert!(storage.is_persistent());
+
+        let conversation = Conversation::new("Test", "model");
+        storage.save_conversation(&conversation).unwrap();
+        storage.save_config(&Config::default()).unwrap();
+        assert_eq!(Conversation::load_all_in(&dir.path().join("conversations")).unwrap().len(), 1);
+        assert!(dir.path().join("config.toml").exists());
+        assert!(!dir.path().join("conversations").join(PROBE_FILE_NAME).exists());
+
+        storage.delete_conversation(&conversation).unwrap();
+        assert!(storage.load_conversations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conversations_are_loaded_and_pruned_in_the_storage_dir() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::in_dir(dir.path());
+        for title in ["First", "Second", "Third"] {
+            storage.save_conversation(&Conversation::new(title, "model")).unwrap();
+        }
+        assert_eq!(storage.load_conversations().unwrap().len(), 3);
+
+        assert_eq!(storage.prune_conversations(1, &|_| false).unwrap(), 2);
+        assert_eq!(storage.load_conversations().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_conversations_are_searched_in_the_storage_dir() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::in_dir(dir.path());
+        let mut conversation = Conversation::new("Saved", "model");
+        conversation.add_message(MessageRole::User, "Where is the needle?");
+        storage.save_conversation(&conversation).unwrap();
+
+        let hits = storage.search_conversations("needle").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_id, conversation.id);
+    }
+
+    #[test]
+    fn test_unwritable_storage_degrades_to_memory() {
+        let dir = tempdir().unwrap();
+        // A directory under a file can't be created, even by root, unlike a read-only one
+        let file = dir.path().join("not-a-dir");
+        fs::write(&file, "").unwrap();
+        let storage = Storage::probe(file.join("conversations"), dir.path().join("config.toml"));
+        assert!(!storage.is_persistent());
+
+        // Saves succeed without writing anything, so every message doesn't report an error
+        let mut conversation = Conversation::new("Test", "model");
+        for _ in 0..3 {
+            conversation.add_message(MessageRole::User, "Hello");
+            assert!(storage.save_conversation(&conversation).is_ok());
+        }
+        assert!(storage.save_config(&Config::default()).is_ok());
+        assert!(!dir.path().join("config.toml").exists());
+        assert!(storage.load_conversations().unwrap().is_empty());
+        assert_eq!(storage.prune_conversations(1, &|_| false).unwrap(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_directory_degrades_to_memory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let read_only = dir.path().join("read-only");
+        fs::create_dir(&read_only).unwrap();
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root ignores directory permissions, so there is nothing to check when running as root
+        if fs::write(read_only.join("test"), "").is_ok() {
+            return;
+        }
+        let storage = Storage::probe(read_only.join("conversations"), dir.path().join("config.toml"));
+        assert!(!storage.is_persistent());
+        fs::set_permissions(&read_only, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
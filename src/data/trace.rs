@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::data::logger::get_log_dir;
+use crate::ollama::models::ChatCompletionRequest;
+
+/// File name of the API trace, kept next to the regular log files
+const TRACE_FILE_NAME: &str = "api-trace.jsonl";
+
+/// Placeholder written in place of secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Keys whose values must never be written to the trace
+const SECRET_KEYS: &[&str] = &["api_key", "authorization", "password", "secret", "token"];
+
+/// A single request/response turn in the API trace
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// When the request was sent
+    pub requested_at: DateTime<Utc>,
+    /// When the response was fully assembled
+    pub completed_at: DateTime<Utc>,
+    /// The request exactly as it was serialized for Ollama
+    pub request: Value,
+    /// The assembled response content
+    pub response: String,
+}
+
+/// Writer for the JSONL transcript of API requests and responses
+#[derive(Debug, Clone)]
+pub struct ApiTracer {
+    /// Path of the trace file
+    path: PathBuf,
+}
+
+impl ApiTracer {
+    /// Create a tracer writing to the given file
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Create a tracer writing to the default location in the log directory
+    pub fn in_log_dir() -> Self {
+        Self::new(get_log_dir().join(TRACE_FILE_NAME))
+    }
+
+    /// Get the path of the trace file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one turn to the trace
+    pub fn record(
+        &self,
+        request: &ChatCompletionRequest,
+        requested_at: DateTime<Utc>,
+        response: &str,
+    ) -> Result<()> {
+        let mut request = serde_json::to_value(request).context("Failed to serialize request")?;
+        redact_secrets(&mut request);
+
+        let entry = TraceEntry {
+            requested_at,
+            completed_at: Utc::now(),
+            request,
+            response: response.to_string(),
+        };
+
+        // Create parent directories if they don't exist
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open trace file: {}", self.path.display()))?;
+
+        let line = serde_json::to_string(&entry).context("Failed to serialize trace entry")?;
+        writeln!(file, "{}", line)
+            .with_context(|| format!("Failed to write trace file: {}", self.path.display()))?;
+
+        debug!("Recorded API trace entry to {}", self.path.display());
+        Ok(())
+    }
+}
+
+/// Replace the values of secret-looking keys with a placeholder
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.to_lowercase().as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ollama::models::{ChatMessage, GenerationParameters};
+    use tempfile::tempdir;
+
+    fn sample_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "llama3.2".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+                images: None,
+            }],
+            stream: Some(true),
+            format: None,
+            parameters: GenerationParameters::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_writes_one_entry_per_turn() {
+        let temp_dir = tempdir().unwrap();
+        let tracer = ApiTracer::new(temp_dir.path().join("api-trace.jsonl"));
+
+        tracer.record(&sample_request(), Utc::now(), "Hi there").unwrap();
+
+        let content = fs::read_to_string(tracer.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+
+        let entry: TraceEntry = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry.request["model"], "llama3.2");
+        assert_eq!(entry.request["messages"][0]["content"], "Hello");
+        assert_eq!(entry.response, "Hi there");
+        assert!(entry.requested_at <= entry.completed_at);
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let mut value = serde_json::json!({
+            "model": "llama3.2",
+            "api_key": "sk-123",
+            "headers": [{ "Authorization": "Bearer abc" }],
+            "max_tokens": 10,
+        });
+
+        redact_secrets(&mut value);
+
+        assert_eq!(value["api_key"], REDACTED);
+        assert_eq!(value["headers"][0]["Authorization"], REDACTED);
+        assert_eq!(value["model"], "llama3.2");
+        assert_eq!(value["max_tokens"], 10);
+    }
+}
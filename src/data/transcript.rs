@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Written after each response to separate turns in the transcript
+pub const TURN_SEPARATOR: &str = "\n\n---\n\n";
+
+/// Appends streamed responses to a plain-text transcript file
+#[derive(Debug)]
+pub struct TranscriptWriter {
+    /// Path of the transcript file
+    path: PathBuf,
+    /// Buffered handle to the transcript file
+    writer: BufWriter<File>,
+}
+
+impl TranscriptWriter {
+    /// Open the transcript for appending, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        // Create parent directories if they don't exist
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open transcript: {}", path.display()))?;
+
+        debug!("Teeing responses to {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Get the path of the transcript file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a streamed chunk of the current response
+    pub fn write_chunk(&mut self, chunk: &str) -> Result<()> {
+        self.writer
+            .write_all(chunk.as_bytes())
+            .with_context(|| format!("Failed to write transcript: {}", self.path.display()))
+    }
+
+    /// Finish the current response with a separator and flush it to disk
+    pub fn end_turn(&mut self) -> Result<()> {
+        self.writer
+            .write_all(TURN_SEPARATOR.as_bytes())
+            .and_then(|_| self.writer.flush())
+            .with_context(|| format!("Failed to write transcript: {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_streamed_responses_are_teed() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("transcripts").join("session.txt");
+
+        let mut transcript = TranscriptWriter::open(&path).unwrap();
+        for chunk in ["Hello", ", ", "world"] {
+            transcript.write_chunk(chunk).unwrap();
+        }
+        transcript.end_turn().unwrap();
+        transcript.write_chunk("Second answer").unwrap();
+        transcript.end_turn().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("Hello, world{0}Second answer{0}", TURN_SEPARATOR)
+        );
+
+        // Reopening appends rather than truncating
+        let mut transcript = TranscriptWriter::open(&path).unwrap();
+        transcript.write_chunk("Third").unwrap();
+        transcript.end_turn().unwrap();
+        assert!(fs::read_to_string(&path).unwrap().ends_with(&format!("Second answer{0}Third{0}", TURN_SEPARATOR)));
+    }
+}
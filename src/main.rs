@@ -37,9 +37,11 @@ fn main() -> iced::Result {
     info!("Configuration loaded successfully");
     
     // Run the application with the loaded configuration
+    // Close requests are handled by the app so it can save before exiting
     let result = App::run(Settings {
         window: crate::ui::window::create_window_settings(&config),
         flags: config,
+        exit_on_close_request: false,
         ..Default::default()
     });
     
@@ -1,20 +1,64 @@
 use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use log::{debug, error, info, warn};
-use reqwest::Client;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
 use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
 use std::time::Duration;
+use tokio::sync::OwnedSemaphorePermit;
 use tokio::time::sleep;
 
+use super::breaker::CircuitBreaker;
+use super::endpoint::Endpoint;
+use super::error::ResponseError;
+use super::framing::{FrameDecoder, Framing};
+use super::limit::RequestLimiter;
 use super::models::{
-    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse,
-    ErrorResponse, ListModelsResponse, ModelInfoRequest, ModelInfoResponse,
+    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, EmbeddingsRequest, EmbeddingsResponse,
+    ErrorResponse, GenerateRequest, GenerateResponse, ListModelsResponse, ModelInfoRequest, ModelInfoResponse,
+    OpenAiChunk, PullProgress, PullRequest, RunningModelsResponse, VersionResponse, JSON_FORMAT,
 };
+use crate::config::{ApiStyle, ProbeEndpoint};
+use crate::data::conversation::ResponseMetadata;
+use crate::data::trace::REDACTED;
 
 /// Maximum number of retry attempts for API requests
 const MAX_RETRY_ATTEMPTS: u32 = 3;
 /// Base delay for exponential backoff in milliseconds
 const BASE_RETRY_DELAY_MS: u64 = 500;
+/// Default longest `Retry-After` delay honored when rate limited
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Number of times a stream that completes without content is retried
+const EMPTY_RESPONSE_RETRIES: u32 = 1;
+/// Default timeout for requests that aren't streamed
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+/// Default time allowed for connecting to the API
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default time allowed for a stream to produce its first content
+const DEFAULT_FIRST_TOKEN_TIMEOUT: Duration = Duration::from_secs(120);
+/// Default time allowed between chunks once a stream is producing content
+const DEFAULT_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default time allowed between progress reports while a model downloads
+const DEFAULT_PULL_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long a prewarmed model stays loaded without being used
+const PREWARM_KEEP_ALIVE: &str = "5m";
+/// Parts of header names whose values are left out of shared curl commands
+const SECRET_HEADER_WORDS: &[&str] = &["auth", "key", "token", "secret", "password", "cookie"];
+
+/// A chat response assembled from a stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamedResponse {
+    /// Full content of the response
+    pub content: String,
+    /// Statistics from the final chunk, if the stream sent one
+    pub metadata: Option<ResponseMetadata>,
+    /// Base64-encoded images sent with the response
+    pub images: Vec<String>,
+}
 
 /// Client for interacting with the Ollama API
 #[derive(Clone)]
@@ -23,6 +67,31 @@ pub struct OllamaClient {
     client: Client,
     /// API base URL
     api_url: String,
+    /// API used for chat requests
+    api_style: ApiStyle,
+    /// Endpoint requested by `probe`
+    probe_endpoint: ProbeEndpoint,
+    /// Timeout for requests that aren't streamed, if any
+    request_timeout: Option<Duration>,
+    /// Longest `Retry-After` delay honored before retrying a rate limited request
+    max_retry_after: Duration,
+    /// Time allowed for a stream to produce its first content, which includes loading the model
+    first_token_timeout: Duration,
+    /// Time allowed between chunks once a stream is producing content
+    stream_idle_timeout: Duration,
+    /// Time allowed between progress reports while a model downloads
+    pull_idle_timeout: Duration,
+    /// Fails requests fast while the API keeps failing, if enabled
+    breaker: Option<CircuitBreaker>,
+    /// Bounds how many requests are in flight at once, if set
+    limiter: Option<RequestLimiter>,
+    /// Bearer token sent with every request, if the server needs one
+    api_key: Option<String>,
+    /// Extra headers sent with every request
+    headers: HeaderMap,
+    /// Client for the unix socket the API is served on, which sends every request instead of `client`
+    #[cfg(unix)]
+    socket: Option<super::endpoint::UnixSocketClient>,
 }
 
 impl std::fmt::Debug for OllamaClient {
@@ -35,30 +104,151 @@ impl std::fmt::Debug for OllamaClient {
 
 impl OllamaClient {
     /// Create a new Ollama client
+    ///
+    /// Unix socket endpoints (`http+unix:///path/to/socket`) are supported on unix platforms.
     pub fn new(api_url: &str) -> Result<Self> {
         // Validate API URL format
-        if !api_url.starts_with("http://") && !api_url.starts_with("https://") {
-            bail!("API URL must start with http:// or https://");
+        let endpoint = Endpoint::parse(api_url)?;
+
+        // Streams are bounded by their own timeouts rather than the client's
+        let client = http_client(Some(DEFAULT_CONNECT_TIMEOUT))?;
+
+        match endpoint {
+            Endpoint::Http(api_url) => Ok(Self {
+                client,
+                api_url,
+                api_style: ApiStyle::default(),
+                probe_endpoint: ProbeEndpoint::default(),
+                request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+                max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+                first_token_timeout: DEFAULT_FIRST_TOKEN_TIMEOUT,
+                stream_idle_timeout: DEFAULT_STREAM_IDLE_TIMEOUT,
+                pull_idle_timeout: DEFAULT_PULL_IDLE_TIMEOUT,
+                breaker: None,
+                limiter: None,
+                api_key: None,
+                headers: HeaderMap::new(),
+                #[cfg(unix)]
+                socket: None,
+            }),
+            #[cfg(unix)]
+            Endpoint::Unix(socket) => {
+                info!("Connecting to Ollama API over unix socket {}", socket.display());
+                Ok(Self {
+                    client,
+                    // Requests name a host to be well formed, but only the socket is connected to
+                    api_url: "http://localhost".to_string(),
+                    api_style: ApiStyle::default(),
+                    probe_endpoint: ProbeEndpoint::default(),
+                    request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+                    max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+                    first_token_timeout: DEFAULT_FIRST_TOKEN_TIMEOUT,
+                    stream_idle_timeout: DEFAULT_STREAM_IDLE_TIMEOUT,
+                    pull_idle_timeout: DEFAULT_PULL_IDLE_TIMEOUT,
+                    breaker: None,
+                    limiter: None,
+                    api_key: None,
+                    headers: HeaderMap::new(),
+                    socket: Some(super::endpoint::UnixSocketClient::new(socket)),
+                })
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => bail!("Unix socket endpoints are not supported on this platform"),
         }
+    }
+    
+    /// Set the API used for chat requests
+    pub fn with_api_style(mut self, api_style: ApiStyle) -> Self {
+        self.api_style = api_style;
+        self
+    }
 
-        // Create HTTP client with reasonable timeouts
-        let client = Client::builder()
-            .timeout(Duration::from_secs(60))
-            .connect_timeout(Duration::from_secs(10))
-            .build()
-            .context("Failed to create HTTP client")?;
+    /// Set the endpoint requested by `probe`
+    pub fn with_probe_endpoint(mut self, probe_endpoint: ProbeEndpoint) -> Self {
+        self.probe_endpoint = probe_endpoint;
+        self
+    }
 
-        // Normalize API URL by removing trailing slash
-        let api_url = api_url.trim_end_matches('/').to_string();
+    /// Set the timeout for requests that aren't streamed and for connecting, `None` for no limit
+    pub fn with_timeouts(mut self, request: Option<Duration>, connect: Option<Duration>) -> Result<Self> {
+        self.client = http_client(connect)?;
+        self.request_timeout = request;
+        Ok(self)
+    }
 
-        Ok(Self { client, api_url })
+    /// Set the longest `Retry-After` delay waited for before retrying a rate limited request
+    pub fn with_max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
     }
-    
+
+    /// Set the time allowed for a stream's first content and between later chunks
+    pub fn with_stream_timeouts(mut self, first_token: Duration, idle: Duration) -> Self {
+        self.first_token_timeout = first_token;
+        self.stream_idle_timeout = idle;
+        self
+    }
+
+    /// Set the time allowed between progress reports while a model downloads
+    pub fn with_pull_idle_timeout(mut self, idle: Duration) -> Self {
+        self.pull_idle_timeout = idle;
+        self
+    }
+
+    /// Fail requests fast while the API keeps failing, as decided by `breaker`
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Wait for a slot from `limiter` before each request, holding it until the response is read
+    ///
+    /// Streamed responses hold their slot until the stream is dropped.
+    pub fn with_limiter(mut self, limiter: RequestLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Authenticate every request with `api_key` as a bearer token
+    ///
+    /// The key is never logged, and the client's `Debug` output leaves it out.
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Send `headers` with every request, alongside the content type and authorization the client sets
+    ///
+    /// Headers with invalid names or values are skipped, as are `Content-Type` and
+    /// `Authorization`, which the client manages itself.
+    pub fn with_headers(mut self, headers: &BTreeMap<String, String>) -> Self {
+        for (name, value) in headers {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .ok()
+                .zip(HeaderValue::from_str(value).ok());
+            match header {
+                Some((name, _)) if name == CONTENT_TYPE || name == AUTHORIZATION => {
+                    warn!("Not sending header {}, which the client sets itself", name);
+                }
+                Some((name, value)) => {
+                    self.headers.insert(name, value);
+                }
+                None => debug!("Skipping invalid header {:?}", name),
+            }
+        }
+        self
+    }
+
     /// Get the API URL
     pub fn api_url(&self) -> &str {
         &self.api_url
     }
 
+    /// Check whether requests are being refused because the API keeps failing
+    pub fn circuit_open(&self) -> bool {
+        self.breaker.as_ref().is_some_and(CircuitBreaker::is_open)
+    }
+
     /// Check if the Ollama service is running
     pub async fn check_connection(&self) -> Result<bool> {
         debug!("Checking connection to Ollama API at {}", self.api_url);
@@ -76,12 +266,71 @@ impl OllamaClient {
         }
     }
 
+    /// Probe the Ollama service, failing if it doesn't respond within the timeout
+    ///
+    /// Unlike `check_connection`, this is bounded independently of the request
+    /// timeout so a slow or unreachable server can't block startup.
+    pub async fn probe(&self, timeout: Duration) -> Result<()> {
+        debug!("Probing Ollama API at {} (timeout {:?})", self.api_url, timeout);
+
+        let request = async {
+            match self.probe_endpoint {
+                ProbeEndpoint::Models => self.list_models().await.map(|_| ()),
+                ProbeEndpoint::Version => self.version().await.map(|_| ()),
+            }
+        };
+        match tokio::time::timeout(timeout, request).await {
+            Ok(result) => result,
+            Err(_) => bail!("No response from {} within {:?}", self.api_url, timeout),
+        }
+    }
+
+    /// Get the version of the Ollama server
+    pub async fn version(&self) -> Result<String> {
+        let url = format!("{}/api/version", self.api_url);
+        Ok(self.get::<VersionResponse>(&url).await?.version)
+    }
+
+    /// Load `model` into memory with an empty generate request, so the first real response starts sooner
+    pub async fn prewarm(&self, model: &str) -> Result<()> {
+        debug!("Prewarming model '{}'", model);
+        let request = GenerateRequest {
+            model: model.to_string(),
+            prompt: String::new(),
+            stream: false,
+            keep_alive: Some(PREWARM_KEEP_ALIVE.to_string()),
+            system: None,
+            context: None,
+            images: None,
+            format: None,
+            options: None,
+        };
+        let response = self.generate(&request).await?;
+        info!("Model '{}' is loaded", response.model);
+        Ok(())
+    }
+
+    /// Generate a completion of a prompt without streaming it, through `/api/generate`
+    ///
+    /// The response's `context` can be sent with the next request to continue
+    /// from it without processing the earlier prompts again.
+    pub async fn generate(&self, request: &GenerateRequest) -> Result<GenerateResponse> {
+        let url = format!("{}/api/generate", self.api_url);
+        self.post::<_, GenerateResponse>(&url, request).await
+    }
+
     /// List available models
     pub async fn list_models(&self) -> Result<ListModelsResponse> {
         let url = format!("{}/api/tags", self.api_url);
         self.get::<ListModelsResponse>(&url).await
     }
 
+    /// List the models currently loaded into memory
+    pub async fn list_running_models(&self) -> Result<RunningModelsResponse> {
+        let url = format!("{}/api/ps", self.api_url);
+        self.get::<RunningModelsResponse>(&url).await
+    }
+
     /// Check if a model exists
     pub async fn check_model_exists(&self, model_name: &str) -> Result<bool> {
         debug!("Checking if model '{}' exists", model_name);
@@ -114,56 +363,230 @@ impl OllamaClient {
         self.post::<_, ModelInfoResponse>(&url, &request).await
     }
 
+    /// Get the embedding vector of `prompt` from `model`
+    pub async fn embeddings(&self, model: &str, prompt: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/api/embeddings", self.api_url);
+        let request = EmbeddingsRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+        };
+        Ok(self.post::<_, EmbeddingsResponse>(&url, &request).await?.embedding)
+    }
+
     /// Send a chat completion request (non-streaming)
     pub async fn chat_completion(
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse> {
-        let url = format!("{}/api/chat", self.api_url);
-        self.post::<_, ChatCompletionResponse>(&url, request).await
+        // Ollama streams unless told not to
+        let (url, body) = self.chat_body(request, false)?;
+        self.post::<_, ChatCompletionResponse>(&url, &body).await
+    }
+
+    /// Send a chat completion request in JSON mode and deserialize the response content into `T`
+    ///
+    /// The response is awaited in full. Streaming in JSON mode works too, but the
+    /// object is only complete, and parseable, once the last chunk has arrived.
+    pub async fn chat_completion_json<T: DeserializeOwned>(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<T> {
+        let mut request = request.clone();
+        request.format = Some(JSON_FORMAT.to_string());
+        let response = self.chat_completion(&request).await?;
+        serde_json::from_str(&response.message.content)
+            .with_context(|| format!("Response doesn't match the expected JSON: {}", response.message.content))
     }
 
     /// Send a chat completion request with streaming response
+    ///
+    /// The chat endpoint and the framing of the stream follow the client's API style.
     pub async fn chat_completion_stream(
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<impl futures::Stream<Item = Result<ChatCompletionChunk>>> {
-        let url = format!("{}/api/chat", self.api_url);
+        let api_style = self.api_style;
+        let framing = match api_style {
+            ApiStyle::Ollama => Framing::Ndjson,
+            ApiStyle::OpenAi => Framing::Sse,
+        };
 
-        // Create a request with streaming enabled
-        let mut streaming_request = request.clone();
-        streaming_request.stream = Some(true);
+        // Send the request with streaming enabled
+        let (url, body) = self.chat_body(request, true)?;
+        let method = reqwest::Method::POST;
+        let (response, permit) = self.send_request_with_retry(method, &url, Some(&body), None).await?;
 
-        // Send the request
-        let response = self
-            .send_request_with_retry(reqwest::Method::POST, &url, Some(&streaming_request))
-            .await?;
+        let chunks = parse_frames(response, framing, move |frame| parse_chunk(api_style, frame));
+        Ok(hold_permit(chunks, permit))
+    }
 
-        // Convert the response to a stream of chunks
-        let stream = response
-            .bytes_stream()
-            .map(|result| {
-                match result {
-                    Ok(bytes) => {
-                        // Parse the bytes as a JSON chunk
-                        match serde_json::from_slice::<ChatCompletionChunk>(&bytes) {
-                            Ok(chunk) => Ok(chunk),
-                            Err(e) => {
-                                error!("Failed to parse response chunk: {}", e);
-                                Err(anyhow::anyhow!("Failed to parse response chunk: {}", e))
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Error receiving stream chunk: {}", e);
-                        Err(anyhow::anyhow!("Error receiving stream chunk: {}", e))
-                    }
-                }
-            });
+    /// Get the URL and body of a chat request as the client sends it
+    ///
+    /// Streamed requests go to the chat endpoint of the client's API style, with the parameters
+    /// where that API reads them. Whole responses are always requested from Ollama's chat API.
+    fn chat_body(&self, request: &ChatCompletionRequest, stream: bool) -> Result<(String, serde_json::Value)> {
+        let mut request = request.clone();
+        request.stream = Some(stream);
+        let (path, body) = match self.api_style {
+            ApiStyle::OpenAi if stream => ("/v1/chat/completions", serde_json::to_value(request.openai_body())),
+            _ => ("/api/chat", serde_json::to_value(&request)),
+        };
+        let body = body.context("Failed to serialize request")?;
+        Ok((format!("{}{}", self.api_url, path), body))
+    }
+
+    /// Render a chat request as the `curl` command that sends what the client would
+    ///
+    /// The command has the client's URL, body and headers. The API key and the values of
+    /// secret-looking headers are replaced with a placeholder so the command can be shared.
+    pub fn to_curl(&self, request: &ChatCompletionRequest, stream: bool) -> Result<String> {
+        let (url, body) = self.chat_body(request, stream)?;
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let secret = SECRET_HEADER_WORDS.iter().any(|word| name.as_str().contains(word));
+                let value = if secret { REDACTED } else { value.to_str().unwrap_or_default() };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        if self.api_key.is_some() {
+            headers.push((AUTHORIZATION.to_string(), format!("Bearer {}", REDACTED)));
+        }
+        super::request::to_curl(&url, &headers, body)
+    }
 
+    /// Download a model, streaming the progress Ollama reports
+    ///
+    /// The stream ends after the final `success` status. The download fails if
+    /// Ollama reports no progress for longer than the pull idle timeout.
+    pub async fn pull_model(&self, name: &str) -> Result<impl futures::Stream<Item = Result<PullProgress>>> {
+        info!("Pulling model '{}'", name);
+        let url = format!("{}/api/pull", self.api_url);
+        let request = PullRequest {
+            name: name.to_string(),
+            stream: true,
+        };
+        let idle = self.pull_idle_timeout;
+        let (response, permit) = tokio::time::timeout(
+            idle,
+            self.send_request_with_retry(reqwest::Method::POST, &url, Some(&request), None),
+        )
+        .await
+        .map_err(|_| anyhow::anyhow!("Download didn't start within {:?}", idle))??;
+
+        let progress = Box::pin(hold_permit(parse_frames(response, Framing::Ndjson, parse_pull_progress), permit));
+        // Stop after the first item that is either the final status or an error
+        let stream = futures::stream::unfold((progress, false), move |(mut progress, finished)| async move {
+            if finished {
+                return None;
+            }
+            let progress_report = match tokio::time::timeout(idle, progress.next()).await {
+                Ok(Some(progress_report)) => progress_report,
+                Ok(None) => return None,
+                Err(_) => Err(anyhow::anyhow!("Download stalled for more than {:?}", idle)),
+            };
+            let finished = progress_report.as_ref().map_or(true, PullProgress::is_success);
+            Some((progress_report, (progress, finished)))
+        });
         Ok(stream)
     }
 
+    /// Stream a chat response, passing each non-empty piece of content to `on_chunk`
+    ///
+    /// Some models intermittently complete without producing any content, so an
+    /// empty response is retried once before surfacing `ResponseError::Empty`.
+    /// Streaming stops early once the content matches `stop`, dropping anything
+    /// after the match. The response fails if no content arrives within the
+    /// first-token timeout, or if the stream then stalls for longer than the idle
+    /// timeout. A response made only of images counts as having content.
+    pub async fn stream_chat<F>(
+        &self,
+        request: &ChatCompletionRequest,
+        stop: Option<&Regex>,
+        mut on_chunk: F,
+    ) -> std::result::Result<StreamedResponse, ResponseError>
+    where
+        F: FnMut(String),
+    {
+        let mut attempt = 0;
+        loop {
+            // The first-token deadline covers connecting, loading the model and the first chunk
+            let first_token_deadline = tokio::time::Instant::now() + self.first_token_timeout;
+            let stream = tokio::time::timeout_at(first_token_deadline, self.chat_completion_stream(request))
+                .await
+                .map_err(|_| self.first_token_timed_out())?
+                .map_err(|e| ResponseError::Failed(format!("Failed to create stream: {}", e)))?;
+            let mut stream = Box::pin(stream);
+
+            let mut full_content = String::new();
+            let mut images = Vec::new();
+            let mut metadata = None;
+            let mut received_content = false;
+
+            // Process each chunk as it arrives
+            loop {
+                let next_chunk = if received_content {
+                    tokio::time::timeout(self.stream_idle_timeout, stream.next())
+                        .await
+                        .map_err(|_| {
+                            ResponseError::Failed(format!(
+                                "Response stalled for more than {:?}",
+                                self.stream_idle_timeout
+                            ))
+                        })?
+                } else {
+                    tokio::time::timeout_at(first_token_deadline, stream.next())
+                        .await
+                        .map_err(|_| self.first_token_timed_out())?
+                };
+                let Some(chunk_result) = next_chunk else {
+                    break;
+                };
+
+                let mut chunk =
+                    chunk_result.map_err(|e| ResponseError::Failed(format!("Stream error: {}", e)))?;
+                let (content, stopped) = append_until_stop(&mut full_content, &chunk.message.content, stop);
+                if !content.is_empty() {
+                    received_content = true;
+                    on_chunk(content);
+                }
+                if !chunk.message.images.is_empty() {
+                    received_content = true;
+                    images.append(&mut chunk.message.images);
+                }
+                if stopped {
+                    debug!("Stopping stream at client stop pattern");
+                    break;
+                }
+
+                // If this is the last chunk, keep its stats and break
+                if chunk.done {
+                    metadata = Some(chunk.metadata());
+                    break;
+                }
+            }
+
+            if !full_content.is_empty() || !images.is_empty() {
+                return Ok(StreamedResponse {
+                    content: full_content,
+                    metadata,
+                    images,
+                });
+            }
+            if attempt >= EMPTY_RESPONSE_RETRIES {
+                return Err(ResponseError::Empty);
+            }
+            attempt += 1;
+            warn!("Received empty response, retrying (attempt {}/{})", attempt, EMPTY_RESPONSE_RETRIES);
+        }
+    }
+
+    /// Build the error for a response that produced no content before the first-token timeout
+    fn first_token_timed_out(&self) -> ResponseError {
+        ResponseError::Failed(format!("No response within {:?}", self.first_token_timeout))
+    }
+
     /// Process a streaming response into a complete message
     pub async fn process_stream_to_string(
         stream: impl futures::Stream<Item = Result<ChatCompletionChunk>>,
@@ -195,8 +618,8 @@ impl OllamaClient {
     where
         T: DeserializeOwned,
     {
-        let response = self
-            .send_request_with_retry(reqwest::Method::GET, url, None::<&()>)
+        let (response, _permit) = self
+            .send_request_with_retry(reqwest::Method::GET, url, None::<&()>, self.request_timeout)
             .await?;
         self.parse_response(response).await
     }
@@ -207,43 +630,102 @@ impl OllamaClient {
         B: serde::Serialize,
         T: DeserializeOwned,
     {
-        let response = self
-            .send_request_with_retry(reqwest::Method::POST, url, Some(body))
+        let (response, _permit) = self
+            .send_request_with_retry(reqwest::Method::POST, url, Some(body), self.request_timeout)
             .await?;
         self.parse_response(response).await
     }
 
-    /// Send a request with retry logic
+    /// Send a request with retry logic, bounding each attempt by `timeout` if given
+    ///
+    /// While the circuit breaker is open the request fails without being sent,
+    /// and otherwise its outcome, after all retries, is recorded by the breaker.
+    /// The request first waits for a slot from the limiter, if any; the returned
+    /// permit holds the slot, so callers keep it until they have read the response.
     async fn send_request_with_retry<B>(
         &self,
         method: reqwest::Method,
         url: &str,
         body: Option<&B>,
+        timeout: Option<Duration>,
+    ) -> Result<(reqwest::Response, Option<OwnedSemaphorePermit>)>
+    where
+        B: serde::Serialize,
+    {
+        if let Some(breaker) = &self.breaker {
+            if let Err(next_trial) = breaker.check() {
+                bail!(
+                    "Ollama API at {} appears to be offline, trying again in {}s",
+                    self.api_url,
+                    next_trial.as_secs().max(1)
+                );
+            }
+        }
+
+        let permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        };
+        let result = self.send_with_retries(method, url, body, timeout).await;
+        if let Some(breaker) = &self.breaker {
+            match &result {
+                Ok(_) => breaker.record_success(),
+                Err(_) => breaker.record_failure(),
+            }
+        }
+        Ok((result?, permit))
+    }
+
+    /// Send a request, retrying connection errors, server errors and rate limiting with exponential backoff
+    ///
+    /// A rate limited request is retried after its `Retry-After` delay instead,
+    /// if given, capped at the client's maximum.
+    async fn send_with_retries<B>(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&B>,
+        timeout: Option<Duration>,
     ) -> Result<reqwest::Response>
     where
         B: serde::Serialize,
     {
         let mut attempt = 0;
         let mut last_error = None;
+        let mut retry_after = None;
 
         while attempt < MAX_RETRY_ATTEMPTS {
-            // Exponential backoff for retries
+            // Exponential backoff for retries, unless the server said how long to wait
             if attempt > 0 {
-                let delay = BASE_RETRY_DELAY_MS * 2u64.pow(attempt - 1);
-                debug!("Retrying request in {}ms (attempt {}/{})", delay, attempt + 1, MAX_RETRY_ATTEMPTS);
-                sleep(Duration::from_millis(delay)).await;
+                let delay = retry_after
+                    .take()
+                    .unwrap_or_else(|| Duration::from_millis(BASE_RETRY_DELAY_MS * 2u64.pow(attempt - 1)));
+                debug!(
+                    "Retrying request in {}ms (attempt {}/{})",
+                    delay.as_millis(),
+                    attempt + 1,
+                    MAX_RETRY_ATTEMPTS
+                );
+                sleep(delay).await;
             }
 
             attempt += 1;
 
             // Build the request
-            let mut request_builder = self.client.request(method.clone(), url);
+            let mut request_builder = self.client.request(method.clone(), url).headers(self.headers.clone());
             if let Some(body_data) = body {
                 request_builder = request_builder.json(body_data);
             }
+            if let Some(timeout) = timeout {
+                request_builder = request_builder.timeout(timeout);
+            }
+            if let Some(api_key) = &self.api_key {
+                request_builder = request_builder.bearer_auth(api_key);
+            }
 
             // Send the request
-            match request_builder.send().await {
+            let request = request_builder.build().context("Failed to build request")?;
+            match self.execute(request).await {
                 Ok(response) => {
                     // Check if the response is a server error (5xx)
                     if response.status().is_server_error() {
@@ -253,6 +735,18 @@ impl OllamaClient {
                         last_error = Some(anyhow::anyhow!("Server error ({}): {}", status, error_text));
                         continue; // Retry on server errors
                     }
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        retry_after = response
+                            .headers()
+                            .get(RETRY_AFTER)
+                            .and_then(|value| value.to_str().ok())
+                            .and_then(|value| parse_retry_after(value, Utc::now()))
+                            .map(|delay| delay.min(self.max_retry_after));
+                        let error_text = response.text().await.unwrap_or_default();
+                        warn!("Rate limited: {}", error_text);
+                        last_error = Some(anyhow::anyhow!("Rate limited (429): {}", error_text));
+                        continue; // Retry once the server is ready for more requests
+                    }
                     return Ok(response);
                 }
                 Err(e) => {
@@ -267,6 +761,15 @@ impl OllamaClient {
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Request failed after {} attempts", MAX_RETRY_ATTEMPTS)))
     }
 
+    /// Send a request over the unix socket the API is served on, if any, or else over HTTP
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response> {
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            return socket.execute(request).await;
+        }
+        Ok(self.client.execute(request).await?)
+    }
+
     /// Parse a response into the expected type
     async fn parse_response<T>(&self, response: reqwest::Response) -> Result<T>
     where
@@ -289,6 +792,113 @@ impl OllamaClient {
     }
 }
 
+/// Parse a `Retry-After` header, given as seconds or as an HTTP date, into a delay from `now`
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+/// Create the HTTP client, with a connect timeout if given
+fn http_client(connect_timeout: Option<Duration>) -> Result<Client> {
+    let mut builder = Client::builder();
+    if let Some(timeout) = connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    builder.build().context("Failed to create HTTP client")
+}
+
+/// Keep `permit` alive for as long as `stream` is, so a streamed response holds its request slot
+fn hold_permit<S: futures::Stream>(
+    stream: S,
+    permit: Option<OwnedSemaphorePermit>,
+) -> impl futures::Stream<Item = S::Item> {
+    stream.map(move |item| {
+        let _held = &permit;
+        item
+    })
+}
+
+/// Split a streamed response into frames, which may span several reads, and parse each one
+fn parse_frames<T, P>(
+    response: reqwest::Response,
+    framing: Framing,
+    parse: P,
+) -> impl futures::Stream<Item = Result<T>>
+where
+    P: Fn(&[u8]) -> Result<T>,
+{
+    let frames = VecDeque::<Vec<u8>>::new();
+    let state = (Box::pin(response.bytes_stream()), FrameDecoder::new(framing), frames, parse);
+    futures::stream::unfold(state, |(mut bytes, mut decoder, mut frames, parse)| async move {
+        loop {
+            if let Some(frame) = frames.pop_front() {
+                let item = parse(&frame);
+                return Some((item, (bytes, decoder, frames, parse)));
+            }
+            if decoder.is_done() {
+                return None;
+            }
+            match bytes.next().await {
+                Some(Ok(data)) => frames.extend(decoder.push(&data)),
+                Some(Err(e)) => {
+                    error!("Error receiving stream chunk: {}", e);
+                    let error = anyhow::anyhow!("Error receiving stream chunk: {}", e);
+                    return Some((Err(error), (bytes, decoder, frames, parse)));
+                }
+                None => frames.extend(decoder.finish()),
+            }
+        }
+    })
+}
+
+/// Parse a line of pull progress, turning an `error` object into an error
+fn parse_pull_progress(frame: &[u8]) -> Result<PullProgress> {
+    if let Ok(error) = serde_json::from_slice::<ErrorResponse>(frame) {
+        bail!("Failed to pull model: {}", error.error);
+    }
+    serde_json::from_slice::<PullProgress>(frame).context("Failed to parse pull progress")
+}
+
+/// Parse the payload of a stream frame as a chunk in the given API style
+///
+/// Frames are only complete once their closing newline arrives, so characters split
+/// across reads are whole again here. Bytes that still aren't valid UTF-8 are
+/// replaced rather than failing the whole response.
+fn parse_chunk(api_style: ApiStyle, frame: &[u8]) -> Result<ChatCompletionChunk> {
+    let frame = String::from_utf8_lossy(frame);
+    if let Cow::Owned(_) = frame {
+        warn!("Replaced invalid UTF-8 in response chunk");
+    }
+    let chunk = match api_style {
+        ApiStyle::Ollama => serde_json::from_str::<ChatCompletionChunk>(&frame),
+        ApiStyle::OpenAi => serde_json::from_str::<OpenAiChunk>(&frame).map(ChatCompletionChunk::from),
+    };
+    chunk.map_err(|e| {
+        error!("Failed to parse response chunk: {}", e);
+        anyhow::anyhow!("Failed to parse response chunk: {}", e)
+    })
+}
+
+/// Append a chunk to the accumulated content, stopping at the first match of `stop`
+///
+/// Returns the part of the chunk that was kept and whether the stop pattern matched.
+fn append_until_stop(content: &mut String, chunk: &str, stop: Option<&Regex>) -> (String, bool) {
+    let start = content.len();
+    content.push_str(chunk);
+
+    match stop.and_then(|stop| stop.find(content)) {
+        Some(found) => {
+            content.truncate(found.end().max(start));
+            (content[start..].to_string(), true)
+        }
+        None => (chunk.to_string(), false),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -308,6 +918,634 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_client_accepts_ipv6_host() {
+        let client = OllamaClient::new("http://[::1]:11434/").unwrap();
+        assert_eq!(client.api_url(), "http://[::1]:11434");
+
+        assert!(OllamaClient::new("http://::1:11434").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_over_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let socket = temp_dir.path().join("ollama.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            assert!(request.starts_with(b"GET /api/tags "));
+
+            let body = r#"{"models":[]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let client = OllamaClient::new(&format!("http+unix://{}", socket.display())).unwrap();
+        let models = client.list_models().await.unwrap();
+        assert!(models.models.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_probe_respects_timeout() {
+        // 10.255.255.1 is non-routable, so the connection never completes on its own
+        let client = OllamaClient::new("http://10.255.255.1:11434").unwrap();
+
+        let started = std::time::Instant::now();
+        let result = client.probe(Duration::from_millis(200)).await;
+
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_is_configurable() {
+        // 10.255.255.1 is non-routable, so only the connect timeout ends each attempt
+        let client = OllamaClient::new("http://10.255.255.1:11434")
+            .unwrap()
+            .with_timeouts(None, Some(Duration::from_millis(100)))
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        assert!(client.list_models().await.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_every_request_waits_for_a_slot_from_the_limiter() {
+        let mut server = mockito::Server::new_async().await;
+        let _tags = server.mock("GET", "/api/tags").with_body(r#"{"models":[]}"#).create_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_body("{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":true}\n")
+            .create_async()
+            .await;
+        let limiter = RequestLimiter::new(1);
+        let client = OllamaClient::new(&server.url()).unwrap().with_limiter(limiter.clone());
+        let waited = |request| tokio::time::timeout(Duration::from_millis(100), request);
+
+        // Listing models is limited like chat requests are
+        let permit = limiter.acquire().await;
+        assert!(waited(client.list_models()).await.is_err());
+        drop(permit);
+        assert!(waited(client.list_models()).await.is_ok());
+
+        // A stream holds its slot until it is dropped
+        let stream = client.chat_completion_stream(&chat_request()).await.unwrap();
+        assert!(waited(client.list_models()).await.is_err());
+        drop(stream);
+        assert!(waited(client.list_models()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_list_running_models() {
+        let mut server = mockito::Server::new_async().await;
+        let ps = server
+            .mock("GET", "/api/ps")
+            .with_status(200)
+            .with_body(
+                r#"{"models":[{"name":"llama3.2:latest","model":"llama3.2:latest","size":5137025024,
+                "digest":"a80c4f17acd5","details":{"format":"gguf","family":"llama"},
+                "expires_at":"2024-06-04T14:38:31.83753-07:00","size_vram":4294967296}]}"#,
+            )
+            .create_async()
+            .await;
+        let client = OllamaClient::new(&server.url()).unwrap();
+
+        let running = client.list_running_models().await.unwrap();
+        ps.assert_async().await;
+        assert_eq!(running.models.len(), 1);
+        let model = &running.models[0];
+        assert_eq!(model.name, "llama3.2:latest");
+        assert_eq!(model.size, 5_137_025_024);
+        assert_eq!(model.size_vram, 4_294_967_296);
+        assert_eq!(model.expires_at, "2024-06-04T14:38:31.83753-07:00");
+    }
+
+    #[tokio::test]
+    async fn test_embeddings() {
+        let mut server = mockito::Server::new_async().await;
+        let embeddings = server
+            .mock("POST", "/api/embeddings")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "model": "nomic-embed-text",
+                "prompt": "Hello",
+            })))
+            .with_status(200)
+            .with_body(r#"{"embedding":[0.5,-0.25,0.125]}"#)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let embedding = client.embeddings("nomic-embed-text", "Hello").await.unwrap();
+        assert_eq!(embedding.len(), 3);
+        assert_eq!(embedding[1], -0.25);
+        embeddings.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_streams_progress_until_success() {
+        let mut server = mockito::Server::new_async().await;
+        let pull = server
+            .mock("POST", "/api/pull")
+            .match_body(mockito::Matcher::Json(serde_json::json!({"name": "llama3.2", "stream": true})))
+            .with_status(200)
+            .with_body(concat!(
+                "{\"status\":\"pulling manifest\"}\n",
+                "{\"status\":\"pulling dde5aa3fc5ff\",\"digest\":\"sha256:dde5\",\"total\":200,\"completed\":90}\n",
+                "{\"status\":\"verifying sha256 digest\"}\n",
+                "{\"status\":\"success\"}\n",
+                "{\"status\":\"ignored\"}\n",
+            ))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let progress: Vec<PullProgress> = client
+            .pull_model("llama3.2")
+            .await
+            .unwrap()
+            .map(|progress| progress.unwrap())
+            .collect()
+            .await;
+        assert_eq!(progress.len(), 4);
+        assert_eq!(progress[0].percent(), None);
+        assert_eq!(progress[1].percent(), Some(45));
+        assert!(progress[3].is_success());
+        pull.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_pull_model_error_ends_the_stream() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/pull")
+            .with_status(200)
+            .with_body("{\"status\":\"pulling manifest\"}\n{\"error\":\"pull model manifest: file does not exist\"}\n")
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let progress: Vec<Result<PullProgress>> = client.pull_model("missing").await.unwrap().collect().await;
+        assert_eq!(progress.len(), 2);
+        assert!(progress[1].as_ref().unwrap_err().to_string().contains("file does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_pull_fails_after_the_idle_timeout() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/pull")
+            .with_status(200)
+            .with_chunked_body(byte_pieces(vec![
+                b"{\"status\":\"pulling manifest\"}\n".to_vec(),
+                // Nothing is reported for longer than the idle timeout
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                b"{\"status\":\"success\"}\n".to_vec(),
+            ]))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_pull_idle_timeout(Duration::from_millis(50));
+        let progress: Vec<Result<PullProgress>> = client.pull_model("llama3.2").await.unwrap().collect().await;
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress[0].as_ref().unwrap().status, "pulling manifest");
+        assert!(progress[1].as_ref().unwrap_err().to_string().contains("stalled"));
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_after_version_probe() {
+        let mut server = mockito::Server::new_async().await;
+        let tags = server.mock("GET", "/api/tags").expect(0).create_async().await;
+        let version = server
+            .mock("GET", "/api/version")
+            .with_status(200)
+            .with_body(r#"{"version":"0.5.7"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let generate = server
+            .mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "model": "llama3.2",
+                "prompt": "",
+                "stream": false,
+                "keep_alive": PREWARM_KEEP_ALIVE,
+            })))
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","response":"","done":true}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_probe_endpoint(ProbeEndpoint::Version);
+        client.probe(Duration::from_secs(5)).await.unwrap();
+        client.prewarm("llama3.2").await.unwrap();
+
+        // The probe skips listing models, and the model is loaded with one empty request
+        tags.assert_async().await;
+        version.assert_async().await;
+        generate.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_generate_sends_and_returns_context() {
+        let mut server = mockito::Server::new_async().await;
+        let generate = server
+            .mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "model": "llama3.2",
+                "prompt": "And in French?",
+                "stream": false,
+                "system": "Be brief",
+                "context": [1, 2, 3],
+                "options": {"temperature": 0.5},
+            })))
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","response":"Bonjour","context":[1,2,3,4,5],"done":true}"#)
+            .create_async()
+            .await;
+        let client = OllamaClient::new(&server.url()).unwrap();
+
+        let request = GenerateRequest {
+            model: "llama3.2".to_string(),
+            prompt: "And in French?".to_string(),
+            stream: false,
+            keep_alive: None,
+            system: Some("Be brief".to_string()),
+            context: Some(vec![1, 2, 3]),
+            images: None,
+            format: None,
+            options: Some(crate::ollama::models::GenerationParameters {
+                temperature: Some(0.5),
+                ..Default::default()
+            }),
+        };
+        let response = client.generate(&request).await.unwrap();
+        generate.assert_async().await;
+        assert_eq!(response.response, "Bonjour");
+        assert_eq!(response.context, Some(vec![1, 2, 3, 4, 5]));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast_until_probe_succeeds() {
+        let mut server = mockito::Server::new_async().await;
+        let down = server
+            .mock("GET", "/api/version")
+            .with_status(503)
+            .expect(MAX_RETRY_ATTEMPTS as usize)
+            .create_async()
+            .await;
+
+        let clock = std::sync::Arc::new(crate::data::clock::FakeClock::new(chrono::Utc::now()));
+        let breaker =
+            CircuitBreaker::new(1, Duration::from_secs(60), Duration::from_secs(30)).with_clock(clock.clone());
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_probe_endpoint(ProbeEndpoint::Version)
+            .with_circuit_breaker(breaker);
+
+        // One request that fails every retry opens the circuit
+        assert!(client.version().await.is_err());
+        assert!(client.circuit_open());
+
+        // Further requests fail without reaching the server
+        let started = std::time::Instant::now();
+        let error = client.version().await.unwrap_err();
+        assert!(error.to_string().contains("offline"));
+        assert!(started.elapsed() < Duration::from_millis(100));
+        down.assert_async().await;
+
+        // Once the server is back and the cooldown is over, a successful probe closes the circuit
+        down.remove_async().await;
+        let up = server
+            .mock("GET", "/api/version")
+            .with_status(200)
+            .with_body(r#"{"version":"0.5.7"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        clock.advance(chrono::Duration::seconds(30));
+        client.probe(Duration::from_secs(5)).await.unwrap();
+        assert!(!client.circuit_open());
+        assert_eq!(client.version().await.unwrap(), "0.5.7");
+        up.assert_async().await;
+    }
+
+    fn chat_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "llama3.2".to_string(),
+            messages: vec![crate::ollama::models::ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+                images: None,
+            }],
+            stream: Some(true),
+            format: None,
+            parameters: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_without_streaming() {
+        let mut server = mockito::Server::new_async().await;
+        let chat = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"stream": false})))
+            .with_status(200)
+            .with_body(concat!(
+                r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":"Hi there"},"#,
+                r#""done":true,"done_reason":"stop","prompt_eval_count":26,"eval_count":4,"eval_duration":200000000}"#
+            ))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let response = client.chat_completion(&chat_request()).await.unwrap();
+        assert_eq!(response.message.content, "Hi there");
+        assert_eq!(response.done_reason.as_deref(), Some("stop"));
+        let metadata = response.metadata();
+        assert_eq!(metadata.prompt_eval_count, Some(26));
+        assert_eq!(metadata.tokens_per_second(), Some(20.0));
+        assert_eq!(metadata.total_duration, None);
+        chat.assert_async().await;
+
+        // Server errors are retried like streamed requests
+        chat.remove_async().await;
+        let down = server
+            .mock("POST", "/api/chat")
+            .with_status(503)
+            .expect(MAX_RETRY_ATTEMPTS as usize)
+            .create_async()
+            .await;
+        assert!(client.chat_completion(&chat_request()).await.is_err());
+        down.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_request_waits_for_retry_after() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("GET", "/api/version")
+            .with_status(429)
+            .with_header("retry-after", "1")
+            .expect(1)
+            .create_async()
+            .await;
+        let version = server
+            .mock("GET", "/api/version")
+            .with_status(200)
+            .with_body(r#"{"version":"0.5.1"}"#)
+            .create_async()
+            .await;
+        let client = OllamaClient::new(&server.url()).unwrap();
+
+        // The header's delay is longer than the first backoff, so waiting it out shows it was honored
+        let started = std::time::Instant::now();
+        assert_eq!(client.version().await.unwrap(), "0.5.1");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        limited.assert_async().await;
+        version.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_capped() {
+        let mut server = mockito::Server::new_async().await;
+        let limited = server
+            .mock("GET", "/api/version")
+            .with_status(429)
+            .with_header("retry-after", "3600")
+            .expect(MAX_RETRY_ATTEMPTS as usize)
+            .create_async()
+            .await;
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_max_retry_after(Duration::from_millis(50));
+
+        let started = std::time::Instant::now();
+        let error = client.version().await.unwrap_err();
+        assert!(error.to_string().contains("429"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        limited.assert_async().await;
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_is_sent_as_bearer_token() {
+        let mut server = mockito::Server::new_async().await;
+        let authenticated = server
+            .mock("GET", "/api/version")
+            .match_header("authorization", "Bearer secret-key")
+            .with_status(200)
+            .with_body(r#"{"version":"0.5.7"}"#)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap().with_api_key(Some("secret-key".to_string()));
+        assert_eq!(client.version().await.unwrap(), "0.5.7");
+        assert!(!format!("{:?}", client).contains("secret-key"));
+        authenticated.assert_async().await;
+
+        // Without a key no Authorization header is sent
+        let anonymous = server
+            .mock("GET", "/api/version")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_body(r#"{"version":"0.5.7"}"#)
+            .create_async()
+            .await;
+        let client = OllamaClient::new(&server.url()).unwrap();
+        assert_eq!(client.version().await.unwrap(), "0.5.7");
+        anonymous.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_custom_headers_are_sent_with_every_request() {
+        let mut server = mockito::Server::new_async().await;
+        let chat = server
+            .mock("POST", "/api/chat")
+            .match_header("x-tenant-id", "acme")
+            .match_header("content-type", "application/json")
+            .match_header("authorization", "Bearer secret-key")
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":"Hi"},"done":true}"#)
+            .create_async()
+            .await;
+
+        let headers = BTreeMap::from([
+            ("X-Tenant-Id".to_string(), "acme".to_string()),
+            ("Content-Type".to_string(), "text/plain".to_string()),
+            ("Authorization".to_string(), "Basic other".to_string()),
+            ("Not a header".to_string(), "ignored".to_string()),
+        ]);
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_api_key(Some("secret-key".to_string()))
+            .with_headers(&headers);
+        assert_eq!(client.chat_completion(&chat_request()).await.unwrap().message.content, "Hi");
+        chat.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_json_deserializes_content() {
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Answer {
+            answer: u32,
+        }
+
+        let mut server = mockito::Server::new_async().await;
+        let chat = server
+            .mock("POST", "/api/chat")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({"format": "json", "stream": false})))
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":"{\"answer\": 42}"},"done":true}"#)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let answer: Answer = client.chat_completion_json(&chat_request()).await.unwrap();
+        assert_eq!(answer, Answer { answer: 42 });
+        chat.assert_async().await;
+
+        // Content that doesn't fit the type is an error
+        assert!(client.chat_completion_json::<Vec<String>>(&chat_request()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_retries_empty_response() {
+        let mut server = mockito::Server::new_async().await;
+        let empty = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":""},"done":true}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let answer = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":"Hi there"},"done":true,"done_reason":"stop","eval_count":3}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let mut chunks = Vec::new();
+        let response = client
+            .stream_chat(&chat_request(), None, |chunk| chunks.push(chunk))
+            .await
+            .unwrap();
+
+        empty.assert_async().await;
+        answer.assert_async().await;
+        assert_eq!(response.content, "Hi there");
+        assert_eq!(chunks, vec!["Hi there".to_string()]);
+        assert!(response.images.is_empty());
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata.done_reason.as_deref(), Some("stop"));
+        assert_eq!(metadata.eval_count, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_returns_image_only_response() {
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"model":"imagegen","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"","images":["iVBORw0KGgo="]},"done":true}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let response = client.stream_chat(&chat_request(), None, |_| {}).await.unwrap();
+        assert_eq!(response.content, "");
+        assert_eq!(response.images, ["iVBORw0KGgo="]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_reports_persistent_empty_response() {
+        let mut server = mockito::Server::new_async().await;
+        let empty = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_body(r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":""},"done":true}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let result = client.stream_chat(&chat_request(), None, |_| {}).await;
+
+        empty.assert_async().await;
+        assert_eq!(result.unwrap_err(), ResponseError::Empty);
+    }
+
+    #[test]
+    fn test_append_until_stop_truncates_at_match() {
+        // The overshoot after the match is dropped
+        let stop = Regex::new(r"\n\n").unwrap();
+        let mut content = String::new();
+        let (kept, stopped) = append_until_stop(&mut content, "First paragraph.\n\nSecond", Some(&stop));
+        assert!(stopped);
+        assert_eq!(kept, "First paragraph.\n\n");
+        assert_eq!(content, "First paragraph.\n\n");
+
+        // A pattern that only matches across chunks stops mid-chunk
+        let stop = Regex::new(r"fn main\(\) \{\}\n```").unwrap();
+        let mut content = String::new();
+        let chunks = ["```rust\nfn ma", "in() {}\n``", "`\nThat is all.", " More text"];
+        let mut kept = Vec::new();
+        let mut stopped_at = None;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let (part, stopped) = append_until_stop(&mut content, chunk, Some(&stop));
+            kept.push(part);
+            if stopped {
+                stopped_at = Some(i);
+                break;
+            }
+        }
+        assert_eq!(stopped_at, Some(2));
+        assert_eq!(kept, vec!["```rust\nfn ma", "in() {}\n``", "`"]);
+        assert_eq!(content, "```rust\nfn main() {}\n```");
+    }
+
+    #[test]
+    fn test_append_without_stop_keeps_everything() {
+        let mut content = String::new();
+        assert_eq!(append_until_stop(&mut content, "Hello", None), ("Hello".to_string(), false));
+        assert_eq!(append_until_stop(&mut content, " world", None), (" world".to_string(), false));
+        assert_eq!(content, "Hello world");
+    }
+
     // Note: The following tests require mockito which has API changes
     // We'll need to update these tests in a future PR
     /*
@@ -357,4 +1595,228 @@ mod tests {
         assert!(!exists);
     }
     */
+
+    /// Body writer that sends each line after the given delay
+    fn delayed_lines(lines: Vec<(u64, &'static str)>) -> impl Fn(&mut dyn std::io::Write) -> std::io::Result<()> {
+        move |writer| {
+            for (delay_ms, line) in &lines {
+                std::thread::sleep(Duration::from_millis(*delay_ms));
+                writer.write_all(format!("{}\n", line).as_bytes())?;
+                writer.flush()?;
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_allows_slow_first_token() {
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_chunked_body(delayed_lines(vec![
+                // Slower to start than the idle timeout, as when a model is loading
+                (600, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"Hello"},"done":false}"#),
+                (50, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":" there"},"done":false}"#),
+                (50, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:02Z","message":{"role":"assistant","content":""},"done":true}"#),
+            ]))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_stream_timeouts(Duration::from_secs(5), Duration::from_millis(300));
+        let response = client.stream_chat(&chat_request(), None, |_| {}).await.unwrap();
+        assert_eq!(response.content, "Hello there");
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_fails_on_stall_after_first_token() {
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_chunked_body(delayed_lines(vec![
+                (0, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"Hello"},"done":false}"#),
+                (1000, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":""},"done":true}"#),
+            ]))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_stream_timeouts(Duration::from_secs(5), Duration::from_millis(300));
+        let result = client.stream_chat(&chat_request(), None, |_| {}).await;
+        assert!(matches!(result, Err(ResponseError::Failed(message)) if message.contains("stalled")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_fails_without_first_token() {
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_chunked_body(delayed_lines(vec![
+                (1000, r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"Hello"},"done":true}"#),
+            ]))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url())
+            .unwrap()
+            .with_stream_timeouts(Duration::from_millis(300), Duration::from_secs(5));
+        let result = client.stream_chat(&chat_request(), None, |_| {}).await;
+        assert!(matches!(result, Err(ResponseError::Failed(message)) if message.contains("No response")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_parses_openai_events() {
+        let events = concat!(
+            ": ping\n\n",
+            "data: {\"model\":\"llama3.2\",\"created\":1704067200,\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Caf\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"model\":\"llama3.2\",\"created\":1704067200,\"choices\":[{\"index\":0,\"delta\":{\"content\":\"é au lait\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"model\":\"llama3.2\",\"created\":1704067201,\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":4}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/v1/chat/completions")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_chunked_body(move |writer| {
+                // Split events, and the multi-byte character, across writes
+                for piece in events.as_bytes().chunks(37) {
+                    writer.write_all(piece)?;
+                    writer.flush()?;
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+                Ok(())
+            })
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap().with_api_style(ApiStyle::OpenAi);
+        let mut chunks = Vec::new();
+        let response = client
+            .stream_chat(&chat_request(), None, |chunk| chunks.push(chunk))
+            .await
+            .unwrap();
+
+        assert_eq!(response.content, "Café au lait");
+        assert_eq!(chunks, ["Caf", "é au lait"]);
+        let metadata = response.metadata.unwrap();
+        assert_eq!(metadata.done_reason.as_deref(), Some("stop"));
+        assert_eq!(metadata.prompt_eval_count, Some(12));
+        assert_eq!(metadata.eval_count, Some(4));
+        assert_eq!(metadata.created_at, "2024-01-01T00:00:01+00:00");
+    }
+
+    #[test]
+    fn test_to_curl_sends_what_the_client_would() {
+        let headers = BTreeMap::from([
+            ("X-Team".to_string(), "docs".to_string()),
+            ("X-Api-Key".to_string(), "proxy-secret".to_string()),
+        ]);
+        let client = OllamaClient::new("http://localhost:11434")
+            .unwrap()
+            .with_api_style(ApiStyle::OpenAi)
+            .with_api_key(Some("sk-live-123".to_string()))
+            .with_headers(&headers);
+        let mut request = chat_request();
+        request.parameters.temperature = Some(0.5);
+
+        let command = client.to_curl(&request, true).unwrap();
+        assert!(command.starts_with("curl -X POST 'http://localhost:11434/v1/chat/completions'"));
+        assert!(command.contains("-H 'x-team: docs'"));
+        assert!(command.contains("-H 'x-api-key: [REDACTED]'"));
+        assert!(command.contains("-H 'authorization: Bearer [REDACTED]'"));
+        assert!(!command.contains("sk-live-123") && !command.contains("proxy-secret"));
+        // The OpenAI body has the parameters at the top level
+        assert!(command.contains(r#""temperature": 0.5"#));
+
+        // Whole responses are requested from Ollama's chat API, without the key when there is none
+        let client = OllamaClient::new("http://localhost:11434").unwrap().with_api_style(ApiStyle::OpenAi);
+        let command = client.to_curl(&request, false).unwrap();
+        assert!(command.starts_with("curl -X POST 'http://localhost:11434/api/chat'"));
+        assert!(command.contains(r#""stream": false"#));
+        assert!(!command.contains("authorization"));
+    }
+
+    /// Body writer that sends the given byte pieces one after another
+    fn byte_pieces(pieces: Vec<Vec<u8>>) -> impl Fn(&mut dyn std::io::Write) -> std::io::Result<()> {
+        move |writer| {
+            for piece in &pieces {
+                writer.write_all(piece)?;
+                writer.flush()?;
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_joins_character_split_across_reads() {
+        let body = concat!(
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"Costs 5€ each"},"done":false}"#,
+            "\n",
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":""},"done":true}"#,
+            "\n",
+        )
+        .as_bytes();
+
+        // Split inside the three bytes of the euro sign
+        let split = body.iter().position(|&byte| byte == 0xE2).unwrap() + 1;
+        let mut server = mockito::Server::new_async().await;
+        let _chat = server
+            .mock("POST", "/api/chat")
+            .with_status(200)
+            .with_chunked_body(byte_pieces(vec![body[..split].to_vec(), body[split..].to_vec()]))
+            .create_async()
+            .await;
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let mut chunks = Vec::new();
+        let response = client.stream_chat(&chat_request(), None, |chunk| chunks.push(chunk)).await.unwrap();
+        assert_eq!(response.content, "Costs 5€ each");
+        assert_eq!(chunks, ["Costs 5€ each"]);
+        assert!(!response.content.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_ndjson_reads_parse_into_one_chunk_per_object() {
+        let objects = [
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"Hel"},"done":false}"#,
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"lo"},"done":false}"#,
+            r#"{"model":"llama3.2","created_at":"2024-01-01T00:00:01Z","message":{"role":"assistant","content":""},"done":true,"done_reason":"stop"}"#,
+        ];
+        let body = format!("{}\n", objects.join("\n"));
+
+        // The first read holds a whole object and half of the next, the second the rest
+        let split = objects[0].len() + 1 + objects[1].len() / 2;
+        let mut decoder = FrameDecoder::new(Framing::Ndjson);
+        let first = decoder.push(&body.as_bytes()[..split]);
+        assert_eq!(first.len(), 1);
+        let second = decoder.push(&body.as_bytes()[split..]);
+        assert_eq!(second.len(), 2);
+        assert!(decoder.finish().is_none());
+
+        let chunks: Vec<ChatCompletionChunk> = first
+            .iter()
+            .chain(&second)
+            .map(|frame| parse_chunk(ApiStyle::Ollama, frame).unwrap())
+            .collect();
+        let contents: Vec<&str> = chunks.iter().map(|chunk| chunk.message.content.as_str()).collect();
+        assert_eq!(contents, ["Hel", "lo", ""]);
+        assert!(chunks[2].done);
+    }
+
+    #[test]
+    fn test_parse_chunk_replaces_invalid_utf8() {
+        let mut frame = br#"{"model":"llama3.2","created_at":"2024-01-01T00:00:00Z","message":{"role":"assistant","content":"ok "#.to_vec();
+        frame.push(0xFF);
+        frame.extend_from_slice(br#""},"done":false}"#);
+
+        let chunk = parse_chunk(ApiStyle::Ollama, &frame).unwrap();
+        assert_eq!(chunk.message.content, "ok \u{FFFD}");
+    }
 }
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+use std::sync::{Arc, Mutex};
+
+use crate::data::clock::{system_clock, Clock};
+
+/// Whether requests are let through to the API
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BreakerState {
+    /// Requests go through, and recent failures are counted
+    Closed { failures: Vec<DateTime<Utc>> },
+    /// Requests fail fast until the cooldown ends
+    Open { until: DateTime<Utc> },
+    /// A single trial request was let through to test whether the API has recovered
+    HalfOpen { since: DateTime<Utc> },
+}
+
+/// Stops sending requests to an API that keeps failing, so a down server fails fast
+///
+/// After `threshold` failures within `window`, the circuit opens and requests are
+/// refused for `cooldown`. Then one trial request is let through: the circuit
+/// closes if it succeeds and opens again if it fails. Clones share their state.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Current state, shared by every clone
+    state: Arc<Mutex<BreakerState>>,
+    /// Number of failures within the window that opens the circuit
+    threshold: usize,
+    /// Period over which failures are counted
+    window: Duration,
+    /// How long the circuit stays open before a trial request
+    cooldown: Duration,
+    /// Source of the current time
+    clock: Arc<dyn Clock>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker that opens after `threshold` failures within `window`
+    pub fn new(threshold: usize, window: std::time::Duration, cooldown: std::time::Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: Vec::new() })),
+            threshold: threshold.max(1),
+            window: Duration::from_std(window).unwrap_or(Duration::MAX),
+            cooldown: Duration::from_std(cooldown).unwrap_or(Duration::MAX),
+            clock: system_clock(),
+        }
+    }
+
+    /// Use the given clock instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Check whether a request may be sent, returning how long until the next trial if not
+    ///
+    /// Once the cooldown is over this lets a single trial request through, and
+    /// another one only if the trial's outcome isn't recorded within a cooldown.
+    pub fn check(&self) -> Result<(), std::time::Duration> {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let next_trial = match *state {
+            BreakerState::Closed { .. } => return Ok(()),
+            BreakerState::Open { until } => until,
+            BreakerState::HalfOpen { since } => since + self.cooldown,
+        };
+        if now < next_trial {
+            return Err((next_trial - now).to_std().unwrap_or_default());
+        }
+        info!("Letting a trial request through to test whether the API has recovered");
+        *state = BreakerState::HalfOpen { since: now };
+        Ok(())
+    }
+
+    /// Check whether requests are currently refused
+    pub fn is_open(&self) -> bool {
+        !matches!(*self.state.lock().unwrap(), BreakerState::Closed { .. })
+    }
+
+    /// Record a successful request, closing the circuit
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !matches!(*state, BreakerState::Closed { .. }) {
+            info!("API recovered, closing the circuit");
+        }
+        *state = BreakerState::Closed { failures: Vec::new() };
+    }
+
+    /// Record a failed request, opening the circuit once there are too many
+    pub fn record_failure(&self) {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let open = match &mut *state {
+            BreakerState::Closed { failures } => {
+                failures.retain(|&failed_at| now - failed_at < self.window);
+                failures.push(now);
+                failures.len() >= self.threshold
+            }
+            // The trial failed, so the API is still down
+            BreakerState::HalfOpen { .. } => true,
+            BreakerState::Open { .. } => false,
+        };
+        if open {
+            warn!("API keeps failing, refusing requests for {}s", self.cooldown.num_seconds());
+            *state = BreakerState::Open { until: now + self.cooldown };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::clock::FakeClock;
+    use chrono::TimeZone;
+
+    fn breaker(clock: &Arc<FakeClock>) -> CircuitBreaker {
+        CircuitBreaker::new(3, std::time::Duration::from_secs(60), std::time::Duration::from_secs(30))
+            .with_clock(clock.clone())
+    }
+
+    fn clock() -> Arc<FakeClock> {
+        Arc::new(FakeClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()))
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures_and_fails_fast() {
+        let clock = clock();
+        let breaker = breaker(&clock);
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        assert!(!breaker.is_open());
+
+        breaker.record_failure();
+        assert!(breaker.is_open());
+        assert_eq!(breaker.check(), Err(std::time::Duration::from_secs(30)));
+
+        clock.advance(Duration::seconds(10));
+        assert_eq!(breaker.check(), Err(std::time::Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_failures_outside_the_window_are_forgotten() {
+        let clock = clock();
+        let breaker = breaker(&clock);
+
+        breaker.record_failure();
+        breaker.record_failure();
+        clock.advance(Duration::seconds(61));
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+
+        // A success resets the count
+        breaker.record_success();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_half_open_trial_closes_or_reopens() {
+        let clock = clock();
+        let breaker = breaker(&clock);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+
+        // After the cooldown, one trial is let through while others still fail fast
+        clock.advance(Duration::seconds(30));
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_err());
+
+        // A failed trial opens the circuit for another cooldown
+        breaker.record_failure();
+        assert_eq!(breaker.check(), Err(std::time::Duration::from_secs(30)));
+
+        // A successful trial closes it
+        clock.advance(Duration::seconds(30));
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_ok());
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::{future::Future, pin::Pin, task::Poll};
+#[cfg(unix)]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Scheme prefix for an Ollama API served over a unix socket
+pub const UNIX_SCHEME: &str = "http+unix://";
+
+/// Where the Ollama API is served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    /// An `http://` or `https://` base URL, including bracketed IPv6 hosts
+    Http(String),
+    /// A unix socket path given as `http+unix:///path/to/socket`
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    /// Parse and validate an API URL
+    pub fn parse(api_url: &str) -> Result<Self> {
+        if let Some(path) = api_url.strip_prefix(UNIX_SCHEME) {
+            let path = Path::new(path);
+            if !path.is_absolute() || path.file_name().is_none() {
+                bail!("Unix socket URL must contain an absolute socket path, e.g. http+unix:///var/run/ollama.sock");
+            }
+            return Ok(Self::Unix(path.to_path_buf()));
+        }
+
+        if !api_url.starts_with("http://") && !api_url.starts_with("https://") {
+            bail!("API URL must start with http://, https:// or http+unix://");
+        }
+
+        // Catches malformed hosts such as unbracketed or unterminated IPv6 addresses
+        let url = url::Url::parse(api_url).with_context(|| format!("Invalid API URL: {}", api_url))?;
+        if url.host().is_none() {
+            bail!("API URL must include a host");
+        }
+
+        Ok(Self::Http(api_url.trim_end_matches('/').to_string()))
+    }
+}
+
+/// Sends requests to an Ollama API served on a unix socket
+///
+/// Requests are built with reqwest as for any other endpoint and sent over the
+/// socket by hyper, so only processes allowed to open the socket can reach the API.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub struct UnixSocketClient {
+    client: hyper::Client<UnixConnector>,
+}
+
+#[cfg(unix)]
+impl UnixSocketClient {
+    /// Create a client connecting to `socket` for each request
+    pub fn new(socket: PathBuf) -> Self {
+        let connector = UnixConnector {
+            socket: std::sync::Arc::new(socket),
+        };
+        Self {
+            client: hyper::Client::builder().build(connector),
+        }
+    }
+
+    /// Send a request over the socket, waiting no longer than its timeout for the response
+    ///
+    /// The request's URL only provides the path and `Host` header; the host itself isn't contacted.
+    pub async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response> {
+        let mut builder = hyper::Request::builder()
+            .method(request.method().clone())
+            .uri(request.url().as_str());
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(request.headers().clone());
+        }
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default().to_vec();
+        let hyper_request = builder.body(hyper::Body::from(body)).context("Failed to build request")?;
+
+        let response = self.client.request(hyper_request);
+        let response = match request.timeout() {
+            Some(timeout) => tokio::time::timeout(*timeout, response)
+                .await
+                .map_err(|_| anyhow::anyhow!("Request timed out after {}s", timeout.as_secs_f32()))?,
+            None => response.await,
+        };
+        Ok(reqwest::Response::from(response?))
+    }
+}
+
+/// Opens a connection to the unix socket for each connection hyper asks for, whatever the URI
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+struct UnixConnector {
+    socket: std::sync::Arc<PathBuf>,
+}
+
+#[cfg(unix)]
+impl hyper::service::Service<hyper::Uri> for UnixConnector {
+    type Response = UnixConnection;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = std::io::Result<UnixConnection>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: hyper::Uri) -> Self::Future {
+        let socket = self.socket.clone();
+        Box::pin(async move { tokio::net::UnixStream::connect(socket.as_path()).await.map(UnixConnection) })
+    }
+}
+
+/// A connection to the unix socket, as hyper expects connections to be
+#[cfg(unix)]
+struct UnixConnection(tokio::net::UnixStream);
+
+#[cfg(unix)]
+impl hyper::client::connect::Connection for UnixConnection {
+    fn connected(&self) -> hyper::client::connect::Connected {
+        hyper::client::connect::Connected::new()
+    }
+}
+
+#[cfg(unix)]
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http() {
+        assert_eq!(
+            Endpoint::parse("http://localhost:11434/").unwrap(),
+            Endpoint::Http("http://localhost:11434".to_string())
+        );
+        assert_eq!(
+            Endpoint::parse("https://ollama.example.com").unwrap(),
+            Endpoint::Http("https://ollama.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_ipv6() {
+        assert_eq!(
+            Endpoint::parse("http://[::1]:11434").unwrap(),
+            Endpoint::Http("http://[::1]:11434".to_string())
+        );
+        assert_eq!(
+            Endpoint::parse("http://[fe80::1]/").unwrap(),
+            Endpoint::Http("http://[fe80::1]".to_string())
+        );
+
+        assert!(Endpoint::parse("http://::1:11434").is_err());
+        assert!(Endpoint::parse("http://[::1:11434").is_err());
+        assert!(Endpoint::parse("http://[not-an-address]:11434").is_err());
+    }
+
+    #[test]
+    fn test_parse_unix_socket() {
+        assert_eq!(
+            Endpoint::parse("http+unix:///var/run/ollama.sock").unwrap(),
+            Endpoint::Unix(PathBuf::from("/var/run/ollama.sock"))
+        );
+
+        assert!(Endpoint::parse("http+unix://").is_err());
+        assert!(Endpoint::parse("http+unix://relative/ollama.sock").is_err());
+        assert!(Endpoint::parse("http+unix:///").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_schemes() {
+        assert!(Endpoint::parse("localhost:11434").is_err());
+        assert!(Endpoint::parse("unix:///var/run/ollama.sock").is_err());
+        assert!(Endpoint::parse("http://").is_err());
+    }
+}
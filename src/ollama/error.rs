@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Why a chat response could not be produced
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+pub enum ResponseError {
+    /// The model completed the stream without producing any content
+    #[error("Received empty response from Ollama")]
+    Empty,
+    /// The request or the stream failed
+    #[error("{0}")]
+    Failed(String),
+}
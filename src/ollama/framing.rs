@@ -0,0 +1,169 @@
+/// Payload of a Server-Sent Events stream that marks its end
+const SSE_DONE: &[u8] = b"[DONE]";
+
+/// How the chunks of a streamed response are delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// One JSON object per line, as sent by `/api/chat`
+    Ndjson,
+    /// Server-Sent Events (`data: {...}` followed by a blank line), as sent by `/v1/chat/completions`
+    Sse,
+}
+
+/// Splits the bytes of a streamed response into the payloads of complete frames
+///
+/// Bytes are buffered until a frame is complete, so frames split across network
+/// reads are reassembled.
+#[derive(Debug)]
+pub struct FrameDecoder {
+    /// Framing of the stream
+    framing: Framing,
+    /// Bytes of the line being received
+    buffer: Vec<u8>,
+    /// Data lines of the event being received, for SSE
+    event: Option<Vec<u8>>,
+    /// Whether the stream has ended
+    done: bool,
+}
+
+impl FrameDecoder {
+    /// Create a decoder for a stream with the given framing
+    pub fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            buffer: Vec::new(),
+            event: None,
+            done: false,
+        }
+    }
+
+    /// Check whether the stream has ended, either at the SSE `[DONE]` sentinel or after `finish`
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Add received bytes, returning the payloads of the frames they complete
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut payloads = Vec::new();
+        for &byte in bytes {
+            if self.done {
+                break;
+            }
+            if byte == b'\n' {
+                let mut line = std::mem::take(&mut self.buffer);
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                payloads.extend(self.end_line(line));
+            } else {
+                self.buffer.push(byte);
+            }
+        }
+        payloads
+    }
+
+    /// End the stream, returning the payload of a final frame that wasn't terminated
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        if self.done {
+            return None;
+        }
+        let line = std::mem::take(&mut self.buffer);
+        let payload = match self.framing {
+            Framing::Ndjson => self.end_line(line),
+            // An event is only complete at a blank line
+            Framing::Sse => self.end_line(line).or_else(|| self.end_line(Vec::new())),
+        };
+        self.done = true;
+        payload
+    }
+
+    /// Handle a complete line, returning the payload of the frame it completes
+    fn end_line(&mut self, line: Vec<u8>) -> Option<Vec<u8>> {
+        match self.framing {
+            Framing::Ndjson => (!line.iter().all(u8::is_ascii_whitespace)).then_some(line),
+            Framing::Sse => {
+                if line.is_empty() {
+                    let payload = self.event.take()?;
+                    if payload == SSE_DONE {
+                        self.done = true;
+                        return None;
+                    }
+                    return Some(payload);
+                }
+
+                // Comments keep the connection alive, and fields other than data carry nothing we use
+                let data = line.strip_prefix(b"data:")?;
+                let data = data.strip_prefix(b" ").unwrap_or(data);
+                match &mut self.event {
+                    Some(event) => {
+                        event.push(b'\n');
+                        event.extend_from_slice(data);
+                    }
+                    None => self.event = Some(data.to_vec()),
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `bytes` to a decoder in pieces of `size` bytes, collecting the payloads as strings
+    fn decode_in_pieces(framing: Framing, bytes: &[u8], size: usize) -> (Vec<String>, bool) {
+        let mut decoder = FrameDecoder::new(framing);
+        let mut payloads: Vec<Vec<u8>> = bytes.chunks(size).flat_map(|piece| decoder.push(piece)).collect();
+        let done_before_finish = decoder.is_done();
+        payloads.extend(decoder.finish());
+        let payloads = payloads
+            .into_iter()
+            .map(|payload| String::from_utf8(payload).unwrap())
+            .collect();
+        (payloads, done_before_finish)
+    }
+
+    #[test]
+    fn test_sse_stream_split_across_buffers() {
+        let stream = concat!(
+            ": keepalive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hé\"}}]}\n\n",
+            "event: message\r\n",
+            "id: 2\r\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"llo\"}}]}\r\n\r\n",
+            ":\n\n",
+            "data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"ignored\":true}\n\n",
+        );
+        let expected = [
+            "{\"choices\":[{\"delta\":{\"content\":\"Hé\"}}]}",
+            "{\"choices\":[{\"delta\":{\"content\":\"llo\"}}]}",
+            "{\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}",
+        ];
+
+        // Every split point, including ones inside the multi-byte character, gives the same frames
+        for size in 1..=stream.len() {
+            let (payloads, done) = decode_in_pieces(Framing::Sse, stream.as_bytes(), size);
+            assert_eq!(payloads, expected, "split into pieces of {} bytes", size);
+            assert!(done);
+        }
+    }
+
+    #[test]
+    fn test_sse_joins_multi_line_data() {
+        let (payloads, done) = decode_in_pieces(Framing::Sse, b"data: first\ndata:second\n\ndata: last", 4);
+        assert_eq!(payloads, ["first\nsecond", "last"]);
+        assert!(!done);
+    }
+
+    #[test]
+    fn test_ndjson_lines_split_across_buffers() {
+        let stream = "{\"n\":1}\n\n{\"n\":2}\r\n{\"n\":3}";
+        for size in 1..=stream.len() {
+            let (payloads, _) = decode_in_pieces(Framing::Ndjson, stream.as_bytes(), size);
+            assert_eq!(payloads, ["{\"n\":1}", "{\"n\":2}", "{\"n\":3}"]);
+        }
+    }
+}
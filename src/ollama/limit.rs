@@ -0,0 +1,80 @@
+use log::debug;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Bounds how many requests to the Ollama API may be in flight at once
+#[derive(Debug, Clone)]
+pub struct RequestLimiter {
+    /// Permits shared by every request started through this limiter
+    permits: Arc<Semaphore>,
+}
+
+impl RequestLimiter {
+    /// Create a limiter allowing the given number of concurrent requests
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Wait for a permit, which is released when dropped
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        if self.permits.available_permits() == 0 {
+            debug!("Waiting for a free request slot");
+        }
+        self.permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("request semaphore is never closed")
+    }
+
+    /// Run a future while holding a permit
+    pub async fn run<F: Future>(&self, future: F) -> F::Output {
+        let _permit = self.acquire().await;
+        future.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_single_permit_serializes_requests() {
+        let limiter = RequestLimiter::new(1);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let request = || {
+            let limiter = limiter.clone();
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                limiter
+                    .run(async {
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await
+            }
+        };
+
+        tokio::join!(request(), request());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_multiple_permits_allow_parallel_requests() {
+        let limiter = RequestLimiter::new(2);
+        let first = limiter.acquire().await;
+        let second = tokio::time::timeout(Duration::from_millis(100), limiter.acquire()).await;
+        assert!(second.is_ok());
+        drop(first);
+    }
+}
@@ -1,3 +1,9 @@
 pub mod api;
+pub mod breaker;
+pub mod endpoint;
+pub mod error;
+pub mod framing;
+pub mod limit;
 pub mod models;
-
+pub mod replay;
+pub mod request;
@@ -1,4 +1,6 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::data::conversation::ResponseMetadata;
 
 /// Request to check if a model exists
 #[derive(Debug, Serialize)]
@@ -53,6 +55,139 @@ pub struct ModelInfo {
     pub digest: Option<String>,
 }
 
+/// Response from a request for the models loaded into memory
+#[derive(Debug, Deserialize)]
+pub struct RunningModelsResponse {
+    /// Models currently loaded
+    pub models: Vec<RunningModel>,
+}
+
+/// A model loaded into memory
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RunningModel {
+    /// Name of the model
+    pub name: String,
+    /// Memory the model takes up in bytes
+    pub size: u64,
+    /// Part of `size` loaded into video memory, in bytes
+    #[serde(default)]
+    pub size_vram: u64,
+    /// When the model will be unloaded unless it is used again
+    pub expires_at: String,
+}
+
+/// Response to a version request
+#[derive(Debug, Deserialize)]
+pub struct VersionResponse {
+    /// Version of the Ollama server
+    pub version: String,
+}
+
+/// Request to generate a completion, also used without a prompt to load a model
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerateRequest {
+    /// Model to use
+    pub model: String,
+    /// Prompt to complete, empty to only load the model
+    pub prompt: String,
+    /// Whether to stream the response
+    pub stream: bool,
+    /// How long the model stays loaded after the request, e.g. "5m"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+    /// System prompt, replacing the model's default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Context returned by the previous response, so the conversation so far isn't processed again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<Vec<i64>>,
+    /// Base64-encoded images sent with the prompt, for multimodal models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>,
+    /// Format the response must follow, such as `json` for a single JSON object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Sampling parameters, sent as Ollama's `options`
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_options")]
+    pub options: Option<GenerationParameters>,
+}
+
+/// Serialize generation parameters the way Ollama names them
+fn serialize_options<S: Serializer>(
+    parameters: &Option<GenerationParameters>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    parameters.as_ref().map(GenerationParameters::ollama_options).serialize(serializer)
+}
+
+/// Response to a generate request
+#[derive(Debug, Deserialize)]
+pub struct GenerateResponse {
+    /// Model used for the response
+    pub model: String,
+    /// Generated text, empty when only loading the model
+    #[serde(default)]
+    pub response: String,
+    /// Context to send with the next request to continue from this response
+    #[serde(default)]
+    pub context: Option<Vec<i64>>,
+    /// Done flag
+    pub done: bool,
+}
+
+/// Request to embed a prompt
+#[derive(Debug, Serialize)]
+pub struct EmbeddingsRequest {
+    /// Model to use
+    pub model: String,
+    /// Text to embed
+    pub prompt: String,
+}
+
+/// Response to an embeddings request
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingsResponse {
+    /// Embedding vector of the prompt
+    pub embedding: Vec<f32>,
+}
+
+/// Request to download a model
+#[derive(Debug, Serialize)]
+pub struct PullRequest {
+    /// Name of the model to download
+    pub name: String,
+    /// Whether to stream progress
+    pub stream: bool,
+}
+
+/// Progress of a model download, one per line of the streamed response
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PullProgress {
+    /// What the download is doing, e.g. "pulling manifest", ending with "success"
+    pub status: String,
+    /// Bytes of the current layer downloaded so far
+    #[serde(default)]
+    pub completed: Option<u64>,
+    /// Size of the current layer in bytes
+    #[serde(default)]
+    pub total: Option<u64>,
+}
+
+impl PullProgress {
+    /// Check whether this is the final status of a finished download
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+
+    /// Get the percentage of the current layer downloaded, if its size is known
+    pub fn percent(&self) -> Option<u64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => Some(completed.min(total) * 100 / total),
+            _ => None,
+        }
+    }
+}
+
 /// Chat message for requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -60,25 +195,164 @@ pub struct ChatMessage {
     pub role: String,
     /// Content of the message
     pub content: String,
+    /// Base64-encoded images sent with the message, for multimodal models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<String>>,
 }
 
+/// Parts of the names of models that accept images
+const MULTIMODAL_MODEL_NAMES: [&str; 9] = [
+    "llava",
+    "vision",
+    "moondream",
+    "minicpm-v",
+    "gemma3",
+    "qwen2.5vl",
+    "qwen2-vl",
+    "mistral-small3",
+    "llama4",
+];
+
+/// Check whether a model accepts images, judging by its name
+///
+/// Models that don't accept images ignore the ones sent to them.
+pub fn is_multimodal_model(model: &str) -> bool {
+    let model = model.to_lowercase();
+    MULTIMODAL_MODEL_NAMES.iter().any(|name| model.contains(name))
+}
+
+/// `format` that makes the model answer with a single JSON object
+pub const JSON_FORMAT: &str = "json";
+
 /// Request to generate a chat completion
-#[derive(Debug, Clone, Serialize)]
+///
+/// Serializes as the body of Ollama's `/api/chat`, which reads sampling
+/// parameters from a nested `options` object; see `openai_body` for the
+/// OpenAI-compatible form.
+#[derive(Debug, Clone)]
 pub struct ChatCompletionRequest {
     /// Model to use
     pub model: String,
     /// Messages in the conversation
     pub messages: Vec<ChatMessage>,
     /// Whether to stream the response
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    /// Format the response must follow, such as `json` for a single JSON object
+    pub format: Option<String>,
     /// Additional generation parameters
-    #[serde(flatten)]
     pub parameters: GenerationParameters,
 }
 
+impl ChatCompletionRequest {
+    /// Get the body of an OpenAI-compatible `/v1/chat/completions` request, with top-level parameters
+    pub fn openai_body(&self) -> OpenAiChatRequest<'_> {
+        let parameters = &self.parameters;
+        let json = self.format.as_deref() == Some(JSON_FORMAT);
+        OpenAiChatRequest {
+            model: &self.model,
+            messages: &self.messages,
+            stream: self.stream,
+            response_format: json.then_some(OpenAiResponseFormat { kind: "json_object" }),
+            temperature: parameters.temperature,
+            top_p: parameters.top_p,
+            max_tokens: parameters.max_tokens,
+            presence_penalty: parameters.presence_penalty,
+            frequency_penalty: parameters.frequency_penalty,
+            stop: parameters.stop.as_deref(),
+            seed: parameters.seed,
+        }
+    }
+}
+
+impl Serialize for ChatCompletionRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parameters = &self.parameters;
+        OllamaChatRequest {
+            model: &self.model,
+            messages: &self.messages,
+            stream: self.stream,
+            format: self.format.as_deref(),
+            think: parameters.think,
+            options: parameters.ollama_options(),
+        }
+        .serialize(serializer)
+    }
+}
+
+
+/// Body of a request to Ollama's `/api/chat`
+#[derive(Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
+    options: OllamaOptions<'a>,
+}
+
+/// Sampling options as Ollama names them
+#[derive(Serialize)]
+struct OllamaOptions<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+}
+
+/// OpenAI's equivalent of Ollama's `format`
+#[derive(Debug, Serialize)]
+struct OpenAiResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+/// Body of a request to an OpenAI-compatible `/v1/chat/completions`
+///
+/// OpenAI has no `top_k`, `think` or `num_ctx`, so those aren't sent.
+#[derive(Debug, Serialize)]
+pub struct OpenAiChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAiResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
 /// Parameters for text generation
-#[derive(Debug, Default, Clone, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GenerationParameters {
     /// Temperature for sampling (higher = more random)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -101,6 +375,68 @@ pub struct GenerationParameters {
     /// Stop sequences (stop generation when these are generated)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
+    /// Whether reasoning models should think before answering (None = model default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<bool>,
+    /// Seed for the random number generator, so the same prompt gives the same response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Size of the context window in tokens (None = model default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+}
+
+impl GenerationParameters {
+    /// Get the sampling options as Ollama names them
+    fn ollama_options(&self) -> OllamaOptions<'_> {
+        OllamaOptions {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            top_k: self.top_k,
+            num_predict: self.max_tokens,
+            presence_penalty: self.presence_penalty,
+            frequency_penalty: self.frequency_penalty,
+            stop: self.stop.as_deref(),
+            seed: self.seed,
+            num_ctx: self.num_ctx,
+        }
+    }
+
+    /// Describe the parameters that are set, one `name: value` per line
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(temperature) = self.temperature {
+            lines.push(format!("temperature: {}", temperature));
+        }
+        if let Some(top_p) = self.top_p {
+            lines.push(format!("top_p: {}", top_p));
+        }
+        if let Some(top_k) = self.top_k {
+            lines.push(format!("top_k: {}", top_k));
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            lines.push(format!("max_tokens: {}", max_tokens));
+        }
+        if let Some(presence_penalty) = self.presence_penalty {
+            lines.push(format!("presence_penalty: {}", presence_penalty));
+        }
+        if let Some(frequency_penalty) = self.frequency_penalty {
+            lines.push(format!("frequency_penalty: {}", frequency_penalty));
+        }
+        if let Some(stop) = &self.stop {
+            lines.push(format!("stop: {}", stop.join(", ")));
+        }
+        if let Some(think) = self.think {
+            lines.push(format!("think: {}", think));
+        }
+        if let Some(seed) = self.seed {
+            lines.push(format!("seed: {}", seed));
+        }
+        if let Some(num_ctx) = self.num_ctx {
+            lines.push(format!("num_ctx: {}", num_ctx));
+        }
+        lines.join("\n")
+    }
 }
 
 /// Response from chat completion request (non-streaming)
@@ -114,6 +450,44 @@ pub struct ChatCompletionResponse {
     pub message: ChatMessage,
     /// Done flag
     pub done: bool,
+    /// Why generation stopped, e.g. "stop" or "length"
+    #[serde(default)]
+    pub done_reason: Option<String>,
+    /// Total time spent generating the response in nanoseconds
+    #[serde(default)]
+    pub total_duration: Option<u64>,
+    /// Time spent loading the model in nanoseconds
+    #[serde(default)]
+    pub load_duration: Option<u64>,
+    /// Number of tokens in the prompt
+    #[serde(default)]
+    pub prompt_eval_count: Option<u64>,
+    /// Time spent evaluating the prompt in nanoseconds
+    #[serde(default)]
+    pub prompt_eval_duration: Option<u64>,
+    /// Number of tokens in the response
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    /// Time spent generating the response in nanoseconds
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+}
+
+impl ChatCompletionResponse {
+    /// Extract the response statistics
+    pub fn metadata(&self) -> ResponseMetadata {
+        ResponseMetadata {
+            model: self.model.clone(),
+            created_at: self.created_at.clone(),
+            done_reason: self.done_reason.clone(),
+            total_duration: self.total_duration,
+            load_duration: self.load_duration,
+            prompt_eval_count: self.prompt_eval_count,
+            prompt_eval_duration: self.prompt_eval_duration,
+            eval_count: self.eval_count,
+            eval_duration: self.eval_duration,
+        }
+    }
 }
 
 /// Streaming response chunk from chat completion
@@ -127,6 +501,123 @@ pub struct ChatCompletionChunk {
     pub message: ChatMessageDelta,
     /// Done flag
     pub done: bool,
+    /// Why generation stopped (final chunk only)
+    #[serde(default)]
+    pub done_reason: Option<String>,
+    /// Total time spent generating the response in nanoseconds (final chunk only)
+    #[serde(default)]
+    pub total_duration: Option<u64>,
+    /// Time spent loading the model in nanoseconds (final chunk only)
+    #[serde(default)]
+    pub load_duration: Option<u64>,
+    /// Number of tokens in the prompt (final chunk only)
+    #[serde(default)]
+    pub prompt_eval_count: Option<u64>,
+    /// Time spent evaluating the prompt in nanoseconds (final chunk only)
+    #[serde(default)]
+    pub prompt_eval_duration: Option<u64>,
+    /// Number of tokens in the response (final chunk only)
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    /// Time spent generating the response in nanoseconds (final chunk only)
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+}
+
+impl ChatCompletionChunk {
+    /// Extract the response statistics carried by this chunk
+    pub fn metadata(&self) -> ResponseMetadata {
+        ResponseMetadata {
+            model: self.model.clone(),
+            created_at: self.created_at.clone(),
+            done_reason: self.done_reason.clone(),
+            total_duration: self.total_duration,
+            load_duration: self.load_duration,
+            prompt_eval_count: self.prompt_eval_count,
+            prompt_eval_duration: self.prompt_eval_duration,
+            eval_count: self.eval_count,
+            eval_duration: self.eval_duration,
+        }
+    }
+}
+
+/// Streaming response chunk from an OpenAI-compatible chat completion
+#[derive(Debug, Deserialize)]
+pub struct OpenAiChunk {
+    /// Model used for the response
+    #[serde(default)]
+    pub model: String,
+    /// Created timestamp in seconds since the Unix epoch
+    #[serde(default)]
+    pub created: i64,
+    /// Choices generated for the request, of which only the first is used
+    #[serde(default)]
+    pub choices: Vec<OpenAiChoice>,
+    /// Token counts, sent with the final chunk by some servers
+    #[serde(default)]
+    pub usage: Option<OpenAiUsage>,
+}
+
+/// A choice within an OpenAI-compatible streaming chunk
+#[derive(Debug, Deserialize)]
+pub struct OpenAiChoice {
+    /// Content added by the chunk
+    #[serde(default)]
+    pub delta: OpenAiDelta,
+    /// Why generation stopped (final chunk only)
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+/// Delta of a message within an OpenAI-compatible streaming chunk
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenAiDelta {
+    /// Role of the message sender (first chunk only)
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Content of the message delta
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// Token counts of an OpenAI-compatible response
+#[derive(Debug, Deserialize)]
+pub struct OpenAiUsage {
+    /// Number of tokens in the prompt
+    pub prompt_tokens: Option<u64>,
+    /// Number of tokens in the response
+    pub completion_tokens: Option<u64>,
+}
+
+impl From<OpenAiChunk> for ChatCompletionChunk {
+    fn from(chunk: OpenAiChunk) -> Self {
+        let choice = chunk.choices.into_iter().next();
+        let (role, content, finish_reason) = match choice {
+            Some(choice) => (choice.delta.role, choice.delta.content, choice.finish_reason),
+            None => (None, None, None),
+        };
+        let created_at = chrono::DateTime::from_timestamp(chunk.created, 0)
+            .map(|created| created.to_rfc3339())
+            .unwrap_or_default();
+
+        Self {
+            model: chunk.model,
+            created_at,
+            message: ChatMessageDelta {
+                role,
+                content: content.unwrap_or_default(),
+                images: Vec::new(),
+            },
+            done: finish_reason.is_some(),
+            done_reason: finish_reason,
+            total_duration: None,
+            load_duration: None,
+            prompt_eval_count: chunk.usage.as_ref().and_then(|usage| usage.prompt_tokens),
+            prompt_eval_duration: None,
+            eval_count: chunk.usage.as_ref().and_then(|usage| usage.completion_tokens),
+            eval_duration: None,
+        }
+    }
 }
 
 /// Delta of a chat message for streaming responses
@@ -136,6 +627,9 @@ pub struct ChatMessageDelta {
     pub role: Option<String>,
     /// Content of the message delta
     pub content: String,
+    /// Base64-encoded images, from models that generate them
+    #[serde(default)]
+    pub images: Vec<String>,
 }
 
 /// Error response from Ollama API
@@ -0,0 +1,169 @@
+use crate::config::OllamaConfig;
+use crate::data::conversation::{Conversation, MessageRole, ResponseMetadata};
+use crate::ollama::models::ChatCompletionRequest;
+use crate::ollama::request::{build_request, RequestOverrides};
+
+/// Re-sends the user prompts of a conversation, one turn at a time, to another model
+///
+/// The replay builds a new conversation tagged with the model, keeping only the
+/// user messages of the original and collecting fresh assistant responses.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// Conversation receiving the replayed turns
+    conversation: Conversation,
+    /// User prompts of the original conversation, in order
+    prompts: Vec<String>,
+    /// Number of prompts sent so far
+    sent: usize,
+}
+
+impl Replay {
+    /// Prepare a replay of the user prompts in `source` against `model`
+    pub fn new(source: &Conversation, model: &str) -> Self {
+        let mut conversation = Conversation::new(&format!("{} (replay: {})", source.title, model), model);
+        conversation.forked_from = Some(source.id.clone());
+        conversation.system_prompt = source.system_prompt.clone();
+        conversation.think = source.think;
+
+        let prompts = source
+            .messages
+            .iter()
+            .filter(|message| message.role == MessageRole::User)
+            .map(|message| message.content.clone())
+            .collect();
+
+        Self {
+            conversation,
+            prompts,
+            sent: 0,
+        }
+    }
+
+    /// Get the conversation built so far
+    pub fn conversation(&self) -> &Conversation {
+        &self.conversation
+    }
+
+    /// Take the conversation built by the replay
+    pub fn into_conversation(self) -> Conversation {
+        self.conversation
+    }
+
+    /// Get the model the prompts are replayed against
+    pub fn model(&self) -> &str {
+        &self.conversation.model
+    }
+
+    /// Get the number of the turn in flight, counting from 1
+    pub fn turn(&self) -> usize {
+        self.sent
+    }
+
+    /// Get the total number of turns to replay
+    pub fn total(&self) -> usize {
+        self.prompts.len()
+    }
+
+    /// Check whether every prompt has been sent
+    pub fn is_finished(&self) -> bool {
+        self.sent >= self.prompts.len()
+    }
+
+    /// Add the next user prompt and build its request, or None once every prompt has been sent
+    pub fn start_turn(&mut self, config: &OllamaConfig) -> Option<ChatCompletionRequest> {
+        let prompt = self.prompts.get(self.sent)?;
+        self.conversation.add_message(MessageRole::User, prompt);
+        self.sent += 1;
+
+        let mut request = build_request(
+            &self.conversation.messages,
+            RequestOverrides::for_conversation(&self.conversation),
+            config,
+        );
+        request.model = self.conversation.model.clone();
+        Some(request)
+    }
+
+    /// Record the response to the turn in flight
+    pub fn finish_turn(&mut self, response: &str, metadata: Option<ResponseMetadata>) {
+        self.conversation.add_message(MessageRole::Assistant, response);
+        if let Some(metadata) = metadata {
+            let index = self.conversation.messages.len() - 1;
+            self.conversation.attach_metadata(index, metadata);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ollama::api::OllamaClient;
+
+    fn source() -> Conversation {
+        let mut conversation = Conversation::new("Session", "llama3.2");
+        for (index, prompt) in ["First", "Second", "Third"].iter().enumerate() {
+            conversation.add_message(MessageRole::User, prompt);
+            conversation.add_message(MessageRole::Assistant, &format!("Old answer {}", index + 1));
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_replay_keeps_only_user_prompts() {
+        let mut replay = Replay::new(&source(), "mistral");
+        assert_eq!(replay.total(), 3);
+        assert_eq!(replay.conversation().model, "mistral");
+        assert!(replay.conversation().title.contains("mistral"));
+        assert!(replay.conversation().messages.is_empty());
+
+        let request = replay.start_turn(&OllamaConfig::default()).unwrap();
+        assert_eq!(request.model, "mistral");
+        assert_eq!(request.messages.len(), 1);
+        assert_eq!(request.messages[0].content, "First");
+        assert_eq!(replay.turn(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_produces_a_response_per_prompt_in_order() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for answer in ["New answer 1", "New answer 2", "New answer 3"] {
+            let body = format!(
+                r#"{{"model":"mistral","created_at":"2024-01-01T00:00:00Z","message":{{"role":"assistant","content":"{}"}},"done":true}}"#,
+                answer
+            );
+            mocks.push(
+                server
+                    .mock("POST", "/api/chat")
+                    .match_body(mockito::Matcher::PartialJsonString(r#"{"model":"mistral"}"#.to_string()))
+                    .with_status(200)
+                    .with_body(body)
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let client = OllamaClient::new(&server.url()).unwrap();
+        let mut replay = Replay::new(&source(), "mistral");
+        while let Some(request) = replay.start_turn(&OllamaConfig::default()) {
+            let response = client.stream_chat(&request, None, |_| {}).await.unwrap();
+            replay.finish_turn(&response.content, response.metadata);
+        }
+
+        assert!(replay.is_finished());
+        let contents: Vec<&str> = replay
+            .conversation()
+            .messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            ["First", "New answer 1", "Second", "New answer 2", "Third", "New answer 3"]
+        );
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+    }
+}
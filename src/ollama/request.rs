@@ -0,0 +1,595 @@
+use anyhow::{Context, Result};
+use log::warn;
+
+use crate::config::OllamaConfig;
+use crate::data::trace::redact_secrets;
+use crate::data::conversation::{approx_tokens, Conversation, Message, MessageRole};
+use crate::ollama::models::{ChatCompletionRequest, ChatMessage, GenerateRequest, GenerationParameters, JSON_FORMAT};
+
+/// Convert conversation messages to Ollama chat messages
+///
+/// The configured per-role prefixes and suffixes are applied to the outgoing
+/// copies only, so the stored conversation keeps its original text.
+pub fn build_chat_messages(messages: &[Message], config: &OllamaConfig) -> Vec<ChatMessage> {
+    messages
+        .iter()
+        .map(|message| {
+            let (prefix, suffix) = match message.role {
+                MessageRole::User => (config.user_prefix.as_str(), config.user_suffix.as_str()),
+                MessageRole::Assistant => (config.assistant_prefix.as_str(), ""),
+                MessageRole::System => ("", ""),
+            };
+            // Images on assistant messages were generated by the model, so only the user's are sent back
+            let images = (message.role == MessageRole::User && !message.images.is_empty())
+                .then(|| message.images.clone());
+            ChatMessage {
+                role: message.role.as_str().to_string(),
+                content: format!("{}{}{}", prefix, message.content, suffix),
+                images,
+            }
+        })
+        .collect()
+}
+
+/// Highest temperature a request is sent with
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+/// Per-conversation and per-send settings layered over the global configuration
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestOverrides<'a> {
+    /// System prompt sent after the global one
+    pub system_prompt: Option<&'a str>,
+    /// Whether reasoning models should think, overriding the configured default
+    pub think: Option<bool>,
+    /// Temperature for this request only, overriding the configured one
+    pub temperature: Option<f32>,
+}
+
+impl<'a> RequestOverrides<'a> {
+    /// Get the overrides stored on a conversation
+    pub fn for_conversation(conversation: &'a Conversation) -> Self {
+        Self {
+            system_prompt: conversation.system_prompt.as_deref(),
+            think: conversation.think,
+            temperature: None,
+        }
+    }
+}
+
+/// Raise a temperature by `delta`, capped at `MAX_TEMPERATURE`
+pub fn bumped_temperature(temperature: f32, delta: f32) -> f32 {
+    (temperature + delta).min(MAX_TEMPERATURE)
+}
+
+/// Combine the global and conversation system prompts into a single system message
+///
+/// The global prompt comes first. Returns None if neither prompt is set.
+pub fn build_system_message(global: Option<&str>, per_convo: Option<&str>) -> Option<ChatMessage> {
+    let prompts: Vec<&str> = [global, per_convo]
+        .into_iter()
+        .flatten()
+        .filter(|prompt| !prompt.trim().is_empty())
+        .collect();
+    if prompts.is_empty() {
+        return None;
+    }
+
+    Some(ChatMessage {
+        role: "system".to_string(),
+        content: prompts.join("\n\n"),
+        images: None,
+    })
+}
+
+/// Build a streaming chat request for the given messages
+///
+/// The system prompts are only added to the request, never to the stored conversation.
+pub fn build_request(
+    messages: &[Message],
+    overrides: RequestOverrides,
+    config: &OllamaConfig,
+) -> ChatCompletionRequest {
+    let mut chat_messages: Vec<ChatMessage> =
+        build_system_message(config.system_prompt.as_deref(), overrides.system_prompt)
+            .into_iter()
+            .collect();
+    chat_messages.extend(build_chat_messages(messages, config));
+
+    if let Some(num_ctx) = config.num_ctx {
+        let tokens: usize = chat_messages.iter().map(|message| approx_tokens(&message.content)).sum();
+        if tokens > num_ctx as usize {
+            warn!(
+                "Conversation is about {} tokens, more than num_ctx ({}), so the model will drop the oldest context",
+                tokens, num_ctx
+            );
+        }
+    }
+
+    ChatCompletionRequest {
+        model: config.default_model.clone(),
+        messages: chat_messages,
+        stream: Some(true),
+        format: config.json_mode.then(|| JSON_FORMAT.to_string()),
+        parameters: GenerationParameters {
+            temperature: Some(overrides.temperature.unwrap_or(config.temperature)),
+            top_p: Some(config.top_p),
+            top_k: Some(config.top_k),
+            max_tokens: Some(config.max_tokens),
+            // Models that don't support the penalties may reject them, so they're only sent when set
+            presence_penalty: (config.presence_penalty != 0.0).then_some(config.presence_penalty),
+            frequency_penalty: (config.frequency_penalty != 0.0).then_some(config.frequency_penalty),
+            stop: (!config.stop.is_empty()).then(|| config.stop.clone()),
+            think: overrides.think.or(config.think),
+            seed: config.seed,
+            num_ctx: config.num_ctx,
+        },
+    }
+}
+
+/// Turn a chat request into a request to Ollama's generate endpoint, for `ollama.reuse_context`
+///
+/// With the `context` returned by the previous response only the last message
+/// is sent. Without one, the conversation is written out as a transcript so
+/// the model still sees all of it, and its context is reused from then on.
+/// Images attached to the last user message are sent with the prompt.
+pub fn build_generate_request(request: &ChatCompletionRequest, context: Option<&[i64]>) -> GenerateRequest {
+    let (system, messages): (Vec<&ChatMessage>, Vec<&ChatMessage>) =
+        request.messages.iter().partition(|message| message.role == "system");
+    let prompt = match (context, messages.as_slice()) {
+        (Some(_), [.., last]) | (None, [last]) => last.content.clone(),
+        _ => messages
+            .iter()
+            .map(|message| format!("{}: {}", transcript_label(&message.role), message.content))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    };
+    let system: Vec<&str> = system.iter().map(|message| message.content.as_str()).collect();
+    let images = messages
+        .iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .and_then(|message| message.images.clone());
+
+    GenerateRequest {
+        model: request.model.clone(),
+        prompt,
+        stream: false,
+        keep_alive: None,
+        system: (!system.is_empty()).then(|| system.join("\n\n")),
+        context: context.map(<[i64]>::to_vec),
+        images,
+        format: request.format.clone(),
+        options: Some(request.parameters.clone()),
+    }
+}
+
+/// Get the label a message's role is written with in a transcript
+fn transcript_label(role: &str) -> &str {
+    match role {
+        "user" => "User",
+        "assistant" => "Assistant",
+        other => other,
+    }
+}
+
+/// Render a request as the pretty-printed JSON body that would be sent
+pub fn preview_request(request: &ChatCompletionRequest) -> Result<String> {
+    serde_json::to_string_pretty(request).context("Failed to serialize request")
+}
+
+/// Render a JSON request as an equivalent `curl` command posting `body` to `url` with `headers`
+///
+/// Secret-looking body fields are replaced with a placeholder so the command can be shared.
+/// Use `OllamaClient::to_curl` to get the command for what the client would send.
+pub fn to_curl(url: &str, headers: &[(String, String)], mut body: serde_json::Value) -> Result<String> {
+    redact_secrets(&mut body);
+    let body = serde_json::to_string_pretty(&body).context("Failed to serialize request")?;
+
+    let mut command = format!("curl -X POST {} \\\n  -H 'Content-Type: application/json'", shell_quote(url));
+    for (name, value) in headers {
+        command.push_str(&format!(" \\\n  -H {}", shell_quote(&format!("{}: {}", name, value))));
+    }
+    command.push_str(&format!(" \\\n  -d {}", shell_quote(&body)));
+    Ok(command)
+}
+
+/// Quote a string for a POSIX shell using single quotes
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::conversation::Conversation;
+
+    #[test]
+    fn test_wrapping_affects_only_outgoing_request() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there");
+
+        let config = OllamaConfig {
+            user_prefix: "[INST] ".to_string(),
+            user_suffix: " [/INST]".to_string(),
+            assistant_prefix: "Answer: ".to_string(),
+            ..OllamaConfig::default()
+        };
+
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+        assert_eq!(request.messages[0].role, "user");
+        assert_eq!(request.messages[0].content, "[INST] Hello [/INST]");
+        assert_eq!(request.messages[1].role, "assistant");
+        assert_eq!(request.messages[1].content, "Answer: Hi there");
+
+        // The stored conversation is untouched
+        assert_eq!(conversation.messages[0].content, "Hello");
+        assert_eq!(conversation.messages[1].content, "Hi there");
+    }
+
+    #[test]
+    fn test_user_images_are_sent() {
+        let mut conversation = Conversation::new("Test", "llava");
+        conversation.add_message(MessageRole::User, "What is this?");
+        conversation.attach_images(0, vec!["iVBORw0KGgo=".to_string()]);
+        conversation.add_message(MessageRole::Assistant, "A picture");
+        conversation.attach_images(1, vec!["R0lGODlh".to_string()]);
+
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+        assert_eq!(request.messages[0].images, Some(vec!["iVBORw0KGgo=".to_string()]));
+        assert_eq!(request.messages[1].images, None);
+
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["messages"][0]["images"][0], "iVBORw0KGgo=");
+        assert!(body["messages"][1].get("images").is_none());
+
+        assert!(crate::ollama::models::is_multimodal_model("llava:13b"));
+        assert!(crate::ollama::models::is_multimodal_model("llama3.2-vision"));
+        assert!(!crate::ollama::models::is_multimodal_model("llama3.2"));
+    }
+
+    #[test]
+    fn test_generate_request_reuses_context() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.add_message(MessageRole::Assistant, "Hi there");
+        conversation.add_message(MessageRole::User, "And in French?");
+        let config = OllamaConfig {
+            system_prompt: Some("Be brief".to_string()),
+            ..OllamaConfig::default()
+        };
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+
+        // With a context only the new message is sent
+        let generate = build_generate_request(&request, Some(&[1, 2, 3]));
+        assert_eq!(generate.prompt, "And in French?");
+        assert_eq!(generate.context, Some(vec![1, 2, 3]));
+        assert_eq!(generate.system.as_deref(), Some("Be brief"));
+        assert_eq!(generate.options, Some(request.parameters.clone()));
+        assert!(!generate.stream);
+
+        // Without one the whole conversation is sent
+        let generate = build_generate_request(&request, None);
+        assert_eq!(generate.prompt, "User: Hello\n\nAssistant: Hi there\n\nUser: And in French?");
+        assert_eq!(generate.context, None);
+
+        // A first message is sent as it is
+        let request = build_request(&conversation.messages[..1], RequestOverrides::default(), &config);
+        assert_eq!(build_generate_request(&request, None).prompt, "Hello");
+    }
+
+    #[test]
+    fn test_generate_request_sends_images_of_the_last_user_message() {
+        let mut conversation = Conversation::new("Test", "llava");
+        conversation.add_message(MessageRole::User, "What is this?");
+        conversation.attach_images(0, vec!["R0lGODlh".to_string()]);
+        conversation.add_message(MessageRole::Assistant, "A cat");
+        conversation.add_message(MessageRole::User, "And this?");
+        conversation.attach_images(2, vec!["iVBORw0KGgo=".to_string()]);
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+
+        let generate = build_generate_request(&request, Some(&[1, 2, 3]));
+        assert_eq!(generate.images, Some(vec!["iVBORw0KGgo=".to_string()]));
+        let body = serde_json::to_value(&generate).unwrap();
+        assert_eq!(body["images"][0], "iVBORw0KGgo=");
+
+        // Without images none are sent
+        conversation.attach_images(2, Vec::new());
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+        let generate = build_generate_request(&request, Some(&[1, 2, 3]));
+        assert_eq!(generate.images, None);
+        assert!(serde_json::to_value(&generate).unwrap().get("images").is_none());
+    }
+
+    #[test]
+    fn test_preview_request() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let preview = preview_request(&build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default())).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(value["model"], "llama3.2");
+        assert_eq!(value["messages"][0]["content"], "Hello");
+        assert_eq!(value["stream"], true);
+        assert!(preview.contains('\n'));
+    }
+
+    #[test]
+    fn test_to_curl() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "It's a test");
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+
+        let body = serde_json::to_value(&request).unwrap();
+        let headers = [("X-Team".to_string(), "docs".to_string())];
+        let command = to_curl("http://localhost:11434/api/chat", &headers, body).unwrap();
+        assert!(command.starts_with("curl -X POST 'http://localhost:11434/api/chat'"));
+        assert!(command.contains("-H 'Content-Type: application/json'"));
+        assert!(command.contains("-H 'X-Team: docs'"));
+        assert!(command.contains(r#""model": "llama3.2""#));
+        assert!(command.contains(r"It'\''s a test"));
+
+        // The body round-trips back to the request JSON once unquoted
+        let body = command.split_once("-d '").unwrap().1.trim_end_matches('\'').replace(r"'\''", "'");
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value, serde_json::to_value(&request).unwrap());
+    }
+
+    #[test]
+    fn test_empty_affixes_are_no_ops() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let messages = build_chat_messages(&conversation.messages, &OllamaConfig::default());
+        assert_eq!(messages[0].content, "Hello");
+    }
+
+    #[test]
+    fn test_build_system_message_without_prompts() {
+        assert!(build_system_message(None, None).is_none());
+    }
+
+    #[test]
+    fn test_build_system_message_global_only() {
+        let message = build_system_message(Some("Be concise."), None).unwrap();
+        assert_eq!(message.role, "system");
+        assert_eq!(message.content, "Be concise.");
+    }
+
+    #[test]
+    fn test_build_system_message_conversation_only() {
+        let message = build_system_message(None, Some("You are a Rust reviewer.")).unwrap();
+        assert_eq!(message.role, "system");
+        assert_eq!(message.content, "You are a Rust reviewer.");
+    }
+
+    #[test]
+    fn test_build_system_message_global_then_conversation() {
+        let message = build_system_message(Some("Be concise."), Some("You are a Rust reviewer.")).unwrap();
+        assert_eq!(message.content, "Be concise.\n\nYou are a Rust reviewer.");
+    }
+
+    #[test]
+    fn test_system_prompts_are_sent_but_not_stored() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.system_prompt = Some("You are a Rust reviewer.".to_string());
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let config = OllamaConfig {
+            system_prompt: Some("Be concise.".to_string()),
+            ..OllamaConfig::default()
+        };
+
+        let request = build_request(&conversation.messages, RequestOverrides::for_conversation(&conversation), &config);
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].role, "system");
+        assert_eq!(request.messages[0].content, "Be concise.\n\nYou are a Rust reviewer.");
+        assert_eq!(request.messages[1].content, "Hello");
+        assert_eq!(conversation.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_think_is_omitted_unless_set() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+        let value = serde_json::to_value(&request).unwrap();
+        assert!(value.get("think").is_none());
+
+        let config = OllamaConfig {
+            think: Some(false),
+            ..OllamaConfig::default()
+        };
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+        assert_eq!(serde_json::to_value(&request).unwrap()["think"], false);
+    }
+
+    #[test]
+    fn test_sampling_options_are_sent_where_each_api_reads_them() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        let config = OllamaConfig {
+            seed: Some(42),
+            num_ctx: Some(8192),
+            ..OllamaConfig::default()
+        };
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+
+        // Ollama reads sampling parameters from `options`, with its own name for the token limit
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["options"]["seed"], 42);
+        assert_eq!(value["options"]["top_k"], config.top_k);
+        assert_eq!(value["options"]["num_predict"], config.max_tokens);
+        assert!(value["options"]["temperature"].is_number());
+        assert!(value.get("temperature").is_none());
+
+        // OpenAI-compatible servers read them from the top level
+        let value = serde_json::to_value(request.openai_body()).unwrap();
+        assert_eq!(value["seed"], 42);
+        assert_eq!(value["max_tokens"], config.max_tokens);
+        assert!(value["temperature"].is_number());
+        assert!(value.get("options").is_none());
+        assert!(value.get("top_k").is_none());
+        // The context window is Ollama's own setting
+        assert!(value.get("num_ctx").is_none());
+    }
+
+    #[test]
+    fn test_optional_options_are_sent_only_when_configured() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        let cases = [
+            ("seed", OllamaConfig { seed: Some(42), ..OllamaConfig::default() }, serde_json::json!(42)),
+            ("num_ctx", OllamaConfig { num_ctx: Some(8192), ..OllamaConfig::default() }, serde_json::json!(8192)),
+            (
+                "stop",
+                OllamaConfig { stop: vec!["User:".to_string()], ..OllamaConfig::default() },
+                serde_json::json!(["User:"]),
+            ),
+            (
+                "presence_penalty",
+                OllamaConfig { presence_penalty: 0.5, ..OllamaConfig::default() },
+                serde_json::json!(0.5),
+            ),
+            (
+                "frequency_penalty",
+                OllamaConfig { frequency_penalty: -0.5, ..OllamaConfig::default() },
+                serde_json::json!(-0.5),
+            ),
+        ];
+
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+        let unset = serde_json::to_value(&request).unwrap();
+        for (name, config, expected) in cases {
+            assert!(unset["options"].get(name).is_none(), "{} sent without being configured", name);
+
+            let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+            let value = serde_json::to_value(&request).unwrap();
+            assert_eq!(value["options"][name], expected, "{} not sent under options", name);
+            assert!(value.get(name).is_none(), "{} sent at the top level", name);
+        }
+    }
+
+    #[test]
+    fn test_json_mode_sets_format() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &OllamaConfig::default());
+        assert!(serde_json::to_value(&request).unwrap().get("format").is_none());
+
+        let config = OllamaConfig {
+            json_mode: true,
+            ..OllamaConfig::default()
+        };
+        let request = build_request(&conversation.messages, RequestOverrides::default(), &config);
+        assert_eq!(serde_json::to_value(&request).unwrap()["format"], "json");
+        assert_eq!(serde_json::to_value(request.openai_body()).unwrap()["response_format"]["type"], "json_object");
+    }
+
+    #[test]
+    fn test_ollama_request_json_shape() {
+        let request = ChatCompletionRequest {
+            model: "llama3.2".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+                images: None,
+            }],
+            stream: Some(true),
+            format: Some(JSON_FORMAT.to_string()),
+            parameters: GenerationParameters {
+                temperature: Some(0.5),
+                top_p: Some(0.25),
+                top_k: Some(40),
+                max_tokens: Some(128),
+                stop: Some(vec!["###".to_string()]),
+                think: Some(false),
+                ..GenerationParameters::default()
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "model": "llama3.2",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "stream": true,
+                "format": "json",
+                "think": false,
+                "options": {
+                    "temperature": 0.5,
+                    "top_p": 0.25,
+                    "top_k": 40,
+                    "num_predict": 128,
+                    "stop": ["###"],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_conversation_think_overrides_config() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        conversation.think = Some(true);
+
+        let config = OllamaConfig {
+            think: Some(false),
+            ..OllamaConfig::default()
+        };
+        let request = build_request(&conversation.messages, RequestOverrides::for_conversation(&conversation), &config);
+        assert_eq!(serde_json::to_value(&request).unwrap()["think"], true);
+    }
+
+    #[test]
+    fn test_temperature_override_applies_to_one_request() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        let config = OllamaConfig::default();
+
+        let overrides = RequestOverrides {
+            temperature: Some(bumped_temperature(config.temperature, 0.3)),
+            ..RequestOverrides::for_conversation(&conversation)
+        };
+        let request = build_request(&conversation.messages, overrides, &config);
+        assert_eq!(request.parameters.temperature, Some(config.temperature + 0.3));
+
+        let request = build_request(&conversation.messages, RequestOverrides::for_conversation(&conversation), &config);
+        assert_eq!(request.parameters.temperature, Some(config.temperature));
+    }
+
+    #[test]
+    fn test_response_params_snapshot_per_send_overrides() {
+        let mut conversation = Conversation::new("Test", "model");
+        conversation.add_message(MessageRole::User, "Hello");
+        let config = OllamaConfig::default();
+
+        let overrides = RequestOverrides {
+            temperature: Some(1.5),
+            ..RequestOverrides::for_conversation(&conversation)
+        };
+        let request = build_request(&conversation.messages, overrides, &config);
+        conversation.add_message(MessageRole::Assistant, "Hi");
+        conversation.messages[1].params = Some(request.parameters.clone());
+
+        let json = serde_json::to_string(&conversation).unwrap();
+        let loaded: Conversation = serde_json::from_str(&json).unwrap();
+        let params = loaded.messages[1].params.as_ref().unwrap();
+        assert_eq!(params, &request.parameters);
+        assert_eq!(params.temperature, Some(1.5));
+        assert_ne!(params.temperature, Some(config.temperature));
+        assert_eq!(params.top_p, Some(config.top_p));
+        assert!(params.summary().contains("temperature: 1.5"));
+
+        // Messages saved before the snapshot existed load without one
+        assert!(loaded.messages[0].params.is_none());
+    }
+
+    #[test]
+    fn test_bumped_temperature_is_capped() {
+        assert_eq!(bumped_temperature(0.5, 0.25), 0.75);
+        assert_eq!(bumped_temperature(1.9, 0.3), MAX_TEMPERATURE);
+    }
+}
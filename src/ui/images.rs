@@ -0,0 +1,74 @@
+use iced::widget::image::Handle;
+
+use crate::data::conversation::Conversation;
+use crate::utils::image::decode_base64_image;
+
+/// An image ready to be drawn, or why its data couldn't be decoded
+pub type DecodedImage = Result<Handle, String>;
+
+/// Decoded images of the conversation's messages, kept so redrawing doesn't decode them again
+#[derive(Debug, Default)]
+pub struct ImageCache {
+    /// Base64 data the images were decoded from and the decoded images, by message index
+    messages: Vec<(Vec<String>, Vec<DecodedImage>)>,
+}
+
+impl ImageCache {
+    /// Decode the images of messages whose images changed since the last sync
+    pub fn sync(&mut self, conversation: &Conversation) {
+        self.messages.truncate(conversation.messages.len());
+        for (index, message) in conversation.messages.iter().enumerate() {
+            match self.messages.get_mut(index) {
+                Some((data, _)) if *data == message.images => {}
+                Some(entry) => *entry = decode_all(&message.images),
+                None => self.messages.push(decode_all(&message.images)),
+            }
+        }
+    }
+
+    /// Get the decoded images of every message, by message index
+    pub fn decoded(&self) -> Vec<Vec<DecodedImage>> {
+        self.messages.iter().map(|(_, images)| images.clone()).collect()
+    }
+}
+
+/// Decode base64 images, keeping their data to notice when they change
+fn decode_all(images: &[String]) -> (Vec<String>, Vec<DecodedImage>) {
+    let decoded = images
+        .iter()
+        .map(|data| decode_base64_image(data).map(Handle::from_memory).map_err(|e| e.to_string()))
+        .collect();
+    (images.to_vec(), decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::conversation::MessageRole;
+
+    #[test]
+    fn test_images_are_decoded_once_until_they_change() {
+        let mut conversation = Conversation::new("Test", "llava");
+        conversation.add_message(MessageRole::User, "What is this?");
+        conversation.add_message(MessageRole::Assistant, "A picture");
+        conversation.attach_images(1, vec!["iVBORw0KGgo=".to_string(), "not base64!".to_string()]);
+
+        let mut cache = ImageCache::default();
+        cache.sync(&conversation);
+        let decoded = cache.decoded();
+        assert!(decoded[0].is_empty());
+        assert!(decoded[1][0].is_ok());
+        assert!(decoded[1][1].is_err());
+
+        // Unchanged images keep their handles, changed ones are decoded again
+        cache.sync(&conversation);
+        assert_eq!(cache.decoded(), decoded);
+        conversation.attach_images(1, Vec::new());
+        cache.sync(&conversation);
+        assert!(cache.decoded()[1].is_empty());
+
+        conversation.messages.truncate(1);
+        cache.sync(&conversation);
+        assert_eq!(cache.decoded().len(), 1);
+    }
+}
@@ -1,87 +1,434 @@
 use iced::{
     keyboard,
-    widget::{Button, container, Container, Row, TextInput},
+    widget::{
+        button, pick_list, scrollable, text, text_input, Button, Column, container, Container, Row, Scrollable,
+        TextInput,
+    },
     Alignment, Color, Element, Event, Length, Padding, Subscription, Theme,
 };
+use std::collections::BTreeMap;
 
 use crate::app::Message;
+use crate::config::Template;
+use crate::ollama::models::is_multimodal_model;
+use crate::utils::format::format_count;
+use crate::utils::grapheme_count;
+use crate::utils::template::template_variables;
 
 /// Maximum height for the input field in pixels
 const MAX_INPUT_HEIGHT: u16 = 150;
 
+/// Approximate height of a line of input text in pixels
+const INPUT_LINE_HEIGHT: u16 = 24;
+
+/// Height of the thumbnails of images attached to the next message in pixels
+const THUMBNAIL_HEIGHT: f32 = 48.0;
+
+/// ID of the scrollable showing the lines above the one being typed
+pub const INPUT_LINES_ID: &str = "input_lines";
+
+/// ID of the text input the line being typed is edited in
+pub const INPUT_ID: &str = "message_input";
+
+/// Get the height of the input's text, one line per line of the message up to `MAX_INPUT_HEIGHT`
+pub fn input_height(message: &str) -> u16 {
+    // A trailing newline starts a line of its own, which `lines` wouldn't count
+    let line_count = message.split('\n').count();
+    (line_count as u16).saturating_mul(INPUT_LINE_HEIGHT).min(MAX_INPUT_HEIGHT)
+}
+
+/// Get the message for the line being typed in `message`, the text after its last newline, changing to `line`
+///
+/// Backspace on an empty line leaves it empty, and joins it to the line above instead,
+/// so earlier lines can be edited again.
+pub fn line_changed(message: &str, line: String) -> Message {
+    match message.rsplit_once('\n') {
+        Some((_, "")) if line.is_empty() => Message::JoinLine,
+        Some((earlier, _)) => Message::InputChanged(format!("{}\n{}", earlier, line)),
+        None => Message::InputChanged(line),
+    }
+}
+
 /// Create an input area for the chat
+///
+/// `context_tokens` is the estimated size of the conversation, highlighted when `context_warning` is set.
 pub fn input_area<'a>(
     message: &str,
     is_sending: bool,
-    _theme: &Theme,
+    context_tokens: usize,
+    context_warning: bool,
+    theme: &Theme,
 ) -> Element<'a, Message> {
-    // Calculate the number of lines in the input
-    let line_count = message.lines().count().max(1);
-    
-    // Calculate the height based on line count, with a maximum
-    let line_height = 24; // Approximate height per line in pixels
-    let _height = (line_count as u16 * line_height).min(MAX_INPUT_HEIGHT);
-    
-    // Create the text input
-    let input = TextInput::new("Type a message...", message)
-        .on_input(Message::InputChanged)
+    // The text input holds a single line, so the lines before the one being typed are shown above it
+    let (earlier, current) = match message.rsplit_once('\n') {
+        Some((earlier, current)) => (Some(earlier), current),
+        None => (None, message),
+    };
+    let typed = message.to_string();
+    let line = TextInput::new("Type a message...", current)
+        .id(text_input::Id::new(INPUT_ID))
+        .on_input(move |line| line_changed(&typed, line))
         .padding(Padding::new(12.0))
         .size(16);
     
-    // Create the send button
-    let send_button_text = if is_sending { "Sending..." } else { "Send" };
-    let send_button = Button::new(send_button_text)
-        .padding(Padding::new(12.0))
-        .style(iced::theme::Button::Primary);
+    // Grow with each line up to the maximum height, then scroll the earlier lines
+    let mut input = Column::new();
+    if let Some(earlier) = earlier {
+        let height = input_height(message) - INPUT_LINE_HEIGHT;
+        input = input.push(
+            Scrollable::new(Container::new(text(earlier).size(16)).padding(Padding::from([0, 12])))
+                .id(scrollable::Id::new(INPUT_LINES_ID))
+                .height(Length::Fixed(height as f32))
+                .width(Length::Fill),
+        );
+    }
+    let input = input.push(line);
     
-    // Only enable the button if there's text and we're not already sending
-    let send_button = if !message.trim().is_empty() && !is_sending {
-        send_button.on_press(Message::SendMessage)
+    // Create the send button, which stops the response while one is being generated
+    let send_button = if is_sending {
+        Button::new("Stop")
+            .padding(Padding::new(12.0))
+            .style(iced::theme::Button::Destructive)
+            .on_press(Message::CancelStreaming)
     } else {
-        send_button
+        let send_button = Button::new("Send")
+            .padding(Padding::new(12.0))
+            .style(iced::theme::Button::Primary);
+        
+        // Only enable the button if there's text
+        if message.trim().is_empty() {
+            send_button
+        } else {
+            send_button.on_press(Message::SendMessage)
+        }
     };
     
-    // Create the row with input and button
+    // Count user-perceived characters so emoji and accents count once
+    let counter = text(grapheme_count(message))
+        .size(12)
+        .style(iced::theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6)));
+    
+    // Show how much of the model's context the conversation is using
+    let context_color = if context_warning {
+        Color::from_rgb(0.85, 0.45, 0.0)
+    } else {
+        Color::from_rgb(0.6, 0.6, 0.6)
+    };
+    let context = text(format!("~{} tokens", format_count(context_tokens as u64)))
+        .size(12)
+        .style(iced::theme::Text::Color(context_color));
+    
+    // Create the row with input, counters and button
     let input_row = Row::new()
         .spacing(8)
         .align_items(Alignment::Center)
         .push(input.width(Length::Fill))
+        .push(counter)
+        .push(context)
         .push(send_button);
     
-    // Create the container for the input area
+    // Create the container for the input area, shaded to match the theme
+    let background = theme.extended_palette().background.base.color;
+    let border = theme.extended_palette().background.strong.color;
     Container::new(input_row)
         .width(Length::Fill)
         .padding(Padding::new(12.0))
-        .style(|_theme: &Theme| {
+        .style(move |_theme: &Theme| {
             container::Appearance {
-                background: Some(Color::from_rgb(1.0, 1.0, 1.0).into()),
+                background: Some(background.into()),
                 border_width: 1.0,
-                border_color: Color::from_rgb(0.8, 0.8, 0.8),
+                border_color: border,
                 ..Default::default()
             }
         })
         .into()
 }
 
+/// Create the note shown above the input while an earlier message is being edited
+pub fn edit_bar<'a>() -> Element<'a, Message> {
+    let row = Row::new()
+        .spacing(6)
+        .align_items(Alignment::Center)
+        .push(text("Editing a message: sending replaces it and the replies after it").size(12))
+        .push(
+            button(text("Cancel").size(12))
+                .on_press(Message::CancelEdit)
+                .padding(4)
+                .style(iced::theme::Button::Text),
+        );
+
+    Container::new(row)
+        .width(Length::Fill)
+        .padding(Padding::from([0, 12]))
+        .into()
+}
+
+/// Create the thumbnails of the images attached to the next message, each with a button to remove it
+///
+/// Warns when `model` doesn't accept images, since it would ignore them.
+pub fn attachment_bar<'a>(images: &[iced::widget::image::Handle], model: &str) -> Element<'a, Message> {
+    let thumbnails = images.iter().enumerate().fold(
+        Row::new().spacing(6).align_items(Alignment::End),
+        |row, (index, handle)| {
+            let thumbnail = iced::widget::image(handle.clone()).height(Length::Fixed(THUMBNAIL_HEIGHT));
+            row.push(
+                Column::new()
+                    .spacing(2)
+                    .align_items(Alignment::Center)
+                    .push(thumbnail)
+                    .push(
+                        button(text("Remove").size(10))
+                            .on_press(Message::RemovePendingImage(index))
+                            .padding(2)
+                            .style(iced::theme::Button::Text),
+                    ),
+            )
+        },
+    );
+
+    let mut bar = Column::new().spacing(4).push(thumbnails);
+    if !is_multimodal_model(model) {
+        bar = bar.push(
+            text(format!("{} doesn't accept images, so they will be ignored", model))
+                .size(12)
+                .style(iced::theme::Text::Color(Color::from_rgb(0.85, 0.45, 0.0))),
+        );
+    }
+
+    Container::new(bar)
+        .width(Length::Fill)
+        .padding(Padding::from([0, 12]))
+        .into()
+}
+
+/// Create the line under the conversation describing the last response, like its speed
+pub fn status_line<'a>(status: &str) -> Element<'a, Message> {
+    Container::new(
+        text(status)
+            .size(11)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+    )
+    .width(Length::Fill)
+    .padding(Padding::from([0, 12]))
+    .into()
+}
+
+/// Create the picker for the model new messages are sent to
+pub fn model_picker<'a>(choices: Vec<String>, selected: &str) -> Element<'a, Message> {
+    let picker = pick_list(choices, Some(selected.to_string()), Message::ModelSelected)
+        .text_size(12)
+        .padding(4);
+    let row = Row::new()
+        .spacing(6)
+        .align_items(Alignment::Center)
+        .push(text("Model").size(12))
+        .push(picker);
+
+    Container::new(row)
+        .width(Length::Fill)
+        .padding(Padding::from([0, 12]))
+        .into()
+}
+
+/// Create the template picker shown above the input, with fields for the selected template's variables
+pub fn template_bar<'a>(
+    templates: &[Template],
+    selected: Option<usize>,
+    values: &BTreeMap<String, String>,
+) -> Element<'a, Message> {
+    let template_button = |label: &str, index: Option<usize>| {
+        let style = if selected == index {
+            iced::theme::Button::Primary
+        } else {
+            iced::theme::Button::Text
+        };
+        button(text(label).size(12))
+            .on_press(Message::SelectTemplate(index))
+            .padding(4)
+            .style(style)
+    };
+    let picker = templates.iter().enumerate().fold(
+        Row::new()
+            .spacing(6)
+            .align_items(Alignment::Center)
+            .push(text("Template").size(12))
+            .push(template_button("None", None)),
+        |row, (index, template)| row.push(template_button(&template.name, Some(index))),
+    );
+
+    let mut bar = Column::new().spacing(6).push(picker);
+    if let Some(template) = selected.and_then(|index| templates.get(index)) {
+        // Config validation rejects malformed templates, so there is always a list of variables
+        for name in template_variables(&template.body).unwrap_or_default() {
+            let value = values.get(&name).map(String::as_str).unwrap_or_default();
+            let field_name = name.clone();
+            bar = bar.push(
+                Row::new()
+                    .spacing(6)
+                    .align_items(Alignment::Center)
+                    .push(text(&name).size(12))
+                    .push(
+                        TextInput::new(&name, value)
+                            .on_input(move |value| Message::TemplateValueChanged(field_name.clone(), value))
+                            .padding(4)
+                            .size(12)
+                            .width(Length::Fill),
+                    ),
+            );
+        }
+    }
+
+    Container::new(bar)
+        .width(Length::Fill)
+        .padding(Padding::from([0, 12]))
+        .into()
+}
+
 /// Create a subscription for keyboard events
 pub fn keyboard_subscription() -> Subscription<Message> {
+    // The event status is ignored so shortcuts work even while the input has focus
     iced::subscription::events_with(|event, _status| {
         if let Event::Keyboard(keyboard::Event::KeyPressed { 
             key_code, 
             modifiers, 
             ..
         }) = event {
-            // Handle Enter key for submission (without shift)
-            if key_code == keyboard::KeyCode::Enter && !modifiers.shift() {
-                return Some(Message::SendMessage);
-            }
-            
-            // Handle Shift+Enter for new line
-            if key_code == keyboard::KeyCode::Enter && modifiers.shift() {
-                return Some(Message::NewLine);
-            }
+            return shortcut_message(key_code, modifiers);
         }
         
         None
     })
 }
+
+/// Map a key press to the message of its keyboard shortcut, if any
+pub fn shortcut_message(key_code: keyboard::KeyCode, modifiers: keyboard::Modifiers) -> Option<Message> {
+    // Command shortcuts never reach the input as characters
+    if modifiers.command() {
+        return match key_code {
+            keyboard::KeyCode::Comma => Some(Message::ToggleSettings),
+            keyboard::KeyCode::F => Some(Message::ToggleFind),
+            keyboard::KeyCode::Backslash => Some(Message::ToggleSidebar),
+            keyboard::KeyCode::L => Some(Message::ToggleLogViewer),
+            keyboard::KeyCode::O => Some(Message::CycleOpacity),
+            keyboard::KeyCode::S => Some(Message::SaveConversation),
+            keyboard::KeyCode::T => Some(Message::ToggleAlwaysOnTop),
+            keyboard::KeyCode::Key1 => Some(Message::QuickSwitch(1)),
+            keyboard::KeyCode::Key2 => Some(Message::QuickSwitch(2)),
+            keyboard::KeyCode::Key3 => Some(Message::QuickSwitch(3)),
+            keyboard::KeyCode::Key4 => Some(Message::QuickSwitch(4)),
+            keyboard::KeyCode::Key5 => Some(Message::QuickSwitch(5)),
+            keyboard::KeyCode::Key6 => Some(Message::QuickSwitch(6)),
+            keyboard::KeyCode::Key7 => Some(Message::QuickSwitch(7)),
+            keyboard::KeyCode::Key8 => Some(Message::QuickSwitch(8)),
+            keyboard::KeyCode::Key9 => Some(Message::QuickSwitch(9)),
+            _ => None,
+        };
+    }
+    
+    // Handle Enter key for submission (without shift)
+    if key_code == keyboard::KeyCode::Enter && !modifiers.shift() {
+        return Some(Message::SendMessage);
+    }
+    
+    // Handle Shift+Enter for new line
+    if key_code == keyboard::KeyCode::Enter && modifiers.shift() {
+        return Some(Message::NewLine);
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyboard::{KeyCode, Modifiers};
+
+    #[test]
+    fn test_input_height_grows_with_lines() {
+        assert_eq!(input_height(""), INPUT_LINE_HEIGHT);
+        assert_eq!(input_height("One line"), INPUT_LINE_HEIGHT);
+        assert_eq!(input_height("First\n"), 2 * INPUT_LINE_HEIGHT);
+        assert_eq!(input_height("First\nSecond\nThird"), 3 * INPUT_LINE_HEIGHT);
+        assert_eq!(input_height(&"\n".repeat(100)), MAX_INPUT_HEIGHT);
+    }
+
+    #[test]
+    fn test_backspace_on_an_empty_line_joins_it_to_the_line_above() {
+        assert!(matches!(line_changed("First\n", String::new()), Message::JoinLine));
+        assert!(matches!(
+            line_changed("First\nSecon", "Second".to_string()),
+            Message::InputChanged(message) if message == "First\nSecond"
+        ));
+        // Emptying the last line by deleting its text keeps the line
+        assert!(matches!(
+            line_changed("First\nS", String::new()),
+            Message::InputChanged(message) if message == "First\n"
+        ));
+        assert!(matches!(line_changed("", String::new()), Message::InputChanged(message) if message.is_empty()));
+    }
+
+    #[test]
+    fn test_command_shortcuts() {
+        assert!(matches!(
+            shortcut_message(KeyCode::Comma, Modifiers::COMMAND),
+            Some(Message::ToggleSettings)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::Backslash, Modifiers::COMMAND),
+            Some(Message::ToggleSidebar)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::L, Modifiers::COMMAND),
+            Some(Message::ToggleLogViewer)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::O, Modifiers::COMMAND),
+            Some(Message::CycleOpacity)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::S, Modifiers::COMMAND),
+            Some(Message::SaveConversation)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::T, Modifiers::COMMAND),
+            Some(Message::ToggleAlwaysOnTop)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::F, Modifiers::COMMAND),
+            Some(Message::ToggleFind)
+        ));
+    }
+
+    #[test]
+    fn test_quick_switch_shortcuts() {
+        assert!(matches!(
+            shortcut_message(KeyCode::Key1, Modifiers::COMMAND),
+            Some(Message::QuickSwitch(1))
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::Key9, Modifiers::COMMAND),
+            Some(Message::QuickSwitch(9))
+        ));
+        assert!(shortcut_message(KeyCode::Key0, Modifiers::COMMAND).is_none());
+        assert!(shortcut_message(KeyCode::Key1, Modifiers::empty()).is_none());
+    }
+
+    #[test]
+    fn test_shortcut_keys_without_command_are_ignored() {
+        assert!(shortcut_message(KeyCode::Comma, Modifiers::empty()).is_none());
+        assert!(shortcut_message(KeyCode::Backslash, Modifiers::SHIFT).is_none());
+        assert!(shortcut_message(KeyCode::A, Modifiers::COMMAND).is_none());
+    }
+
+    #[test]
+    fn test_enter_shortcuts() {
+        assert!(matches!(
+            shortcut_message(KeyCode::Enter, Modifiers::empty()),
+            Some(Message::SendMessage)
+        ));
+        assert!(matches!(
+            shortcut_message(KeyCode::Enter, Modifiers::SHIFT),
+            Some(Message::NewLine)
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+/// Font size of message text
+pub const MESSAGE_TEXT_SIZE: f32 = 16.0;
+
+/// Padding inside a message bubble
+pub const BUBBLE_PADDING: f32 = 12.0;
+
+/// Fraction of the conversation width a message bubble may take
+pub const BUBBLE_WIDTH_FRACTION: f32 = 0.8;
+
+/// Spacing between the rows of the conversation column
+pub const MESSAGE_SPACING: f32 = 12.0;
+
+/// Padding around the conversation column
+pub const CONVERSATION_PADDING: f32 = 16.0;
+
+/// Interval between the frames of a smooth scroll
+pub const SMOOTH_SCROLL_FRAME: std::time::Duration = std::time::Duration::from_millis(16);
+
+/// Fraction of the remaining distance covered by each frame of a smooth scroll
+const SMOOTH_SCROLL_EASING: f32 = 0.35;
+
+/// Distance from the target, as a fraction of the scrollable height, at which a smooth scroll snaps to it
+const SMOOTH_SCROLL_SNAP: f32 = 0.002;
+
+/// Height of the action row under each message
+const ACTIONS_HEIGHT: f32 = 20.0;
+
+/// Average glyph width as a fraction of the font size
+const AVERAGE_GLYPH_WIDTH: f32 = 0.55;
+
+/// Line height as a multiple of the font size
+const LINE_HEIGHT: f32 = 1.3;
+
+/// Estimate the height of a message bubble and its action row in a conversation `width` pixels wide
+///
+/// iced doesn't report the laid-out height of individual widgets, so the height
+/// is worked out from the text and the width it wraps at.
+pub fn estimate_message_height(content: &str, width: f32) -> f32 {
+    let text_width = (width - 2.0 * CONVERSATION_PADDING) * BUBBLE_WIDTH_FRACTION - 2.0 * BUBBLE_PADDING;
+    let chars_per_line = (text_width / (MESSAGE_TEXT_SIZE * AVERAGE_GLYPH_WIDTH)).floor().max(1.0) as usize;
+
+    // Every line takes at least one row, and long lines wrap
+    let lines: usize = content
+        .split('\n')
+        .map(|line| line.chars().count().div_ceil(chars_per_line).max(1))
+        .sum();
+
+    lines as f32 * MESSAGE_TEXT_SIZE * LINE_HEIGHT + 2.0 * BUBBLE_PADDING + ACTIONS_HEIGHT
+}
+
+/// Get the scroll offset that brings message `index` to the top of the viewport
+///
+/// `heights` holds the height of each message with its action row, in order.
+pub fn message_offset(heights: &[f32], index: usize) -> f32 {
+    // The bubble and the action row of each message are both followed by the column spacing
+    let above: f32 = heights.iter().take(index).map(|height| height + 2.0 * MESSAGE_SPACING).sum();
+    CONVERSATION_PADDING + above
+}
+
+/// Get the relative scroll offset of the next frame of a smooth scroll from `current` to `target`
+///
+/// Each frame covers a fixed fraction of the remaining distance, so the scroll
+/// slows down as it arrives, and snaps to the target once it is close.
+pub fn smooth_scroll_step(current: f32, target: f32) -> f32 {
+    let next = current + (target - current) * SMOOTH_SCROLL_EASING;
+    if (target - next).abs() <= SMOOTH_SCROLL_SNAP {
+        target
+    } else {
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_offset() {
+        let heights = [60.0, 100.0, 40.0];
+
+        assert_eq!(message_offset(&heights, 0), CONVERSATION_PADDING);
+        assert_eq!(message_offset(&heights, 1), CONVERSATION_PADDING + 60.0 + 2.0 * MESSAGE_SPACING);
+        assert_eq!(
+            message_offset(&heights, 3),
+            CONVERSATION_PADDING + 200.0 + 6.0 * MESSAGE_SPACING
+        );
+
+        // Indexes past the end stop at the last message
+        assert_eq!(message_offset(&heights, 10), message_offset(&heights, 3));
+    }
+
+    #[test]
+    fn test_smooth_scroll_reaches_target() {
+        let mut offset = 0.0;
+        let mut ticks = 0;
+        while offset != 1.0 {
+            let next = smooth_scroll_step(offset, 1.0);
+            assert!(next > offset, "each frame moves towards the target");
+            offset = next;
+            ticks += 1;
+            assert!(ticks <= 20, "the scroll should have arrived by now");
+        }
+
+        // 0.65^15 of the distance is left after 15 frames, the first within the snap distance
+        assert_eq!(ticks, 15);
+
+        // Being at the target already, or almost, arrives at once
+        assert_eq!(smooth_scroll_step(1.0, 1.0), 1.0);
+        assert_eq!(smooth_scroll_step(0.999, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_estimate_message_height() {
+        let line = MESSAGE_TEXT_SIZE * LINE_HEIGHT;
+        let one_line = estimate_message_height("Hello", 400.0);
+        assert_eq!(one_line, line + 2.0 * BUBBLE_PADDING + ACTIONS_HEIGHT);
+
+        // Each line break adds a row, as does wrapping a long line
+        assert_eq!(estimate_message_height("a\nb\n\nc", 400.0), one_line + 3.0 * line);
+        let long = "word ".repeat(100);
+        assert!(estimate_message_height(&long, 400.0) > one_line + 5.0 * line);
+        assert!(estimate_message_height(&long, 800.0) < estimate_message_height(&long, 400.0));
+    }
+}
@@ -0,0 +1,182 @@
+use iced::{
+    widget::{button, container, text, Column, Row, Scrollable, Space},
+    Color, Element, Font, Length, Padding,
+};
+use log::Level;
+use std::time::Duration;
+
+use crate::app::Message;
+use crate::data::logger::{active_log_path, line_level, tail_lines};
+
+/// Number of lines shown from the end of the log file
+pub const LOG_VIEWER_LINES: usize = 200;
+
+/// How often the log viewer re-reads the log file
+pub const LOG_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Height of the log viewer panel in pixels
+const LOG_VIEWER_HEIGHT: f32 = 220.0;
+
+/// State of the log viewer panel
+#[derive(Debug, Clone)]
+pub struct LogViewer {
+    /// Last lines of the log file, oldest first
+    lines: Vec<String>,
+    /// Least severe level shown
+    min_level: Level,
+    /// Why no lines could be read, if they could not
+    status: Option<String>,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            min_level: Level::Trace,
+            status: None,
+        }
+    }
+}
+
+impl LogViewer {
+    /// Create a log viewer showing the current end of the log file
+    pub fn new() -> Self {
+        let mut viewer = Self::default();
+        viewer.refresh();
+        viewer
+    }
+
+    /// Re-read the end of the log file
+    ///
+    /// The file is reopened by path each time, so a file that was truncated or
+    /// replaced underneath the viewer is picked up on the next refresh.
+    pub fn refresh(&mut self) {
+        let Some(path) = active_log_path() else {
+            self.lines.clear();
+            self.status = Some("File logging is disabled".to_string());
+            return;
+        };
+
+        match tail_lines(path, LOG_VIEWER_LINES) {
+            Ok(lines) => {
+                self.lines = lines;
+                self.status = None;
+            }
+            Err(e) => {
+                self.lines.clear();
+                self.status = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Show only lines at `level` or more severe
+    pub fn set_min_level(&mut self, level: Level) {
+        self.min_level = level;
+    }
+
+    /// Get the lines passing the level filter with their levels
+    ///
+    /// Lines without a level continue a multi-line message and take its level.
+    pub fn visible_lines(&self) -> Vec<(Option<Level>, &str)> {
+        let mut current = None;
+        self.lines
+            .iter()
+            .filter_map(|line| {
+                current = line_level(line).or(current);
+                let shown = !current.is_some_and(|level| level > self.min_level);
+                shown.then_some((current, line.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// Get the color used for lines of a level, matching the console colors
+fn level_color(level: Option<Level>) -> Color {
+    match level {
+        Some(Level::Error) => Color::from_rgb(0.9, 0.3, 0.3),
+        Some(Level::Warn) => Color::from_rgb(0.9, 0.8, 0.3),
+        Some(Level::Info) => Color::from_rgb(0.4, 0.8, 0.4),
+        Some(Level::Debug) => Color::from_rgb(0.4, 0.6, 0.9),
+        Some(Level::Trace) => Color::from_rgb(0.8, 0.4, 0.8),
+        None => Color::from_rgb(0.7, 0.7, 0.7),
+    }
+}
+
+/// Create the log viewer panel with level filter buttons
+pub fn log_viewer<'a>(viewer: &LogViewer) -> Element<'a, Message> {
+    let filters = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace]
+        .into_iter()
+        .fold(Row::new().spacing(4), |row, level| {
+            row.push(
+                button(text(level.as_str()).size(11))
+                    .padding(Padding::from([2, 6]))
+                    .style(if level == viewer.min_level {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    })
+                    .on_press(Message::LogLevelSelected(level)),
+            )
+        });
+
+    let header = Row::new()
+        .spacing(8)
+        .push(text("Logs").size(14))
+        .push(filters)
+        .push(Space::with_width(Length::Fill))
+        .push(
+            button(text("Close").size(11))
+                .padding(Padding::from([2, 6]))
+                .on_press(Message::ToggleLogViewer),
+        );
+
+    let lines = match &viewer.status {
+        Some(status) => Column::new().push(text(status).size(11)),
+        None => viewer
+            .visible_lines()
+            .into_iter()
+            .fold(Column::new(), |column, (level, line)| {
+                column.push(
+                    text(line)
+                        .size(11)
+                        .font(Font::MONOSPACE)
+                        .style(iced::theme::Text::Color(level_color(level))),
+                )
+            }),
+    };
+
+    container(
+        Column::new()
+            .spacing(6)
+            .push(header)
+            .push(Scrollable::new(lines).height(Length::Fill)),
+    )
+    .width(Length::Fill)
+    .height(Length::Fixed(LOG_VIEWER_HEIGHT))
+    .padding(8)
+    .style(iced::theme::Container::Box)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_keeps_continuation_lines_with_their_message() {
+        let viewer = LogViewer {
+            lines: vec![
+                "t,DEBUG,screensage,1,Request body:".to_string(),
+                "{\"model\": \"llama3.2\"}".to_string(),
+                "t,ERROR,screensage,2,Request failed".to_string(),
+            ],
+            min_level: Level::Warn,
+            status: None,
+        };
+
+        assert_eq!(
+            viewer.visible_lines(),
+            [(Some(Level::Error), "t,ERROR,screensage,2,Request failed")]
+        );
+    }
+}
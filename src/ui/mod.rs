@@ -1,6 +1,12 @@
 pub mod window;
 pub mod presentation;
 pub mod input;
+pub mod images;
+pub mod layout;
+pub mod log_viewer;
+pub mod settings;
+pub mod sidebar;
+pub mod theme;
 
 use iced::{
     widget::{button, row, text, text_input},
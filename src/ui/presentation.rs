@@ -1,10 +1,63 @@
 use iced::{
-    alignment, widget::{container, scrollable, Scrollable, text, Column, Container, Row},
+    alignment,
+    widget::{button, container, scrollable, Scrollable, text, text_input, tooltip, Column, Container, Row},
     Alignment, Color, Element, Length, Padding, Theme
 };
 
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
 use crate::app::Message;
-use crate::data::conversation::{Conversation, MessageRole};
+use crate::config::QUICK_SWITCH_SLOTS;
+use crate::data::conversation::{Conversation, Message as ConversationMessage, MessageRole, ResponseMetadata};
+use crate::data::issues::FailedRequest;
+use crate::data::outline::OutlineEntry;
+use crate::ui::images::DecodedImage;
+use crate::ui::layout::{
+    BUBBLE_PADDING, BUBBLE_WIDTH_FRACTION, CONVERSATION_PADDING, MESSAGE_SPACING, MESSAGE_TEXT_SIZE,
+};
+use crate::ui::theme::Palette;
+use crate::utils::diff::{DiffKind, DiffSegment};
+use crate::utils::markdown::{parse_markdown, Block, Inline, ParseMode};
+use crate::utils::{extract_and_prettify_json, extract_single_code_block};
+use crate::utils::format::{format_count, format_duration_ns};
+
+/// ID of the find bar's input, focused when the bar is opened
+pub const FIND_INPUT_ID: &str = "find_input";
+
+/// View state that affects how the conversation is drawn
+#[derive(Debug, Clone, Default)]
+pub struct PresentationOptions {
+    /// Whether messages can be read aloud
+    pub can_speak: bool,
+    /// Index of the message currently being read aloud
+    pub speaking: Option<usize>,
+    /// Colors used to draw the conversation
+    pub palette: Palette,
+    /// Whether to offer the response details disclosure
+    pub show_metadata: bool,
+    /// Whether the last request can be copied as a curl command
+    pub can_copy_request: bool,
+    /// Whether messages can currently be moved up and down
+    pub can_reorder: bool,
+    /// Whether the last response can be regenerated
+    pub can_regenerate: bool,
+    /// Indices of messages whose response details are expanded
+    pub expanded_details: HashSet<usize>,
+    /// Indices of messages selected for exporting as a snippet
+    pub selected: HashSet<usize>,
+    /// Text of the indicator shown at the bottom while a response is generated
+    pub loading: Option<String>,
+    /// Index of the message a response is streaming into
+    pub streaming: Option<usize>,
+    /// Indices of responses with earlier versions to compare against
+    pub regenerated: HashSet<usize>,
+    /// Changes of a response from its previous version, by message index
+    pub diff: Option<(usize, Vec<DiffSegment>)>,
+    /// Indices of messages matching the find query, which are outlined
+    pub found: HashSet<usize>,
+    /// Decoded images of each message, by message index
+    pub images: Vec<Vec<DecodedImage>>,
+}
 
 /// Message display style
 #[derive(Debug, Clone, Copy)]
@@ -15,24 +68,32 @@ pub enum MessageStyle {
     LLM,
     /// Error message style (left-aligned, white on dark red)
     Error,
+    /// Warning message style (left-aligned, dark on amber)
+    Warning,
+    /// System message style (centered, in the LLM colors)
+    System,
 }
 
 impl MessageStyle {
     /// Get the background color for the message style
-    pub fn background_color(&self, _theme: &Theme) -> Color {
+    pub fn background_color(&self, palette: &Palette) -> Color {
         match self {
-            MessageStyle::User => Color::from_rgb(0.0, 0.4, 0.8), // Blue
-            MessageStyle::LLM => Color::from_rgb(0.9, 0.9, 0.9),  // Light grey
-            MessageStyle::Error => Color::from_rgb(0.8, 0.0, 0.0), // Dark red
+            MessageStyle::User => palette.user_background,
+            MessageStyle::LLM => palette.assistant_background,
+            MessageStyle::Error => palette.error_background,
+            MessageStyle::Warning => palette.warning_background,
+            MessageStyle::System => palette.assistant_background,
         }
     }
 
     /// Get the text color for the message style
-    pub fn text_color(&self, _theme: &Theme) -> Color {
+    pub fn text_color(&self, palette: &Palette) -> Color {
         match self {
-            MessageStyle::User => Color::WHITE,
-            MessageStyle::LLM => Color::from_rgb(0.1, 0.1, 0.1), // Dark grey
-            MessageStyle::Error => Color::WHITE,
+            MessageStyle::User => palette.user_text,
+            MessageStyle::LLM => palette.assistant_text,
+            MessageStyle::Error => palette.error_text,
+            MessageStyle::Warning => palette.warning_text,
+            MessageStyle::System => palette.assistant_text,
         }
     }
 
@@ -42,6 +103,8 @@ impl MessageStyle {
             MessageStyle::User => Alignment::End,
             MessageStyle::LLM => Alignment::Start,
             MessageStyle::Error => Alignment::Start,
+            MessageStyle::Warning => Alignment::Start,
+            MessageStyle::System => Alignment::Center,
         }
     }
 }
@@ -50,19 +113,23 @@ impl MessageStyle {
 pub fn message_bubble<'a>(
     content: &str,
     style: MessageStyle,
-    theme: &Theme,
+    palette: &Palette,
 ) -> Element<'a, Message> {
-    let max_width = 0.8; // Maximum width as a fraction of the container
-
     let message_text = text(content)
-        .size(16)
-        .style(style.text_color(theme));
+        .size(MESSAGE_TEXT_SIZE)
+        .style(style.text_color(palette));
 
-    let message_container = container(message_text)
-        .padding(Padding::new(12.0))
-        .style(move |theme: &Theme| {
+    bubble(message_text.into(), style, palette)
+}
+
+/// Wrap content in a bubble aligned and colored for the message style
+fn bubble<'a>(content: Element<'a, Message>, style: MessageStyle, palette: &Palette) -> Element<'a, Message> {
+    let background = style.background_color(palette);
+    let message_container = container(content)
+        .padding(Padding::new(BUBBLE_PADDING))
+        .style(move |_theme: &Theme| {
             container::Appearance {
-                background: Some(style.background_color(theme).into()),
+                background: Some(background.into()),
                 border_radius: 12.0.into(),
                 ..Default::default()
             }
@@ -73,7 +140,7 @@ pub fn message_bubble<'a>(
         .align_items(style.alignment())
         .push(
             Container::new(message_container)
-                .width(Length::FillPortion((max_width * 10.0) as u16))
+                .width(Length::FillPortion((BUBBLE_WIDTH_FRACTION * 10.0) as u16))
                 .align_x(match style {
                     MessageStyle::User => alignment::Horizontal::Right,
                     _ => alignment::Horizontal::Left,
@@ -83,71 +150,822 @@ pub fn message_bubble<'a>(
     row.into()
 }
 
+/// Join inline runs into plain text, dropping the syntax of closed constructs
+fn inline_text(runs: &[Inline]) -> String {
+    runs.iter().map(Inline::text).collect()
+}
+
+/// Create an assistant bubble rendering Markdown blocks
+///
+/// Headings are drawn larger and bold, and code blocks in a monospace panel.
+pub fn markdown_bubble<'a>(content: &str, mode: ParseMode, palette: &Palette) -> Element<'a, Message> {
+    let text_color = MessageStyle::LLM.text_color(palette);
+    let bold = iced::Font {
+        weight: iced::font::Weight::Bold,
+        ..iced::Font::DEFAULT
+    };
+    let mut blocks = Column::new().spacing(8);
+    for block in parse_markdown(content, mode) {
+        let element: Element<'a, Message> = match block {
+            Block::Heading { depth, content } => {
+                // Top-level headings are largest, and deeper ones shrink towards the body size
+                let size = MESSAGE_TEXT_SIZE + 2.0 * (4.0 - depth.min(4) as f32);
+                text(inline_text(&content)).size(size).font(bold).style(text_color).into()
+            }
+            Block::Paragraph(runs) => text(inline_text(&runs))
+                .size(MESSAGE_TEXT_SIZE)
+                .style(text_color)
+                .into(),
+            Block::CodeBlock { language, code } => code_block(&language, &code, palette),
+        };
+        blocks = blocks.push(element);
+    }
+
+    bubble(blocks.into(), MessageStyle::LLM, palette)
+}
+
+/// Create a code block panel with its language, if any, and a button copying the code
+///
+/// Lines scroll sideways instead of wrapping, so indentation stays readable. The panel takes the
+/// conversation background to stand out from the bubble around it.
+fn code_block<'a>(language: &str, code: &str, palette: &Palette) -> Element<'a, Message> {
+    let muted = Color {
+        a: 0.6,
+        ..palette.assistant_text
+    };
+    let header = Row::new()
+        .align_items(Alignment::Center)
+        .push(text(language).size(11).font(iced::Font::MONOSPACE).style(muted))
+        .push(iced::widget::Space::with_width(Length::Fill))
+        .push(
+            button(text("Copy").size(11).style(muted))
+                .on_press(Message::CopyToClipboard(code.to_string()))
+                .padding(2)
+                .style(iced::theme::Button::Text),
+        );
+
+    // Tabs are drawn at unpredictable widths, so they are expanded to keep columns aligned
+    let code = text(code.replace('\t', "    "))
+        .size(MESSAGE_TEXT_SIZE - 2.0)
+        .font(iced::Font::MONOSPACE)
+        .style(palette.assistant_text);
+    let body = Scrollable::new(code)
+        .direction(scrollable::Direction::Horizontal(scrollable::Properties::default()));
+
+    let background = palette.background;
+    container(Column::new().spacing(4).push(header).push(body))
+        .padding(8)
+        .width(Length::Fill)
+        .style(move |_theme: &Theme| container::Appearance {
+            background: Some(background.into()),
+            border_radius: 6.0.into(),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Create the images returned with a response, each with an action to save it
+///
+/// Images that can't be decoded are shown as a placeholder instead.
+fn response_images<'a>(message_index: usize, images: &[DecodedImage], palette: &Palette) -> Element<'a, Message> {
+    let column = images.iter().enumerate().fold(Column::new().spacing(4), |column, (image_index, image)| {
+        match image {
+            Ok(handle) => column
+                .push(iced::widget::image(handle.clone()).width(Length::Fill))
+                .push(action_button("Save image", Message::SaveImage(message_index, image_index))),
+            Err(e) => column.push(
+                text(format!("Image could not be displayed: {}", e))
+                    .size(12)
+                    .style(palette.error_text),
+            ),
+        }
+    });
+
+    bubble(column.into(), MessageStyle::LLM, palette)
+}
+
+/// Split a pretty-printed JSON line into its indentation, object key (with the colon) and value
+fn split_json_line(line: &str) -> (&str, Option<&str>, &str) {
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+    if !rest.starts_with('"') {
+        return (indent, None, rest);
+    }
+
+    // Find the closing quote, skipping escaped quotes
+    let mut escaped = false;
+    for (offset, c) in rest.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => {
+                let end = offset + 1;
+                return match rest[end..].strip_prefix(": ") {
+                    Some(value) => (indent, Some(&rest[..end + 2]), value),
+                    None => (indent, None, rest),
+                };
+            }
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    (indent, None, rest)
+}
+
+/// Get the color of a JSON value from its first character
+fn json_value_color(value: &str, palette: &Palette) -> Color {
+    match value.chars().next() {
+        Some('"') => Color::from_rgb(0.2, 0.55, 0.25),
+        Some('-' | '0'..='9') => Color::from_rgb(0.15, 0.4, 0.8),
+        Some('t' | 'f' | 'n') => Color::from_rgb(0.6, 0.25, 0.65),
+        _ => palette.assistant_text,
+    }
+}
+
+/// Create an assistant bubble showing pretty-printed JSON with keys and values colored
+pub fn json_bubble<'a>(pretty: &str, palette: &Palette) -> Element<'a, Message> {
+    let key_color = Color::from_rgb(0.7, 0.3, 0.1);
+    let segment = |content: &str, color: Color| {
+        text(content)
+            .size(MESSAGE_TEXT_SIZE)
+            .font(iced::Font::MONOSPACE)
+            .style(color)
+    };
+    let lines = pretty.lines().fold(Column::new(), |column, line| {
+        let (indent, key, value) = split_json_line(line);
+        let mut row = Row::new().push(segment(indent, palette.assistant_text));
+        if let Some(key) = key {
+            row = row.push(segment(key, key_color));
+        }
+        column.push(row.push(segment(value, json_value_color(value, palette))))
+    });
+
+    bubble(lines.into(), MessageStyle::LLM, palette)
+}
+
+/// Create a small text button used for per-message actions
+fn action_button<'a>(label: &str, message: Message) -> iced::widget::Button<'a, Message> {
+    button(text(label).size(12))
+        .on_press(message)
+        .padding(2)
+        .style(iced::theme::Button::Text)
+}
+
+/// Create the row of actions shown under a message
+fn message_actions<'a>(
+    index: usize,
+    message: &ConversationMessage,
+    is_last: bool,
+    options: &PresentationOptions,
+) -> Element<'a, Message> {
+    let bookmark_label = if message.bookmarked { "Bookmarked" } else { "Bookmark" };
+    let select_label = if options.selected.contains(&index) { "Selected" } else { "Select" };
+    let mut actions = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(action_button(bookmark_label, Message::ToggleBookmark(index)))
+        .push(action_button("Fork", Message::ForkFromMessage(index)))
+        .push(action_button(select_label, Message::ToggleMessageSelection(index)));
+
+    // Messages that are a single code block copy just the code
+    if !message.content.is_empty() {
+        let copy_label = if extract_single_code_block(&message.content).is_some() {
+            "Copy code"
+        } else {
+            "Copy"
+        };
+        actions = actions.push(action_button(copy_label, Message::CopyMessage(index)));
+    }
+
+    if options.can_speak && message.role == MessageRole::Assistant {
+        let speak_button = if options.speaking == Some(index) {
+            action_button("Stop", Message::StopSpeaking)
+        } else {
+            action_button("Speak", Message::SpeakMessage(index))
+        };
+        actions = actions.push(speak_button);
+    }
+
+    if options.can_reorder {
+        if index > 0 {
+            actions = actions.push(action_button("↑", Message::MoveMessage(index, index - 1)));
+        }
+        if !is_last {
+            actions = actions.push(action_button("↓", Message::MoveMessage(index, index + 1)));
+        }
+    }
+
+    if options.can_regenerate && message.role == MessageRole::User {
+        actions = actions.push(action_button("Edit", Message::EditMessage(index)));
+    }
+
+    if options.can_regenerate && is_last && message.role == MessageRole::Assistant {
+        actions = actions.push(action_button("More creative", Message::RegenerateMoreCreative));
+    }
+
+    if message.metadata.as_ref().is_some_and(ResponseMetadata::hit_length_limit) {
+        actions = actions.push(
+            text("Response truncated (length)")
+                .size(12)
+                .style(iced::theme::Text::Color(Color::from_rgb(0.6, 0.6, 0.6))),
+        );
+        if options.can_regenerate && is_last {
+            actions = actions.push(action_button("Continue", Message::ContinueResponse));
+        }
+    }
+
+    if options.regenerated.contains(&index) && message.role == MessageRole::Assistant {
+        let diff_label = if options.diff.as_ref().is_some_and(|(shown, _)| *shown == index) {
+            "Hide changes"
+        } else {
+            "What changed"
+        };
+        actions = actions.push(action_button(diff_label, Message::ShowDiff(index)));
+    }
+
+    if let Some(temperature) = message.temperature {
+        actions = actions.push(text(format!("Temperature {:.1}", temperature)).size(12));
+    }
+
+    if let Some(params) = &message.params {
+        let badge = tooltip(text("ⓘ").size(12), params.summary(), tooltip::Position::Top)
+            .style(iced::theme::Container::Box)
+            .size(12);
+        actions = actions.push(badge);
+    }
+
+    if options.can_copy_request && is_last && message.role == MessageRole::Assistant {
+        actions = actions.push(action_button("Copy as curl", Message::CopyAsCurl));
+    }
+
+    if options.show_metadata && message.metadata.is_some() {
+        let details_label = if options.expanded_details.contains(&index) {
+            "Hide details"
+        } else {
+            "Details"
+        };
+        actions = actions.push(action_button(details_label, Message::ToggleResponseDetails(index)));
+    }
+
+    Container::new(actions)
+        .width(Length::Fill)
+        .align_x(match message.role {
+            MessageRole::User => alignment::Horizontal::Right,
+            MessageRole::System => alignment::Horizontal::Center,
+            _ => alignment::Horizontal::Left,
+        })
+        .into()
+}
+
+/// Create a panel showing the lines a response added and removed since its previous version
+fn response_diff<'a>(segments: &[DiffSegment], palette: &Palette) -> Element<'a, Message> {
+    let lines = segments.iter().fold(Column::new().spacing(2), |column, segment| {
+        let (prefix, color) = match segment.kind {
+            DiffKind::Unchanged => ("  ", palette.assistant_text),
+            DiffKind::Added => ("+ ", Color::from_rgb(0.1, 0.55, 0.2)),
+            DiffKind::Removed => ("- ", Color::from_rgb(0.75, 0.15, 0.15)),
+        };
+        segment.text.split('\n').fold(column, |column, line| {
+            column.push(
+                text(format!("{}{}", prefix, line))
+                    .size(12)
+                    .font(iced::Font::MONOSPACE)
+                    .style(color),
+            )
+        })
+    });
+
+    let background = palette.assistant_background;
+    container(lines)
+        .padding(Padding::from([6, 10]))
+        .width(Length::Fill)
+        .style(move |_theme: &Theme| container::Appearance {
+            background: Some(background.into()),
+            border_radius: 6.0.into(),
+            ..Default::default()
+        })
+        .into()
+}
+
+/// Create the expanded details of a response's API statistics
+fn response_details<'a>(metadata: &ResponseMetadata) -> Element<'a, Message> {
+    let duration = |nanos: Option<u64>| nanos.map_or_else(|| "-".to_string(), format_duration_ns);
+    let count = |value: Option<u64>| value.map_or_else(|| "-".to_string(), format_count);
+    let rows = [
+        ("Model", metadata.model.clone()),
+        ("Created", metadata.created_at.clone()),
+        ("Done reason", metadata.done_reason.clone().unwrap_or_else(|| "-".to_string())),
+        ("Total duration", duration(metadata.total_duration)),
+        ("Load duration", duration(metadata.load_duration)),
+        ("Prompt tokens", count(metadata.prompt_eval_count)),
+        ("Prompt eval", duration(metadata.prompt_eval_duration)),
+        ("Response tokens", count(metadata.eval_count)),
+        ("Response eval", duration(metadata.eval_duration)),
+        (
+            "Speed",
+            metadata
+                .tokens_per_second()
+                .map_or_else(|| "-".to_string(), |speed| format!("{:.1} tokens/s", speed)),
+        ),
+    ];
+
+    let details = rows.into_iter().fold(Column::new().spacing(2), |column, (label, value)| {
+        column.push(
+            Row::new()
+                .spacing(8)
+                .push(text(label).size(11).width(Length::Fixed(110.0)))
+                .push(text(value).size(11)),
+        )
+    });
+
+    container(details).padding(Padding::from([4, 8])).into()
+}
+
+/// Create a bar for jumping between bookmarked messages
+pub fn bookmark_navigator<'a>(bookmark_count: usize) -> Element<'a, Message> {
+    let label = if bookmark_count == 1 {
+        "1 bookmark".to_string()
+    } else {
+        format!("{} bookmarks", bookmark_count)
+    };
+
+    Row::new()
+        .spacing(8)
+        .padding(Padding::from([4, 16]))
+        .align_items(Alignment::Center)
+        .push(text(label).size(12))
+        .push(action_button("Next bookmark", Message::NextBookmark))
+        .into()
+}
+
+/// Create the bar for finding messages in the conversation, with the matches counted
+///
+/// `current` is the position in the matches of the one last jumped to.
+pub fn find_bar<'a>(query: &str, match_count: usize, current: Option<usize>) -> Element<'a, Message> {
+    let label = match (match_count, current) {
+        (0, _) if query.trim().is_empty() => String::new(),
+        (0, _) => "No matches".to_string(),
+        (count, Some(current)) => format!("{} of {}", current + 1, count),
+        (1, None) => "1 match".to_string(),
+        (count, None) => format!("{} matches", count),
+    };
+
+    let mut row = Row::new()
+        .spacing(8)
+        .padding(Padding::from([4, 16]))
+        .align_items(Alignment::Center)
+        .push(
+            text_input("Find in conversation", query)
+                .id(text_input::Id::new(FIND_INPUT_ID))
+                .on_input(Message::FindChanged)
+                .on_submit(Message::NextFindMatch)
+                .size(12)
+                .width(Length::Fill),
+        )
+        .push(text(label).size(12));
+    if match_count > 0 {
+        row = row
+            .push(action_button("Previous", Message::PreviousFindMatch))
+            .push(action_button("Next", Message::NextFindMatch));
+    }
+    row.push(action_button("Close", Message::ToggleFind)).into()
+}
+
+/// Create a bar asking to confirm clearing the conversation
+///
+/// `deletes_file` says whether the saved conversation is deleted too.
+pub fn clear_confirmation_bar<'a>(deletes_file: bool) -> Element<'a, Message> {
+    let label = if deletes_file {
+        "Clear this conversation and delete its saved file?"
+    } else {
+        "Clear this conversation? It stays in the sidebar."
+    };
+
+    Row::new()
+        .spacing(8)
+        .padding(Padding::from([4, 16]))
+        .align_items(Alignment::Center)
+        .push(text(label).size(12))
+        .push(action_button("Clear", Message::ClearConversation))
+        .push(action_button("Cancel", Message::CancelClearConversation))
+        .into()
+}
+
+/// Create a bar asking to confirm deleting the conversation `id`, titled `title`, and its saved file
+pub fn delete_confirmation_bar<'a>(id: &str, title: &str) -> Element<'a, Message> {
+    Row::new()
+        .spacing(8)
+        .padding(Padding::from([4, 16]))
+        .align_items(Alignment::Center)
+        .push(text(format!("Delete \"{}\" and its saved file?", title)).size(12))
+        .push(action_button("Delete", Message::DeleteConversation(id.to_string())))
+        .push(action_button("Cancel", Message::CancelDeleteConversation))
+        .into()
+}
+
+/// Create a bar with actions for the selected messages
+pub fn selection_bar<'a>(selected_count: usize) -> Element<'a, Message> {
+    let label = if selected_count == 1 {
+        "1 message selected".to_string()
+    } else {
+        format!("{} messages selected", selected_count)
+    };
+
+    Row::new()
+        .spacing(8)
+        .padding(Padding::from([4, 16]))
+        .align_items(Alignment::Center)
+        .push(text(label).size(12))
+        .push(action_button("Copy as Markdown", Message::CopySelection))
+        .push(action_button("Save snippet", Message::SaveSelectionAsSnippet))
+        .push(action_button("Clear", Message::ClearSelection))
+        .into()
+}
+
+/// Create a panel listing the headings of the responses, each scrolling to its message
+pub fn outline_panel<'a>(outline: &[OutlineEntry]) -> Element<'a, Message> {
+    let header = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(text("Outline").size(12))
+        .push(action_button("Hide", Message::ToggleOutline));
+
+    let entries = if outline.is_empty() {
+        Column::new().push(text("No headings in the responses yet").size(12))
+    } else {
+        outline.iter().fold(Column::new().spacing(2), |column, entry| {
+            // Indent each level under its parent
+            let indent = (entry.depth - 1) as f32 * 12.0;
+            column.push(
+                Row::new()
+                    .push(iced::widget::Space::with_width(Length::Fixed(indent)))
+                    .push(action_button(&entry.title, Message::ScrollToMessage(entry.message_index))),
+            )
+        })
+    };
+
+    container(Column::new().spacing(4).push(header).push(scrollable(entries)))
+        .padding(Padding::from([8, 16]))
+        .width(Length::Fill)
+        .max_height(200.0)
+        .into()
+}
+
+/// Create a panel listing the failed requests, newest first, each with an action to send it again
+pub fn issues_panel<'a>(
+    failed: &VecDeque<FailedRequest>,
+    can_retry: bool,
+    palette: &Palette,
+) -> Element<'a, Message> {
+    let header = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(text("Issues").size(12))
+        .push(action_button("Clear", Message::ClearFailedRequests))
+        .push(action_button("Hide", Message::ToggleIssues));
+
+    let entries = if failed.is_empty() {
+        Column::new().push(text("No failed requests").size(12))
+    } else {
+        failed.iter().enumerate().rev().fold(Column::new().spacing(6), |column, (index, request)| {
+            let time = request.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+            // Retrying waits until the current response is done
+            let mut retry = button(text("Retry this").size(12))
+                .padding(2)
+                .style(iced::theme::Button::Text);
+            if can_retry {
+                retry = retry.on_press(Message::RetryFailedRequest(index));
+            }
+            column.push(
+                Column::new()
+                    .spacing(2)
+                    .push(
+                        Row::new()
+                            .spacing(8)
+                            .align_items(Alignment::Center)
+                            .push(text(format!("{} · {}", time, request.model)).size(12))
+                            .push(retry),
+                    )
+                    .push(text(request.error.to_string()).size(12).style(palette.error_text))
+                    .push(text(request.prompt_preview()).size(12)),
+            )
+        })
+    };
+
+    container(Column::new().spacing(4).push(header).push(scrollable(entries)))
+        .padding(Padding::from([8, 16]))
+        .width(Length::Fill)
+        .max_height(200.0)
+        .into()
+}
+
+/// Create a panel describing the conversation, with a field for replaying it against another model
+pub fn conversation_info<'a>(
+    conversation: &Conversation,
+    replay_model: &str,
+    quick_switch: &BTreeMap<u8, String>,
+) -> Element<'a, Message> {
+    let stats = conversation.stats();
+    let local_time = |time: &chrono::DateTime<chrono::Utc>| {
+        time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+    };
+    let rows = [
+        ("ID", conversation.id.clone()),
+        ("Model", conversation.model.clone()),
+        ("Created", local_time(&conversation.created_at)),
+        ("Updated", local_time(&conversation.updated_at)),
+        (
+            "Messages",
+            format!(
+                "{} ({} user, {} assistant)",
+                stats.message_count, stats.user_messages, stats.assistant_messages
+            ),
+        ),
+        ("Estimated tokens", format!("~{}", stats.estimated_tokens)),
+        (
+            "Words",
+            format!(
+                "{} ({} user, {} assistant)",
+                stats.word_count, stats.user_words, stats.assistant_words
+            ),
+        ),
+        ("Reading time", format!("~{} min", stats.reading_minutes())),
+    ];
+
+    let details = rows.into_iter().fold(Column::new().spacing(4), |column, (label, value)| {
+        column.push(
+            Row::new()
+                .spacing(8)
+                .push(text(label).size(12).width(Length::Fixed(110.0)))
+                .push(text(value).size(12)),
+        )
+    });
+
+    let replay = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(
+            text_input("Model to replay with", replay_model)
+                .on_input(Message::ReplayModelChanged)
+                .on_submit(Message::ReplayWith(replay_model.to_string()))
+                .size(12),
+        )
+        .push(action_button("Replay", Message::ReplayWith(replay_model.to_string())));
+
+    let think_label = match conversation.think {
+        None => "Thinking: model default",
+        Some(true) => "Thinking: on",
+        Some(false) => "Thinking: off",
+    };
+    let think = action_button(think_label, Message::CycleThink);
+
+    let prompt = conversation.system_prompt.as_deref().unwrap_or_default();
+    let system_prompt = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(text("System prompt").size(12).width(Length::Fixed(110.0)))
+        .push(
+            text_input("Sent after ollama.system_prompt", prompt)
+                .on_input(Message::SystemPromptChanged)
+                .size(12),
+        );
+
+    // The slots assigned to this conversation are highlighted, and clicking one clears it
+    let slots = QUICK_SWITCH_SLOTS.fold(
+        Row::new().spacing(4).align_items(Alignment::Center).push(text("Quick switch ⌘").size(12)),
+        |row, slot| {
+            let assigned = quick_switch.get(&slot) == Some(&conversation.id);
+            row.push(
+                button(text(slot.to_string()).size(12))
+                    .padding(Padding::from([2, 6]))
+                    .style(if assigned {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    })
+                    .on_press(Message::AssignQuickSwitch(slot)),
+            )
+        },
+    );
+
+    container(details.push(system_prompt).push(think).push(slots).push(replay))
+        .padding(Padding::from([8, 16]))
+        .width(Length::Fill)
+        .into()
+}
+
 /// Create a presentation area for the conversation
 pub fn presentation_area<'a>(
     conversation: &Conversation,
-    theme: &Theme,
+    options: &PresentationOptions,
 ) -> Element<'a, Message> {
     let mut messages_column = Column::new()
-        .spacing(12)
-        .padding(Padding::new(16.0))
+        .spacing(MESSAGE_SPACING)
+        .padding(Padding::new(CONVERSATION_PADDING))
         .width(Length::Fill);
 
     // Add messages from the conversation
-    for message in &conversation.messages {
+    for (index, message) in conversation.messages.iter().enumerate() {
+        // The loading bubble stands in for a response that hasn't started arriving
+        let is_last = index + 1 == conversation.messages.len();
+        let is_placeholder = message.role == MessageRole::Assistant && message.content.is_empty();
+        if options.loading.is_some() && is_last && is_placeholder {
+            break;
+        }
+
         let style = match message.role {
             MessageRole::User => MessageStyle::User,
             MessageRole::Assistant => MessageStyle::LLM,
+            MessageRole::System => MessageStyle::System,
         };
-        messages_column = messages_column.push(message_bubble(&message.content, style, theme));
+        // Responses show the text the response hook printed, if any, and those that are only JSON
+        // are shown pretty-printed, both keeping the stored content as sent,
+        // and others as Markdown, leaving unclosed syntax literal while they stream
+        let bubble = match message.role {
+            MessageRole::Assistant => match extract_and_prettify_json(message.displayed()) {
+                Some(pretty) => json_bubble(&pretty, &options.palette),
+                None => {
+                    let mode = if options.streaming == Some(index) {
+                        ParseMode::Streaming
+                    } else {
+                        ParseMode::Complete
+                    };
+                    markdown_bubble(message.displayed(), mode, &options.palette)
+                }
+            },
+            MessageRole::User | MessageRole::System => {
+                message_bubble(&message.content, style, &options.palette)
+            }
+        };
+        // Messages matching the find query are outlined
+        let bubble: Element<'a, Message> = if options.found.contains(&index) {
+            let outline = options.palette.warning_background;
+            container(bubble)
+                .padding(2)
+                .style(move |_theme: &Theme| container::Appearance {
+                    border_width: 2.0,
+                    border_color: outline,
+                    border_radius: 8.0.into(),
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            bubble
+        };
+        // Responses made only of images show no empty bubble above them
+        if !message.content.is_empty() || message.images.is_empty() {
+            messages_column = messages_column.push(bubble);
+        }
+        if !message.images.is_empty() {
+            let images = options.images.get(index).map(Vec::as_slice).unwrap_or_default();
+            messages_column = messages_column.push(response_images(index, images, &options.palette));
+        }
+        messages_column = messages_column.push(message_actions(index, message, is_last, options));
+
+        if let Some((_, segments)) = options.diff.as_ref().filter(|(shown, _)| *shown == index) {
+            messages_column = messages_column.push(response_diff(segments, &options.palette));
+        }
+
+        if options.show_metadata && options.expanded_details.contains(&index) {
+            if let Some(metadata) = &message.metadata {
+                messages_column = messages_column.push(response_details(metadata));
+            }
+        }
+    }
+
+    if let Some(loading) = &options.loading {
+        messages_column = messages_column.push(loading_bubble(loading, &options.palette));
     }
 
     // Create a scrollable container for the messages with a specific ID
     let scrollable = Scrollable::new(messages_column)
         .width(Length::Fill)
         .height(Length::Fill)
-        .id(scrollable::Id::new("conversation_messages"));
+        .id(scrollable::Id::new("conversation_messages"))
+        .on_scroll(|viewport| Message::ConversationScrolled(viewport.relative_offset().y));
 
+    let background = options.palette.background;
     container(scrollable)
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(|_theme: &Theme| {
+        .style(move |_theme: &Theme| {
             container::Appearance {
-                background: Some(Color::from_rgb(0.95, 0.95, 0.95).into()),
+                background: Some(background.into()),
                 ..Default::default()
             }
         })
         .into()
 }
 
+/// Create a panel showing the request that would have been sent in dry-run mode
+pub fn dry_run_preview<'a>(preview: &str, palette: &Palette) -> Element<'a, Message> {
+    let header = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(text("Dry run: request not sent").size(12))
+        .push(action_button("Dismiss", Message::DismissDryRunPreview));
+
+    let body = text(preview)
+        .size(12)
+        .font(iced::Font::MONOSPACE)
+        .style(MessageStyle::LLM.text_color(palette));
+
+    let background = MessageStyle::LLM.background_color(palette);
+    let panel = container(scrollable(body))
+        .padding(Padding::new(12.0))
+        .width(Length::Fill)
+        .max_height(240.0)
+        .style(move |_theme: &Theme| {
+            container::Appearance {
+                background: Some(background.into()),
+                border_radius: 12.0.into(),
+                ..Default::default()
+            }
+        });
+
+    Column::new()
+        .spacing(4)
+        .padding(Padding::from([0, 16]))
+        .push(header)
+        .push(panel)
+        .into()
+}
+
 /// Create an error message
-pub fn error_message<'a>(error: &str, theme: &Theme) -> Element<'a, Message> {
-    message_bubble(error, MessageStyle::Error, theme)
+pub fn error_message<'a>(error: &str, palette: &Palette) -> Element<'a, Message> {
+    message_bubble(error, MessageStyle::Error, palette)
 }
 
-/// Create a loading indicator with a message
-pub fn loading_indicator<'a>(message: &str, theme: &Theme) -> Element<'a, Message> {
-    use iced::widget::{container, row, text, Space};
-    use iced::{Alignment, Length};
-    
-    let loading_text = text(message)
+/// Create a warning message
+pub fn warning_message<'a>(warning: &str, palette: &Palette) -> Element<'a, Message> {
+    message_bubble(warning, MessageStyle::Warning, palette)
+}
+
+/// Create an assistant bubble showing that a response is being generated
+pub fn loading_bubble<'a>(message: &str, palette: &Palette) -> Element<'a, Message> {
+    message_bubble(&format!("⟳ {}", message), MessageStyle::LLM, palette)
+}
+
+/// Create a loading indicator for the connection probe with a button to skip it
+pub fn connecting_indicator<'a>(message: &str) -> Element<'a, Message> {
+    let status = text(message)
+        .size(14)
+        .style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7)));
+
+    Row::new()
+        .spacing(10)
+        .padding(10)
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .push(text("⟳").size(16).style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7))))
+        .push(status)
+        .push(action_button("Skip", Message::CancelConnecting))
+        .into()
+}
+
+/// Create a progress indicator for a replay with a button to cancel it
+pub fn replay_indicator<'a>(message: &str) -> Element<'a, Message> {
+    let status = text(message)
         .size(14)
         .style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7)));
-    
-    let spinner = container(
-        text("⟳").size(16).style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7)))
-    )
-    .width(Length::Shrink)
-    .height(Length::Shrink);
-    
-    row![
-        spinner,
-        Space::with_width(Length::Fixed(10.0)),
-        loading_text
-    ]
-    .spacing(5)
-    .padding(10)
-    .align_items(Alignment::Center)
-    .width(Length::Fill)
-    .into()
+
+    Row::new()
+        .spacing(10)
+        .padding(10)
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .push(text("⟳").size(16).style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7))))
+        .push(status)
+        .push(action_button("Cancel", Message::CancelReplay))
+        .into()
+}
+
+/// Create a progress indicator for the download of a missing model
+pub fn download_indicator<'a>(message: &str) -> Element<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .padding(Padding::from([0, 10]))
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .push(text("⤓").size(16).style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7))))
+        .push(
+            text(message)
+                .size(14)
+                .style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7))),
+        )
+        .into()
+}
+
+/// Create an indicator shown while working offline with a button to reconnect
+pub fn offline_indicator<'a>() -> Element<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .padding(Padding::from([0, 10]))
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .push(
+            text("Offline")
+                .size(14)
+                .style(iced::theme::Text::Color(Color::from_rgb(0.7, 0.7, 0.7))),
+        )
+        .push(action_button("Retry", Message::Reconnect))
+        .into()
 }
@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use iced::{
+    widget::{button, checkbox, container, text, text_input, Column, Row},
+    Alignment, Color, Element, Length, Padding,
+};
+use std::str::FromStr;
+
+use crate::app::Message;
+use crate::config::{Config, WindowLevel};
+use crate::ollama::models::RunningModel;
+use crate::utils::format::format_bytes;
+
+/// Width of the label in front of each setting
+const LABEL_WIDTH: f32 = 110.0;
+
+/// Color of the message explaining why a change wasn't applied
+const ERROR_COLOR: Color = Color::from_rgb(0.8, 0.15, 0.15);
+
+/// A setting typed into the settings panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// `ollama.default_model`
+    Model,
+    /// `ollama.temperature`
+    Temperature,
+    /// `ollama.top_p`
+    TopP,
+    /// `ollama.top_k`
+    TopK,
+    /// `ollama.max_tokens`
+    MaxTokens,
+    /// `window.opacity`
+    Opacity,
+}
+
+impl Setting {
+    /// Every setting, in the order they are shown
+    const ALL: [Setting; 6] = [
+        Setting::Model,
+        Setting::Temperature,
+        Setting::TopP,
+        Setting::TopK,
+        Setting::MaxTokens,
+        Setting::Opacity,
+    ];
+
+    /// Get the label shown in front of the setting
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Model => "Model",
+            Setting::Temperature => "Temperature",
+            Setting::TopP => "Top-p",
+            Setting::TopK => "Top-k",
+            Setting::MaxTokens => "Max tokens",
+            Setting::Opacity => "Opacity",
+        }
+    }
+}
+
+/// Settings as typed into the settings panel
+///
+/// Numbers are kept as text so a partly typed value like `0.` can still be edited.
+#[derive(Debug, Clone)]
+pub struct SettingsForm {
+    model: String,
+    temperature: String,
+    top_p: String,
+    top_k: String,
+    max_tokens: String,
+    opacity: String,
+    /// Why the last change wasn't applied, if it wasn't
+    pub error: Option<String>,
+    /// Models loaded into memory, or why they couldn't be listed; `None` while listing them
+    pub running_models: Option<Result<Vec<RunningModel>, String>>,
+}
+
+impl SettingsForm {
+    /// Fill the form in from the current configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            model: config.ollama.default_model.clone(),
+            temperature: config.ollama.temperature.to_string(),
+            top_p: config.ollama.top_p.to_string(),
+            top_k: config.ollama.top_k.to_string(),
+            max_tokens: config.ollama.max_tokens.to_string(),
+            opacity: config.window.opacity.to_string(),
+            error: None,
+            running_models: None,
+        }
+    }
+
+    /// Get the text typed for a setting
+    pub fn value(&self, setting: Setting) -> &str {
+        match setting {
+            Setting::Model => &self.model,
+            Setting::Temperature => &self.temperature,
+            Setting::TopP => &self.top_p,
+            Setting::TopK => &self.top_k,
+            Setting::MaxTokens => &self.max_tokens,
+            Setting::Opacity => &self.opacity,
+        }
+    }
+
+    /// Replace the text typed for a setting
+    pub fn set(&mut self, setting: Setting, value: String) {
+        let field = match setting {
+            Setting::Model => &mut self.model,
+            Setting::Temperature => &mut self.temperature,
+            Setting::TopP => &mut self.top_p,
+            Setting::TopK => &mut self.top_k,
+            Setting::MaxTokens => &mut self.max_tokens,
+            Setting::Opacity => &mut self.opacity,
+        };
+        *field = value;
+    }
+
+    /// Get a copy of `config` with the typed settings, failing if any of them is invalid
+    pub fn apply(&self, config: &Config) -> Result<Config> {
+        let mut config = config.clone();
+        config.ollama.default_model = self.model.trim().to_string();
+        config.ollama.temperature = parse_number(&self.temperature, Setting::Temperature)?;
+        config.ollama.top_p = parse_number(&self.top_p, Setting::TopP)?;
+        config.ollama.top_k = parse_number(&self.top_k, Setting::TopK)?;
+        config.ollama.max_tokens = parse_number(&self.max_tokens, Setting::MaxTokens)?;
+        config.window.opacity = parse_number(&self.opacity, Setting::Opacity)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Parse the number typed for a setting
+fn parse_number<T: FromStr>(value: &str, setting: Setting) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .with_context(|| format!("{} must be a number, got {:?}", setting.label(), value))
+}
+
+/// Describe a loaded model's memory use and when it will be unloaded
+fn running_model_summary(model: &RunningModel) -> String {
+    let mut summary = format!("{}  {}", model.name, format_bytes(model.size));
+    if model.size_vram > 0 {
+        summary.push_str(&format!(" ({} in VRAM)", format_bytes(model.size_vram)));
+    }
+    if let Ok(expires_at) = DateTime::parse_from_rfc3339(&model.expires_at) {
+        summary.push_str(&format!(", unloads at {}", expires_at.with_timezone(&Local).format("%H:%M")));
+    }
+    summary
+}
+
+/// Create the list of models loaded into memory, with a button to list them again
+fn running_models_section<'a>(
+    running_models: &Option<Result<Vec<RunningModel>, String>>,
+) -> Element<'a, Message> {
+    let lines: Vec<String> = match running_models {
+        None => vec!["Listing loaded models...".to_string()],
+        Some(Ok(models)) if models.is_empty() => vec!["No models loaded".to_string()],
+        Some(Ok(models)) => models.iter().map(running_model_summary).collect(),
+        Some(Err(e)) => vec![format!("Failed to list loaded models: {}", e)],
+    };
+    let header = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(text("Loaded models").size(12).width(Length::Fill))
+        .push(
+            button(text("Refresh").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::ListRunningModels),
+        );
+    lines
+        .into_iter()
+        .fold(Column::new().spacing(4).push(header), |column, line| column.push(text(line).size(12)))
+        .into()
+}
+
+/// Create the settings panel shown over the conversation
+///
+/// Valid changes apply as they are typed; Save writes them to the configuration file.
+pub fn settings_panel<'a>(config: &Config, form: &SettingsForm) -> Element<'a, Message> {
+    let fields = Setting::ALL.into_iter().fold(Column::new().spacing(6), |column, setting| {
+        column.push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(text(setting.label()).size(12).width(Length::Fixed(LABEL_WIDTH)))
+                .push(
+                    text_input(setting.label(), form.value(setting))
+                        .on_input(move |value| Message::SettingChanged(setting, value))
+                        .size(12),
+                ),
+        )
+    });
+
+    let level = config.window.level.with_always_on_top(config.window.always_on_top);
+    let on_top = level == WindowLevel::AlwaysOnTop;
+    let always_on_top = checkbox("Keep the window above other windows", on_top, |_| Message::ToggleAlwaysOnTop)
+        .text_size(12);
+
+    let mut panel = Column::new()
+        .spacing(10)
+        .push(text("Settings").size(16))
+        .push(fields)
+        .push(always_on_top)
+        .push(running_models_section(&form.running_models));
+    if let Some(error) = &form.error {
+        panel = panel.push(text(error).size(12).style(ERROR_COLOR));
+    }
+
+    // Only the last valid settings are applied, so saving waits until the typed ones are valid
+    let save = button(text("Save").size(12)).style(iced::theme::Button::Primary);
+    let save = if form.error.is_none() { save.on_press(Message::SaveSettings) } else { save };
+    let actions = Row::new()
+        .spacing(8)
+        .push(save)
+        .push(
+            button(text("Edit config file").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::OpenConfigFile),
+        )
+        .push(
+            button(text("Close").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::ToggleSettings),
+        );
+
+    container(panel.push(actions))
+        .padding(Padding::from([8, 16]))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_valid_settings() {
+        let config = Config::default();
+        let mut form = SettingsForm::from_config(&config);
+        form.set(Setting::Model, " mistral ".to_string());
+        form.set(Setting::Temperature, "1.2".to_string());
+        form.set(Setting::Opacity, "0.5".to_string());
+
+        let applied = form.apply(&config).unwrap();
+        assert_eq!(applied.ollama.default_model, "mistral");
+        assert_eq!(applied.ollama.temperature, 1.2);
+        assert_eq!(applied.window.opacity, 0.5);
+        assert_eq!(applied.ollama.top_k, config.ollama.top_k);
+    }
+
+    #[test]
+    fn test_running_model_summary() {
+        let model = RunningModel {
+            name: "llama3.2:latest".to_string(),
+            size: 5_137_025_024,
+            size_vram: 0,
+            expires_at: "not a time".to_string(),
+        };
+        assert_eq!(running_model_summary(&model), "llama3.2:latest  5.1 GB");
+
+        let model = RunningModel { size_vram: 4_294_967_296, ..model };
+        assert_eq!(running_model_summary(&model), "llama3.2:latest  5.1 GB (4.3 GB in VRAM)");
+    }
+
+    #[test]
+    fn test_apply_rejects_invalid_settings() {
+        let config = Config::default();
+        let mut form = SettingsForm::from_config(&config);
+        form.set(Setting::TopK, "many".to_string());
+        assert!(form.apply(&config).unwrap_err().to_string().contains("Top-k"));
+
+        let mut form = SettingsForm::from_config(&config);
+        form.set(Setting::Opacity, "1.5".to_string());
+        assert!(form.apply(&config).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use iced::{
+    font,
+    widget::{button, container, text, text_input, Column, Row, Scrollable},
+    Element, Font, Length, Padding,
+};
+
+use crate::app::Message;
+use crate::data::conversation::Conversation;
+use crate::data::search::SearchHit;
+
+/// Width of the conversation sidebar in pixels
+pub const SIDEBAR_WIDTH: f32 = 140.0;
+
+/// Create a sidebar listing saved conversations, highlighting the selected one
+///
+/// While `search_query` is not empty, the matching messages are listed instead.
+pub fn sidebar<'a>(
+    conversations: &[Conversation],
+    selected_id: &str,
+    preview_len: usize,
+    search_query: &str,
+    search_hits: &[SearchHit],
+) -> Element<'a, Message> {
+    let search = text_input("Search", search_query)
+        .on_input(Message::SearchChanged)
+        .size(12)
+        .padding(4);
+
+    let list = if search_query.trim().is_empty() {
+        conversation_list(conversations, selected_id, preview_len)
+    } else {
+        search_results(search_hits)
+    };
+
+    let new_conversation = button(text("New Conversation").size(12))
+        .on_press(Message::NewConversation)
+        .width(Length::Fill)
+        .padding(Padding::from([6, 8]));
+
+    container(
+        Column::new()
+            .spacing(6)
+            .push(new_conversation)
+            .push(search)
+            .push(Scrollable::new(list).height(Length::Fill)),
+    )
+    .width(Length::Fixed(SIDEBAR_WIDTH))
+    .height(Length::Fill)
+    .padding(Padding::from([8, 4]))
+    .into()
+}
+
+/// Create the list of saved conversations
+fn conversation_list<'a>(
+    conversations: &[Conversation],
+    selected_id: &str,
+    preview_len: usize,
+) -> Column<'a, Message> {
+    conversations.iter().fold(Column::new().spacing(2), |column, conversation| {
+        let selected = conversation.id == selected_id;
+        let label = text(conversation.summary_with_preview_len(preview_len)).size(12);
+        let entry = button(label)
+            .width(Length::Fill)
+            .padding(Padding::from([6, 8]))
+            .style(if selected {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Text
+            });
+
+        // The selected conversation is already shown, so only others are clickable
+        let entry = if selected {
+            entry
+        } else {
+            entry.on_press(Message::SelectConversation(conversation.id.clone()))
+        };
+        let delete = button(text("×").size(12))
+            .on_press(Message::RequestDeleteConversation(conversation.id.clone()))
+            .padding(Padding::from([6, 4]))
+            .style(iced::theme::Button::Text);
+        column.push(Row::new().push(entry).push(delete))
+    })
+}
+
+/// Create the list of search hits, each showing the match in bold with its context
+fn search_results<'a>(hits: &[SearchHit]) -> Column<'a, Message> {
+    if hits.is_empty() {
+        return Column::new().push(text("No matches").size(12));
+    }
+
+    let bold = Font {
+        weight: font::Weight::Bold,
+        ..Font::DEFAULT
+    };
+    hits.iter().fold(Column::new().spacing(2), |column, hit| {
+        let snippet = Row::new()
+            .push(text(&hit.context_before).size(12))
+            .push(text(&hit.matched).size(12).font(bold))
+            .push(text(&hit.context_after).size(12));
+        let entry = Column::new()
+            .spacing(2)
+            .push(text(&hit.conversation_title).size(10))
+            .push(snippet);
+
+        column.push(
+            button(entry)
+                .width(Length::Fill)
+                .padding(Padding::from([6, 8]))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::OpenSearchHit(hit.conversation_id.clone(), hit.message_index)),
+        )
+    })
+}
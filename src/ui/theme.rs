@@ -0,0 +1,189 @@
+use iced::{Color, Theme};
+use log::{debug, warn};
+
+use crate::config::{ColorScheme, ThemeMode};
+
+/// Resolved colors used to draw the conversation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Background of the conversation area
+    pub background: Color,
+    /// Background of user message bubbles
+    pub user_background: Color,
+    /// Text color of user message bubbles
+    pub user_text: Color,
+    /// Background of assistant message bubbles
+    pub assistant_background: Color,
+    /// Text color of assistant message bubbles
+    pub assistant_text: Color,
+    /// Background of error message bubbles
+    pub error_background: Color,
+    /// Text color of error message bubbles
+    pub error_text: Color,
+    /// Background of warning message bubbles
+    pub warning_background: Color,
+    /// Text color of warning message bubbles
+    pub warning_text: Color,
+}
+
+impl Palette {
+    /// Resolve a configured color scheme for `theme`, falling back to defaults for invalid entries
+    ///
+    /// With the dark theme, colors left at their light defaults use the dark defaults instead.
+    pub fn from_scheme(scheme: &ColorScheme, theme: &Theme) -> Self {
+        let light = ColorScheme::default();
+        let defaults = if is_dark(theme) { ColorScheme::dark() } else { ColorScheme::default() };
+        let resolve = |name: &str, value: &str, light_default: &str, default: &str| {
+            let value = if value == light_default { default } else { value };
+            parse_hex_color(value).unwrap_or_else(|| {
+                warn!("Invalid color {:?} for theme.colors.{}, using {}", value, name, default);
+                parse_hex_color(default).expect("default colors are valid hex")
+            })
+        };
+        macro_rules! color {
+            ($field:ident) => {
+                resolve(stringify!($field), &scheme.$field, &light.$field, &defaults.$field)
+            };
+        }
+
+        Self {
+            background: color!(background),
+            user_background: color!(user_background),
+            user_text: color!(user_text),
+            assistant_background: color!(assistant_background),
+            assistant_text: color!(assistant_text),
+            error_background: color!(error_background),
+            error_text: color!(error_text),
+            warning_background: color!(warning_background),
+            warning_text: color!(warning_text),
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::from_scheme(&ColorScheme::default(), &Theme::Light)
+    }
+}
+
+/// Check whether a theme draws light text on dark backgrounds
+pub fn is_dark(theme: &Theme) -> bool {
+    *theme == Theme::Dark
+}
+
+/// Get the theme for a mode, checking the OS appearance for `ThemeMode::System`
+pub fn resolve_theme(mode: ThemeMode) -> Theme {
+    let dark = match mode {
+        ThemeMode::Light => false,
+        ThemeMode::Dark => true,
+        ThemeMode::System => system_prefers_dark(),
+    };
+    if dark {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
+
+/// Check whether macOS is set to the dark appearance
+#[cfg(target_os = "macos")]
+fn system_prefers_dark() -> bool {
+    // The key only exists while dark mode is on, so reading it fails in light mode
+    match std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleInterfaceStyle"])
+        .output()
+    {
+        Ok(output) => output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "Dark",
+        Err(e) => {
+            debug!("Failed to read the system appearance: {}", e);
+            false
+        }
+    }
+}
+
+/// Check whether the OS is set to a dark appearance, which is only detected on macOS
+#[cfg(not(target_os = "macos"))]
+fn system_prefers_dark() -> bool {
+    debug!("System appearance isn't detected on this platform, using the light theme");
+    false
+}
+
+/// Parse a `#RRGGBB` or `#RRGGBBAA` hex string into a color
+pub fn parse_hex_color(hex: &str) -> Option<Color> {
+    let digits = hex.trim().strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).ok();
+    match digits.len() {
+        6 => Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?)),
+        8 => Some(Color::from_rgba8(
+            channel(0)?,
+            channel(2)?,
+            channel(4)?,
+            channel(6)? as f32 / 255.0,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_hex_colors() {
+        assert_eq!(parse_hex_color("#0066CC"), Some(Color::from_rgb8(0x00, 0x66, 0xCC)));
+        assert_eq!(parse_hex_color("#ffffff"), Some(Color::WHITE));
+        assert_eq!(
+            parse_hex_color("#FF000080"),
+            Some(Color::from_rgba8(0xFF, 0x00, 0x00, 128.0 / 255.0))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_hex_colors() {
+        assert_eq!(parse_hex_color("0066CC"), None);
+        assert_eq!(parse_hex_color("#0066C"), None);
+        assert_eq!(parse_hex_color("#GG66CC"), None);
+        assert_eq!(parse_hex_color("#+066CC"), None);
+        assert_eq!(parse_hex_color("#0066CC0"), None);
+        assert_eq!(parse_hex_color(""), None);
+    }
+
+    #[test]
+    fn test_invalid_scheme_falls_back_to_defaults() {
+        let scheme = ColorScheme {
+            user_background: "not a color".to_string(),
+            assistant_text: "#123456".to_string(),
+            ..ColorScheme::default()
+        };
+
+        let palette = Palette::from_scheme(&scheme, &Theme::Light);
+        assert_eq!(palette.user_background, Palette::default().user_background);
+        assert_eq!(palette.assistant_text, Color::from_rgb8(0x12, 0x34, 0x56));
+    }
+
+    #[test]
+    fn test_dark_theme_replaces_default_colors_only() {
+        let scheme = ColorScheme {
+            assistant_text: "#123456".to_string(),
+            ..ColorScheme::default()
+        };
+
+        let palette = Palette::from_scheme(&scheme, &Theme::Dark);
+        let dark = ColorScheme::dark();
+        assert_eq!(Some(palette.background), parse_hex_color(&dark.background));
+        assert_eq!(Some(palette.assistant_background), parse_hex_color(&dark.assistant_background));
+        assert_eq!(palette.assistant_text, Color::from_rgb8(0x12, 0x34, 0x56));
+        assert_ne!(palette.background, Palette::default().background);
+    }
+
+    #[test]
+    fn test_resolve_theme() {
+        assert_eq!(resolve_theme(ThemeMode::Light), Theme::Light);
+        assert_eq!(resolve_theme(ThemeMode::Dark), Theme::Dark);
+        assert!(is_dark(&resolve_theme(ThemeMode::Dark)));
+    }
+}
@@ -6,7 +6,8 @@ use iced::{
 use iced::window::Position;
 
 use crate::app::Message;
-use crate::config::{Config, save_config};
+use crate::config::{Config, ThemeMode, WindowLevel};
+use crate::data::storage::Storage;
 
 /// Window state
 #[derive(Debug)]
@@ -25,6 +26,10 @@ pub struct Window {
     opacity: f32,
     /// Whether the window is always on top
     always_on_top: bool,
+    /// Stacking level of the window relative to other apps
+    level: WindowLevel,
+    /// Level restored when always-on-top is toggled off
+    level_before_on_top: WindowLevel,
     /// Whether the window is being dragged
     dragging: bool,
     /// The position where the drag started
@@ -78,6 +83,11 @@ impl Window {
             min_size,
             opacity,
             always_on_top,
+            level: config.window.level,
+            level_before_on_top: match config.window.level {
+                WindowLevel::AlwaysOnTop => WindowLevel::Normal,
+                level => level,
+            },
             dragging: false,
             drag_start: None,
             window_start_pos: None,
@@ -123,6 +133,11 @@ impl Window {
         self.always_on_top
     }
 
+    /// Get the stacking level of the window, taking `always_on_top` into account
+    pub fn level(&self) -> WindowLevel {
+        self.level.with_always_on_top(self.always_on_top)
+    }
+
     /// Set the window position
     pub fn set_position(&mut self, position: Position) {
         self.position = position;
@@ -143,6 +158,19 @@ impl Window {
         self.always_on_top = always_on_top;
     }
 
+    /// Switch between keeping the window above others and the level it had before, returning the new level
+    pub fn toggle_always_on_top(&mut self) -> WindowLevel {
+        if self.level() == WindowLevel::AlwaysOnTop {
+            self.always_on_top = false;
+            self.level = self.level_before_on_top;
+        } else {
+            self.level_before_on_top = self.level;
+            self.always_on_top = true;
+            self.level = WindowLevel::AlwaysOnTop;
+        }
+        self.level
+    }
+
     /// Handle a mouse press event
     pub fn handle_mouse_press(&mut self, position: Point) -> Option<Message> {
         // Check if we're in a resize area
@@ -277,7 +305,15 @@ impl Window {
     }
 
     /// Save the window position and size to configuration
-    pub fn save_to_config(&self, config: &mut Config) -> Result<()> {
+    pub fn save_to_config(&self, config: &mut Config, storage: &Storage) -> Result<()> {
+        self.apply_to_config(config);
+        storage.save_config(config)?;
+        
+        Ok(())
+    }
+
+    /// Copy the window position and size into the configuration without saving it
+    pub fn apply_to_config(&self, config: &mut Config) {
         match self.position {
             Position::Specific(x, y) => {
                 config.window.position_x = Some(x);
@@ -293,10 +329,7 @@ impl Window {
         config.window.height = self.size.height as u32;
         config.window.opacity = self.opacity;
         config.window.always_on_top = self.always_on_top;
-        
-        save_config(config, None)?;
-        
-        Ok(())
+        config.window.level = self.level;
     }
 
     /// Create a subscription for window events
@@ -312,6 +345,15 @@ impl Window {
                 Event::Window(window::Event::CloseRequested { .. }) => {
                     Some(Message::Close)
                 }
+                Event::Window(window::Event::Focused) => {
+                    Some(Message::FocusChanged(true))
+                }
+                Event::Window(window::Event::Unfocused) => {
+                    Some(Message::FocusChanged(false))
+                }
+                Event::Window(window::Event::FileDropped(path)) => {
+                    Some(Message::AttachImage(path))
+                }
                 Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
                     Some(Message::MouseDown)
                 }
@@ -321,8 +363,11 @@ impl Window {
                 Event::Mouse(mouse::Event::CursorMoved { position }) => {
                     Some(Message::MouseMoved(position))
                 }
-                Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => {
-                    if key_code == keyboard::KeyCode::Escape {
+                Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) => {
+                    // Cmd+Q quits through the same path as the close button so state is saved
+                    if key_code == keyboard::KeyCode::Escape
+                        || (key_code == keyboard::KeyCode::Q && modifiers.command())
+                    {
                         Some(Message::Close)
                     } else {
                         None
@@ -334,6 +379,16 @@ impl Window {
     }
 }
 
+/// Get the iced window level for a configured level, given whether the app is frontmost
+pub fn level_for(level: WindowLevel, focused: bool) -> window::Level {
+    match level {
+        WindowLevel::Normal => window::Level::Normal,
+        WindowLevel::AlwaysOnTop => window::Level::AlwaysOnTop,
+        WindowLevel::FloatingWhenFocused if focused => window::Level::AlwaysOnTop,
+        WindowLevel::FloatingWhenFocused => window::Level::Normal,
+    }
+}
+
 /// Create window settings from configuration
 pub fn create_window_settings(config: &Config) -> window::Settings {
     window::Settings {
@@ -347,32 +402,90 @@ pub fn create_window_settings(config: &Config) -> window::Settings {
         resizable: true,
         decorations: false, // No window decorations for floating effect
         transparent: true,  // Transparent background
-        // Note: always_on_top is not directly supported in iced::window::Settings
-        // We'll need to implement this differently later
+        // The window opens focused, so a floating window starts above others.
+        // Platforms without window levels, such as Wayland, ignore this.
+        level: level_for(config.window.level.with_always_on_top(config.window.always_on_top), true),
         icon: None,
         ..Default::default()
     }
 }
 
 /// Build the title bar for the application window
-pub fn title_bar<'a>(window: &Window) -> iced::Element<'a, crate::app::Message> {
+///
+/// `show_save` adds a button for saving the conversation, for when auto-save is off.
+pub fn title_bar<'a>(
+    window: &Window,
+    show_save: bool,
+    theme_mode: ThemeMode,
+) -> iced::Element<'a, crate::app::Message> {
     use iced::widget::{button, container, row, text};
     use iced::{Alignment, Length};
     
     let title = text(window.title())
         .size(20);
     
+    let sidebar_button = button(text("☰").size(16))
+        .on_press(crate::app::Message::ToggleSidebar)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    let clear_button = button(text("Clear").size(14))
+        .on_press(crate::app::Message::RequestClearConversation)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    let copy_button = button(text("Copy all").size(14))
+        .on_press(crate::app::Message::CopyConversation)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    let outline_button = button(text("§").size(16))
+        .on_press(crate::app::Message::ToggleOutline)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    let issues_button = button(text("⚠").size(16))
+        .on_press(crate::app::Message::ToggleIssues)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    let info_button = button(text("ⓘ").size(16))
+        .on_press(crate::app::Message::ToggleConversationInfo)
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
+    // Step through light, dark and system themes
+    let theme_button = button(text("◐").size(16))
+        .on_press(crate::app::Message::SetTheme(theme_mode.next()))
+        .style(iced::theme::Button::Text)
+        .padding(5);
+    
     let close_button = button(text("×").size(20))
         .on_press(crate::app::Message::Close)
         .padding(5);
     
-    let row_content = row![
+    let mut row_content = row![
+        sidebar_button,
         title,
         iced::widget::Space::with_width(Length::Fill),
-        close_button
     ]
     .spacing(10)
     .align_items(Alignment::Center);
+    if show_save {
+        let save_button = button(text("Save").size(14))
+            .on_press(crate::app::Message::SaveConversation)
+            .style(iced::theme::Button::Text)
+            .padding(5);
+        row_content = row_content.push(save_button);
+    }
+    let row_content = row_content
+        .push(clear_button)
+        .push(copy_button)
+        .push(outline_button)
+        .push(issues_button)
+        .push(info_button)
+        .push(theme_button)
+        .push(close_button);
     
     // Use a button as the container to make it interactive for dragging
     iced::widget::button(
@@ -411,3 +524,25 @@ pub fn chat_input<'a>(message: &str) -> iced::Element<'a, crate::app::Message> {
     .width(Length::Fill)
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggling_always_on_top_off_restores_the_previous_level() {
+        let mut config = Config::default();
+        config.window.level = WindowLevel::FloatingWhenFocused;
+        let mut window = Window::new(&config);
+
+        assert_eq!(window.toggle_always_on_top(), WindowLevel::AlwaysOnTop);
+        assert!(window.always_on_top());
+        assert_eq!(window.toggle_always_on_top(), WindowLevel::FloatingWhenFocused);
+        assert!(!window.always_on_top());
+
+        // A window saved on top goes back to stacking normally
+        config.window.level = WindowLevel::AlwaysOnTop;
+        let mut window = Window::new(&config);
+        assert_eq!(window.toggle_always_on_top(), WindowLevel::Normal);
+    }
+}
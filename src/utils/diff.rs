@@ -0,0 +1,92 @@
+use similar::{ChangeTag, TextDiff};
+
+/// How a line differs between two versions of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Present in both versions
+    Unchanged,
+    /// Only in the new version
+    Added,
+    /// Only in the old version
+    Removed,
+}
+
+/// A run of consecutive lines that changed in the same way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSegment {
+    /// How the lines changed
+    pub kind: DiffKind,
+    /// The lines, without their final line break
+    pub text: String,
+}
+
+/// Compare two texts line by line, grouping consecutive lines of the same kind
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffSegment> {
+    // A last line without a line break would otherwise differ from the same line followed by more
+    let terminated = |text: &str| {
+        if text.is_empty() || text.ends_with('\n') {
+            text.to_string()
+        } else {
+            format!("{}\n", text)
+        }
+    };
+    let (old, new) = (terminated(old), terminated(new));
+    let diff = TextDiff::from_lines(&old, &new);
+    let mut segments: Vec<DiffSegment> = Vec::new();
+
+    for change in diff.iter_all_changes() {
+        let kind = match change.tag() {
+            ChangeTag::Equal => DiffKind::Unchanged,
+            ChangeTag::Insert => DiffKind::Added,
+            ChangeTag::Delete => DiffKind::Removed,
+        };
+        let line = change.value().strip_suffix('\n').unwrap_or(change.value());
+        match segments.last_mut() {
+            Some(segment) if segment.kind == kind => {
+                segment.text.push('\n');
+                segment.text.push_str(line);
+            }
+            _ => segments.push(DiffSegment {
+                kind,
+                text: line.to_string(),
+            }),
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(kind: DiffKind, text: &str) -> DiffSegment {
+        DiffSegment {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff_lines_groups_changes() {
+        let old = "Rust is fast.\nIt has a borrow checker.\nUse cargo to build.";
+        let new = "Rust is fast.\nIt is memory safe.\nIt has no GC.\nUse cargo to build.\nHave fun!";
+
+        assert_eq!(
+            diff_lines(old, new),
+            [
+                segment(DiffKind::Unchanged, "Rust is fast."),
+                segment(DiffKind::Removed, "It has a borrow checker."),
+                segment(DiffKind::Added, "It is memory safe.\nIt has no GC."),
+                segment(DiffKind::Unchanged, "Use cargo to build."),
+                segment(DiffKind::Added, "Have fun!"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_of_identical_texts() {
+        assert_eq!(diff_lines("Same\ntext", "Same\ntext"), [segment(DiffKind::Unchanged, "Same\ntext")]);
+        assert!(diff_lines("", "").is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+/// Format a duration given in nanoseconds as milliseconds or seconds
+pub fn format_duration_ns(nanos: u64) -> String {
+    let millis = nanos as f64 / 1_000_000.0;
+    if millis < 1000.0 {
+        format!("{:.0} ms", millis)
+    } else {
+        format!("{:.2} s", millis / 1000.0)
+    }
+}
+
+/// Format a count with comma thousands separators
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|group| std::str::from_utf8(group).expect("digits are ASCII"))
+        .collect();
+    groups.join(",")
+}
+
+/// Format a size in bytes with a decimal unit, like `4.7 GB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration_ns() {
+        assert_eq!(format_duration_ns(0), "0 ms");
+        assert_eq!(format_duration_ns(850_000_000), "850 ms");
+        assert_eq!(format_duration_ns(1_250_000_000), "1.25 s");
+        assert_eq!(format_duration_ns(61_000_000_000), "61.00 s");
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1000), "1,000");
+        assert_eq!(format_count(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1500), "1.5 KB");
+        assert_eq!(format_bytes(5_137_025_024), "5.1 GB");
+        assert_eq!(format_bytes(2_000_000_000_000_000), "2000.0 TB");
+    }
+}
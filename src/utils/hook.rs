@@ -0,0 +1,70 @@
+use anyhow::{bail, Context, Result};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Run a shell hook with `input` on its stdin, returning what it writes to stdout
+///
+/// The command runs through `sh -c` with the user's own permissions and is
+/// killed if it doesn't finish within `timeout`. A hook that exits with an
+/// error or prints nothing but whitespace counts as failed.
+pub async fn run_hook(command: &str, input: &str, timeout: Duration) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start hook: {}", command))?;
+
+    let mut stdin = child.stdin.take().context("Hook has no stdin")?;
+    let input = input.to_string();
+    // Write on a separate task so a hook that prints before reading all of its input can't deadlock
+    let writer = tokio::spawn(async move {
+        // A hook that ignores its input may exit before reading it, which isn't an error
+        let _ = stdin.write_all(input.as_bytes()).await;
+    });
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .with_context(|| format!("Hook timed out after {}s: {}", timeout.as_secs_f32(), command))?
+        .with_context(|| format!("Hook failed: {}", command))?;
+    let _ = writer.await;
+
+    if !output.status.success() {
+        bail!(
+            "Hook exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8(output.stdout).context("Hook output is not valid UTF-8")?;
+    if stdout.trim().is_empty() {
+        bail!("Hook printed nothing: {}", command);
+    }
+    Ok(stdout.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hook_transforms_input() {
+        let output = run_hook("tr a-z A-Z", "Hello, world\n", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(output, "HELLO, WORLD");
+    }
+
+    #[tokio::test]
+    async fn test_failing_or_slow_hooks_are_errors() {
+        let timeout = Duration::from_secs(5);
+        assert!(run_hook("exit 3", "Hello", timeout).await.is_err());
+        assert!(run_hook("cat > /dev/null", "Hello", timeout).await.is_err());
+
+        let error = run_hook("sleep 5", "Hello", Duration::from_millis(100)).await.unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+    }
+}
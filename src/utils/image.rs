@@ -0,0 +1,117 @@
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use std::path::Path;
+
+/// Decode base64 image data, as sent in the `images` of a response, into the image's bytes
+///
+/// A `data:` URL prefix and line breaks in the data are allowed.
+pub fn decode_base64_image(data: &str) -> Result<Vec<u8>> {
+    let data = match data.split_once(";base64,") {
+        Some((prefix, encoded)) if prefix.starts_with("data:") => encoded,
+        _ => data,
+    };
+    let encoded: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    if encoded.is_empty() {
+        bail!("Image data is empty");
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("Image data is not valid base64")
+}
+
+/// Get the file extension for image bytes from their signature, or None if the format isn't recognized
+pub fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+/// Largest image file that can be attached to a message
+pub const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Read an image file to send to a multimodal model
+///
+/// Fails if the file is larger than [`MAX_IMAGE_BYTES`] or isn't a PNG, JPEG, GIF or WebP image.
+pub async fn read_image_file(path: &Path) -> Result<Vec<u8>> {
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("Failed to read image: {}", path.display()))?;
+    if metadata.len() > MAX_IMAGE_BYTES {
+        bail!(
+            "Image is larger than {} MB: {}",
+            MAX_IMAGE_BYTES / (1024 * 1024),
+            path.display()
+        );
+    }
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read image: {}", path.display()))?;
+    if image_extension(&bytes).is_none() {
+        bail!("Not a PNG, JPEG, GIF or WebP image: {}", path.display());
+    }
+    Ok(bytes)
+}
+
+/// Base64-encode image bytes as the `images` of a request expect
+pub fn encode_image(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signature of a PNG file, base64-encoded
+    const PNG_SIGNATURE: &str = "iVBORw0KGgo=";
+
+    #[test]
+    fn test_decode_base64_image() {
+        let bytes = decode_base64_image(PNG_SIGNATURE).unwrap();
+        assert_eq!(bytes, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(image_extension(&bytes), Some("png"));
+
+        // Data URLs and wrapped lines decode to the same bytes
+        let data_url = format!("data:image/png;base64,{}", PNG_SIGNATURE);
+        assert_eq!(decode_base64_image(&data_url).unwrap(), bytes);
+        assert_eq!(decode_base64_image("iVBORw0K\nGgo=\n").unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_decode_invalid_image_data_fails() {
+        assert!(decode_base64_image("not base64!").is_err());
+        assert!(decode_base64_image("  \n").is_err());
+        assert_eq!(image_extension(b"plain text"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_image_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("screenshot.png");
+        std::fs::write(&image, b"\x89PNG\r\n\x1a\n").unwrap();
+        assert_eq!(encode_image(&read_image_file(&image).await.unwrap()), PNG_SIGNATURE);
+
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "plain text").unwrap();
+        assert!(read_image_file(&notes).await.is_err());
+        assert!(read_image_file(&dir.path().join("missing.png")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_images_over_the_size_limit_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("huge.png");
+        let file = std::fs::File::create(&image).unwrap();
+        file.set_len(MAX_IMAGE_BYTES + 1).unwrap();
+
+        let error = read_image_file(&image).await.unwrap_err();
+        assert!(error.to_string().contains("larger than 20 MB"));
+    }
+}
@@ -0,0 +1,316 @@
+/// Deepest Markdown heading level
+const MAX_HEADING_DEPTH: usize = 6;
+
+/// How content that may still be growing is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// The content is still streaming, so a code fence that isn't closed yet is literal text
+    Streaming,
+    /// The content is complete, so an unclosed code fence runs to the end
+    Complete,
+}
+
+/// A run of text within a paragraph or heading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    /// Text shown as written
+    Text(String),
+    /// Text between `**` or `__`
+    Strong(String),
+    /// Text between `*` or `_`
+    Emphasis(String),
+    /// Text between backticks
+    Code(String),
+}
+
+impl Inline {
+    /// Get the text without Markdown syntax
+    pub fn text(&self) -> &str {
+        match self {
+            Inline::Text(text) | Inline::Strong(text) | Inline::Emphasis(text) | Inline::Code(text) => text,
+        }
+    }
+}
+
+/// A block of Markdown content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// An ATX heading
+    Heading { depth: usize, content: Vec<Inline> },
+    /// Consecutive lines of text
+    Paragraph(Vec<Inline>),
+    /// A fenced code block
+    CodeBlock { language: String, code: String },
+}
+
+/// Get the level and text of an ATX heading line, or None if the line isn't a heading
+pub fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    // Up to three spaces of indentation are allowed before the hashes
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+    let depth = line.len() - line.trim_start_matches('#').len();
+    if depth == 0 || depth > MAX_HEADING_DEPTH {
+        return None;
+    }
+
+    // The hashes must be followed by whitespace, so `#hashtag` isn't a heading
+    let rest = &line[depth..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // A closing sequence of hashes is not part of the heading
+    let title = rest.trim();
+    let without_closing = title.trim_end_matches('#');
+    let title = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        title
+    };
+    Some((depth, title))
+}
+
+/// Split text into inline runs, keeping markers literal unless they are closed
+///
+/// Each construct is only formatted once its closing marker has arrived, so
+/// appending text never changes how earlier text is formatted.
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut runs = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let marker = match c {
+            '`' => "`",
+            '*' if rest.starts_with("**") => "**",
+            '_' if rest.starts_with("__") => "__",
+            '*' => "*",
+            '_' => "_",
+            _ => "",
+        };
+        // Underscores inside words, as in snake_case, are literal
+        let inside_word = c == '_' && literal.chars().last().is_some_and(char::is_alphanumeric);
+
+        let closed = (!marker.is_empty() && !inside_word)
+            .then(|| find_closer(&rest[marker.len()..], marker))
+            .flatten();
+        match closed {
+            Some(end) => {
+                if !literal.is_empty() {
+                    runs.push(Inline::Text(std::mem::take(&mut literal)));
+                }
+                let inner = rest[marker.len()..marker.len() + end].to_string();
+                runs.push(match marker {
+                    "`" => Inline::Code(inner),
+                    "**" | "__" => Inline::Strong(inner),
+                    _ => Inline::Emphasis(inner),
+                });
+                rest = &rest[2 * marker.len() + end..];
+            }
+            None => {
+                // Keep a whole marker together so `**` isn't read as two `*`
+                let len = marker.len().max(c.len_utf8());
+                literal.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+    }
+
+    if !literal.is_empty() {
+        runs.push(Inline::Text(literal));
+    }
+    runs
+}
+
+/// Find where the closing `marker` starts in `text`, or None if the construct isn't closed
+fn find_closer(text: &str, marker: &str) -> Option<usize> {
+    // Constructs don't span blank lines, and empty ones are literal
+    let end = text.find(marker)?;
+    if end == 0 || text[..end].contains("\n\n") {
+        return None;
+    }
+    // A single `*` closer must not be the start of a `**`
+    if marker.len() == 1 && marker != "`" && text[end + 1..].starts_with(marker) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Parse Markdown into blocks
+///
+/// While streaming, a code fence stays literal text until it is closed, so the
+/// layout doesn't switch between text and code as the response arrives.
+pub fn parse_markdown(content: &str, mode: ParseMode) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut fence: Option<(&str, Vec<&str>)> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join("\n"))));
+            paragraph.clear();
+        }
+    };
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some((opening, code)) = &mut fence {
+            if trimmed.starts_with("```") {
+                blocks.push(Block::CodeBlock {
+                    language: opening.trim_start().trim_start_matches('`').trim().to_string(),
+                    code: code.join("\n"),
+                });
+                fence = None;
+            } else {
+                code.push(line);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            fence = Some((line, Vec::new()));
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if let Some((depth, title)) = parse_heading(line) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading {
+                depth,
+                content: parse_inline(title),
+            });
+        } else {
+            paragraph.push(line);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    if let Some((opening, code)) = fence {
+        blocks.push(match mode {
+            ParseMode::Complete => Block::CodeBlock {
+                language: opening.trim_start().trim_start_matches('`').trim().to_string(),
+                code: code.join("\n"),
+            },
+            // Shown as written until the closing fence arrives
+            ParseMode::Streaming => {
+                let mut text = opening.to_string();
+                for line in code {
+                    text.push('\n');
+                    text.push_str(line);
+                }
+                Block::Paragraph(vec![Inline::Text(text)])
+            }
+        });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the formatted constructs of parsed blocks, in order
+    fn committed(blocks: &[Block]) -> Vec<Block> {
+        let formatted = |runs: &[Inline]| -> Vec<Inline> {
+            runs.iter().filter(|run| !matches!(run, Inline::Text(_))).cloned().collect()
+        };
+        blocks
+            .iter()
+            .flat_map(|block| match block {
+                Block::CodeBlock { .. } => vec![block.clone()],
+                Block::Paragraph(runs) | Block::Heading { content: runs, .. } => {
+                    formatted(runs).into_iter().map(|run| Block::Paragraph(vec![run])).collect()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_inline() {
+        assert_eq!(
+            parse_inline("Use **cargo** with `--release`, *not* snake_case_names"),
+            [
+                Inline::Text("Use ".to_string()),
+                Inline::Strong("cargo".to_string()),
+                Inline::Text(" with ".to_string()),
+                Inline::Code("--release".to_string()),
+                Inline::Text(", ".to_string()),
+                Inline::Emphasis("not".to_string()),
+                Inline::Text(" snake_case_names".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dangling_markers_stay_literal() {
+        assert_eq!(parse_inline("a **bold"), [Inline::Text("a **bold".to_string())]);
+        assert_eq!(parse_inline("a **bold*"), [Inline::Text("a **bold*".to_string())]);
+        assert_eq!(parse_inline("`code"), [Inline::Text("`code".to_string())]);
+        assert_eq!(parse_inline("2 * 3 = 6"), [Inline::Text("2 * 3 = 6".to_string())]);
+    }
+
+    #[test]
+    fn test_unclosed_fence_depends_on_mode() {
+        let content = "Example:\n```rust\nfn main() {}";
+        assert_eq!(
+            parse_markdown(content, ParseMode::Streaming),
+            [
+                Block::Paragraph(vec![Inline::Text("Example:".to_string())]),
+                Block::Paragraph(vec![Inline::Text("```rust\nfn main() {}".to_string())]),
+            ]
+        );
+        assert_eq!(
+            parse_markdown(content, ParseMode::Complete)[1],
+            Block::CodeBlock {
+                language: "rust".to_string(),
+                code: "fn main() {}".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_keep_indentation_and_blank_lines() {
+        let content = "```python\ndef greet(name):\n    if name:\n\n        print(name)\n```";
+        assert_eq!(
+            parse_markdown(content, ParseMode::Complete),
+            [Block::CodeBlock {
+                language: "python".to_string(),
+                code: "def greet(name):\n    if name:\n\n        print(name)".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_streaming_renders_are_stable_as_content_grows() {
+        let content = concat!(
+            "## Building **fast**\n\n",
+            "Run `cargo build` with *care*, then:\n\n",
+            "```bash\ncargo run --release\n```\n\n",
+            "That's **all**.",
+        );
+
+        // Formatting only ever gets added at the end, never taken back or changed
+        let mut previous: Vec<Block> = Vec::new();
+        for (end, _) in content.char_indices().skip(1).chain([(content.len(), ' ')]) {
+            let current = committed(&parse_markdown(&content[..end], ParseMode::Streaming));
+            assert!(
+                current.starts_with(&previous),
+                "formatting changed at {:?}: {:?} -> {:?}",
+                &content[..end],
+                previous,
+                current
+            );
+            previous = current;
+        }
+
+        // Once everything is closed, streaming and full parsing agree
+        assert_eq!(
+            parse_markdown(content, ParseMode::Streaming),
+            parse_markdown(content, ParseMode::Complete)
+        );
+        assert_eq!(previous.len(), 5);
+    }
+}
@@ -1 +1,14 @@
+pub mod diff;
+pub mod format;
+pub mod hook;
+pub mod image;
 pub mod logger;
+pub mod markdown;
+pub mod redact;
+pub mod speech;
+pub mod template;
+pub mod text;
+
+pub use text::{
+    extract_and_prettify_json, extract_single_code_block, grapheme_count, grapheme_truncate, word_count,
+};
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::data::trace::REDACTED;
+
+/// Replaces text matching configured patterns before it leaves the app
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    /// Compiled redact patterns
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Compile the given patterns, failing on the first invalid one
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).with_context(|| format!("Invalid redact pattern: {}", pattern)))
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Redact every match in `text`, returning None if nothing matched
+    pub fn redact(&self, text: &str) -> Option<String> {
+        let mut redacted = text.to_string();
+        for pattern in &self.patterns {
+            if pattern.is_match(&redacted) {
+                redacted = pattern.replace_all(&redacted, REDACTED).into_owned();
+            }
+        }
+        (redacted != text).then_some(redacted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_matches() {
+        let redactor = Redactor::new(&[r"sk-[A-Za-z0-9]{8,}".to_string(), r"ghp_\w+".to_string()]).unwrap();
+        assert_eq!(
+            redactor.redact("keys sk-abcdef123456 and ghp_token42").as_deref(),
+            Some("keys [REDACTED] and [REDACTED]")
+        );
+    }
+
+    #[test]
+    fn test_redact_without_matches() {
+        let redactor = Redactor::new(&[r"sk-[A-Za-z0-9]{8,}".to_string()]).unwrap();
+        assert_eq!(redactor.redact("nothing secret here"), None);
+        assert_eq!(Redactor::default().redact("sk-abcdef123456"), None);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        assert!(Redactor::new(&["(unclosed".to_string()]).is_err());
+    }
+}
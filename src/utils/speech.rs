@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use log::{debug, info};
+use std::env;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+/// Text-to-speech commands to try, in order of preference
+#[cfg(target_os = "macos")]
+const TTS_COMMANDS: &[&str] = &["say"];
+#[cfg(not(target_os = "macos"))]
+const TTS_COMMANDS: &[&str] = &["espeak-ng", "espeak"];
+
+/// Speaks text aloud using the OS text-to-speech command
+#[derive(Debug, Clone)]
+pub struct Speaker {
+    /// Path to the text-to-speech executable
+    command: PathBuf,
+    /// Signal used to stop the current utterance
+    stop: Arc<Notify>,
+}
+
+impl Speaker {
+    /// Find an available text-to-speech command, if any
+    pub fn detect() -> Option<Self> {
+        let command = TTS_COMMANDS.iter().find_map(|name| find_in_path(name));
+        match &command {
+            Some(path) => info!("Using text-to-speech command {}", path.display()),
+            None => info!("No text-to-speech command available, disabling read aloud"),
+        }
+        command.map(|command| Self {
+            command,
+            stop: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Speak the given text, returning when it finishes or is stopped
+    pub async fn speak(&self, text: &str) -> Result<()> {
+        let mut child = Command::new(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.command.display()))?;
+
+        // Both `say` and `espeak` read the text from stdin when no argument is given
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .await
+                .context("Failed to send text to speech command")?;
+        }
+
+        tokio::select! {
+            status = child.wait() => {
+                status.context("Speech command failed")?;
+            }
+            _ = self.stop.notified() => {
+                debug!("Stopping speech");
+                child.kill().await.context("Failed to stop speech command")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop any utterance currently in progress
+    pub fn stop(&self) {
+        self.stop.notify_waiters();
+    }
+}
+
+/// Look up an executable by name in the directories listed in PATH
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
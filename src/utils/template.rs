@@ -0,0 +1,120 @@
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+/// Variable replaced with the typed message
+pub const INPUT_VARIABLE: &str = "input";
+
+/// A piece of a prompt template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Text used as written
+    Literal(String),
+    /// A `{name}` placeholder
+    Variable(String),
+}
+
+/// Split a template body into literal text and `{name}` placeholders
+///
+/// `{{` and `}}` stand for literal braces.
+fn parse(body: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_alphanumeric() || c == '_' => name.push(c),
+                        Some(c) => bail!("Invalid character '{}' in template variable {{{}", c, name),
+                        None => bail!("Unclosed template variable {{{}", name),
+                    }
+                }
+                if name.is_empty() {
+                    bail!("Empty template variable {{}}; write {{{{ for a literal brace");
+                }
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                segments.push(Segment::Variable(name));
+            }
+            '}' => bail!("Unmatched '}}' in template; write }}}} for a literal brace"),
+            c => literal.push(c),
+        }
+    }
+    segments.push(Segment::Literal(literal));
+    Ok(segments)
+}
+
+/// Get the names of the variables in a template, other than `{input}`, in order of first use
+pub fn template_variables(body: &str) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for segment in parse(body)? {
+        if let Segment::Variable(name) = segment {
+            if name != INPUT_VARIABLE && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Fill in a template's `{name}` placeholders from `vars`
+///
+/// Fails if the template is malformed or uses a variable that has no value.
+pub fn apply_template(body: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut output = String::new();
+    for segment in parse(body)? {
+        match segment {
+            Segment::Literal(text) => output.push_str(&text),
+            Segment::Variable(name) => match vars.get(&name) {
+                Some(value) => output.push_str(value),
+                None => bail!("No value for template variable {{{}}}", name),
+            },
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_apply_template_substitutes_variables() {
+        let body = "Translate to {lang}: {input}\n({lang} only)";
+        let filled = apply_template(body, &vars(&[("lang", "French"), ("input", "Good morning")])).unwrap();
+        assert_eq!(filled, "Translate to French: Good morning\n(French only)");
+        assert_eq!(template_variables(body).unwrap(), ["lang"]);
+    }
+
+    #[test]
+    fn test_missing_variable_is_an_error() {
+        let error = apply_template("Translate to {lang}: {input}", &vars(&[("input", "Hi")])).unwrap_err();
+        assert!(error.to_string().contains("{lang}"));
+
+        assert!(apply_template("Unclosed {lang", &vars(&[("lang", "French")])).is_err());
+        assert!(apply_template("Empty {}", &vars(&[])).is_err());
+        assert!(apply_template("Stray } brace", &vars(&[])).is_err());
+    }
+
+    #[test]
+    fn test_doubled_braces_are_literal() {
+        let body = "Return {{\"text\": \"{input}\"}} as JSON";
+        let filled = apply_template(body, &vars(&[("input", "hi")])).unwrap();
+        assert_eq!(filled, "Return {\"text\": \"hi\"} as JSON");
+        assert!(template_variables(body).unwrap().is_empty());
+    }
+}
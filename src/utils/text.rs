@@ -0,0 +1,337 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Convert Markdown content into plain text suitable for speech or previews
+///
+/// Fenced code blocks are dropped entirely, and heading, emphasis, quote,
+/// list and link syntax is removed while keeping the readable text.
+pub fn strip_markdown(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+
+        // Skip fences and everything between them
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        // Remove block-level prefixes
+        let mut line = trimmed.trim_start_matches('#').trim_start();
+        line = line.trim_start_matches('>').trim_start();
+        for bullet in ["- ", "* ", "+ "] {
+            if let Some(rest) = line.strip_prefix(bullet) {
+                line = rest;
+                break;
+            }
+        }
+
+        lines.push(strip_inline_markdown(line));
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+/// Remove inline emphasis, code and link syntax from a single line
+fn strip_inline_markdown(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '_' | '`' => {}
+            '[' => {
+                // Keep the link text and drop the target
+                let text: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                result.push_str(&text);
+                if chars.peek() == Some(&'(') {
+                    chars.by_ref().take_while(|&c| c != ')').for_each(drop);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Get the code of the only fenced code block in `content`
+///
+/// Returns None if the content has no fenced code block, more than one, or
+/// a fence that is never closed.
+pub fn extract_single_code_block(content: &str) -> Option<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(code) => blocks.push(code.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+            continue;
+        }
+        if let Some(code) = &mut current {
+            code.push(line);
+        }
+    }
+
+    if current.is_some() || blocks.len() != 1 {
+        return None;
+    }
+    blocks.pop()
+}
+
+/// Pretty-print a response that is a JSON object or array, alone or as a ```` ```json ```` block
+///
+/// Keys keep their original order. Returns None if the content is anything
+/// else, including invalid JSON, so it can be rendered as normal text.
+pub fn extract_and_prettify_json(content: &str) -> Option<String> {
+    let trimmed = content.trim();
+    let json = match trimmed.strip_prefix("```") {
+        Some(fenced) => {
+            let (info, body) = fenced.split_once('\n')?;
+            if !info.trim().eq_ignore_ascii_case("json") {
+                return None;
+            }
+            body.strip_suffix("```")?
+        }
+        None => trimmed,
+    };
+
+    let json = json.trim();
+    if !(json.starts_with('{') || json.starts_with('[')) {
+        return None;
+    }
+    serde_json::from_str::<serde::de::IgnoredAny>(json).ok()?;
+    Some(reindent_json(json))
+}
+
+/// Lay out valid JSON with one value per line and two-space indentation
+fn reindent_json(json: &str) -> String {
+    const INDENT: &str = "  ";
+
+    // Drop the whitespace between tokens, keeping whitespace inside strings
+    let mut tokens = Vec::with_capacity(json.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for c in json.chars() {
+        if in_string {
+            in_string = c != '"' || escaped;
+            escaped = c == '\\' && !escaped;
+        } else if c == '"' {
+            in_string = true;
+        } else if c.is_whitespace() {
+            continue;
+        }
+        tokens.push((c, in_string || c == '"'));
+    }
+
+    let mut result = String::with_capacity(json.len() * 2);
+    let mut depth = 0;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some((c, quoted)) = tokens.next() {
+        if quoted {
+            result.push(c);
+            continue;
+        }
+        match c {
+            '{' | '[' => {
+                result.push(c);
+                // Empty containers stay on one line
+                if let Some((close @ ('}' | ']'), _)) = tokens.peek().copied() {
+                    tokens.next();
+                    result.push(close);
+                } else {
+                    depth += 1;
+                    result.push('\n');
+                    result.push_str(&INDENT.repeat(depth));
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                result.push('\n');
+                result.push_str(&INDENT.repeat(depth));
+                result.push(c);
+            }
+            ',' => {
+                result.push_str(",\n");
+                result.push_str(&INDENT.repeat(depth));
+            }
+            ':' => result.push_str(": "),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Count the user-perceived characters (grapheme clusters) in a string
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// Count the words in a string, splitting on whitespace
+///
+/// Runs of punctuation on their own, such as a dash between spaces, aren't words.
+pub fn word_count(s: &str) -> usize {
+    s.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Get the longest prefix of `s` with at most `max` grapheme clusters
+///
+/// Unlike truncating by `char`, this never splits an emoji sequence or a
+/// letter from its combining marks.
+pub fn grapheme_truncate(s: &str, max: usize) -> &str {
+    match s.grapheme_indices(true).nth(max) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Cut `s` down to at most `max` chars, returning whether anything was removed
+pub fn truncate_chars(s: &mut String, max: usize) -> bool {
+    match s.char_indices().nth(max) {
+        Some((end, _)) => {
+            s.truncate(end);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars() {
+        let mut text = "héllo wörld".to_string();
+        assert!(truncate_chars(&mut text, 7));
+        assert_eq!(text, "héllo w");
+
+        assert!(!truncate_chars(&mut text, 7));
+        assert_eq!(text, "héllo w");
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let content = "# Title\n\nSome **bold** and _italic_ text with `code`.\n\n```rust\nfn main() {}\n```\n- a [link](https://example.com)\n> quoted";
+        assert_eq!(
+            strip_markdown(content),
+            "Title\n\nSome bold and italic text with code.\n\na link\nquoted"
+        );
+    }
+
+    #[test]
+    fn test_strip_markdown_plain_text() {
+        assert_eq!(strip_markdown("Hello there"), "Hello there");
+        assert_eq!(strip_markdown(""), "");
+    }
+
+    #[test]
+    fn test_extract_single_code_block_without_fences() {
+        assert_eq!(extract_single_code_block("Just some text"), None);
+        assert_eq!(extract_single_code_block("Inline `code` only"), None);
+    }
+
+    #[test]
+    fn test_extract_single_code_block() {
+        let content = "Here you go:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\nRun it with cargo.";
+        assert_eq!(
+            extract_single_code_block(content).as_deref(),
+            Some("fn main() {\n    println!(\"hi\");\n}")
+        );
+    }
+
+    #[test]
+    fn test_extract_single_code_block_with_multiple_fences() {
+        let content = "```\nfirst\n```\nand\n```\nsecond\n```";
+        assert_eq!(extract_single_code_block(content), None);
+    }
+
+    #[test]
+    fn test_extract_single_code_block_unclosed_fence() {
+        assert_eq!(extract_single_code_block("```\nfirst\n```\n```\nunclosed"), None);
+        assert_eq!(extract_single_code_block("```\nunclosed"), None);
+    }
+
+    #[test]
+    fn test_extract_and_prettify_json() {
+        let compact = r#" {"name":"Ada, \"the\" first","tags":["a",{}],"age":36,"ok":null} "#;
+        let expected = concat!(
+            "{\n",
+            "  \"name\": \"Ada, \\\"the\\\" first\",\n",
+            "  \"tags\": [\n    \"a\",\n    {}\n  ],\n",
+            "  \"age\": 36,\n",
+            "  \"ok\": null\n",
+            "}",
+        );
+        assert_eq!(extract_and_prettify_json(compact).as_deref(), Some(expected));
+        assert_eq!(extract_and_prettify_json("[ ]").as_deref(), Some("[]"));
+    }
+
+    #[test]
+    fn test_extract_and_prettify_json_falls_through_on_invalid() {
+        assert_eq!(extract_and_prettify_json(r#"{"name": "Ada",}"#), None);
+        assert_eq!(extract_and_prettify_json(r#"{"name": "Ada"} and more"#), None);
+        assert_eq!(extract_and_prettify_json("Just text"), None);
+        assert_eq!(extract_and_prettify_json("42"), None);
+        assert_eq!(extract_and_prettify_json(""), None);
+    }
+
+    #[test]
+    fn test_extract_and_prettify_json_in_fence() {
+        let fenced = "\n```json\n{\"a\": {\"b\": [1, 2]}}\n```\n";
+        assert_eq!(
+            extract_and_prettify_json(fenced).as_deref(),
+            Some("{\n  \"a\": {\n    \"b\": [\n      1,\n      2\n    ]\n  }\n}")
+        );
+
+        // Other languages and text around the block are rendered as usual
+        assert_eq!(extract_and_prettify_json("```rust\n{\"a\": 1}\n```"), None);
+        assert_eq!(extract_and_prettify_json("Here:\n```json\n{\"a\": 1}\n```"), None);
+        assert_eq!(extract_and_prettify_json("```json\n{\"a\": 1\n```"), None);
+    }
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(word_count("Hello, world! How's it going?"), 5);
+        assert_eq!(word_count("  spaced   out\n\nacross\tlines  "), 4);
+        assert_eq!(word_count("before - after ... done"), 3);
+        assert_eq!(word_count(""), 0);
+        assert_eq!(word_count(" \n "), 0);
+    }
+
+    #[test]
+    fn test_grapheme_truncate_ascii() {
+        assert_eq!(grapheme_truncate("Hello", 3), "Hel");
+        assert_eq!(grapheme_truncate("Hello", 5), "Hello");
+        assert_eq!(grapheme_truncate("Hello", 10), "Hello");
+        assert_eq!(grapheme_truncate("", 3), "");
+    }
+
+    #[test]
+    fn test_grapheme_truncate_keeps_clusters_whole() {
+        // Regional indicator pairs form a single flag
+        let flags = "🇯🇵🇫🇷🇧🇷";
+        assert_eq!(grapheme_count(flags), 3);
+        assert_eq!(grapheme_truncate(flags, 2), "🇯🇵🇫🇷");
+
+        // Emoji with a skin tone modifier and a ZWJ family sequence
+        let emoji = "👍🏽👨‍👩‍👧x";
+        assert_eq!(grapheme_count(emoji), 3);
+        assert_eq!(grapheme_truncate(emoji, 1), "👍🏽");
+        assert_eq!(grapheme_truncate(emoji, 2), "👍🏽👨‍👩‍👧");
+
+        // Letters followed by combining accents
+        let combining = "e\u{301}a\u{308}o";
+        assert_eq!(grapheme_count(combining), 3);
+        assert_eq!(grapheme_truncate(combining, 2), "e\u{301}a\u{308}");
+    }
+}
@@ -1,6 +1,23 @@
 use screensage::App;
 use screensage::Config;
+use screensage::data::storage::Storage;
 use iced::Application;
+use tempfile::TempDir;
+
+/// Create the app with its conversations and configuration in a temporary directory,
+/// so tests never touch the real saved conversations
+fn test_app(config: Config) -> (App, TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let (app, _) = App::with_storage(config, Storage::in_dir(dir.path()));
+    (app, dir)
+}
+
+/// Read an image the way dropping it on the window does, and hand the result to the app
+fn attach_image(app: &mut App, path: std::path::PathBuf) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let result = runtime.block_on(screensage::utils::image::read_image_file(&path));
+    let _ = app.update(screensage::app::Message::ImageRead(path, result.map_err(|e| format!("{:#}", e))));
+}
 
 #[test]
 fn test_app_initialization() {
@@ -8,7 +25,7 @@ fn test_app_initialization() {
     let config = Config::default();
     
     // Initialize the app
-    let (app, _) = App::new(config);
+    let (app, _dir) = test_app(config);
     
     // Verify the app was initialized correctly
     assert_eq!(app.title(), "ScreenSage");
@@ -20,7 +37,7 @@ fn test_message_handling() {
     let config = Config::default();
     
     // Initialize the app
-    let (mut app, _) = App::new(config);
+    let (mut app, _dir) = test_app(config);
     
     // Test input message handling
     let test_message = "Test message";
@@ -39,7 +56,7 @@ fn test_error_handling() {
     let config = Config::default();
     
     // Initialize the app
-    let (mut app, _) = App::new(config);
+    let (mut app, _dir) = test_app(config);
     
     // Test setting an error
     let error_message = "Test error";
@@ -51,3 +68,658 @@ fn test_error_handling() {
     app.set_error(None);
     assert_eq!(app.error(), None);
 }
+
+#[test]
+fn test_dry_run_previews_request_without_sending() {
+    let mut server = mockito::Server::new();
+    let chat = server.mock("POST", "/api/chat").expect(0).create();
+    
+    let mut config = Config::default();
+    config.ollama.api_url = server.url();
+    config.dry_run = true;
+    
+    let (mut app, _dir) = test_app(config);
+    app.update_message("What would you send?".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    
+    // The request is previewed as JSON instead of being sent
+    let preview = app.dry_run_preview().expect("dry run should produce a preview");
+    let request: serde_json::Value = serde_json::from_str(preview).unwrap();
+    let messages = request["messages"].as_array().unwrap();
+    assert_eq!(messages.last().unwrap()["content"], "What would you send?");
+    assert!(!app.is_sending());
+    chat.assert();
+}
+
+#[test]
+fn test_fork_from_message_selects_fork() {
+    let (mut app, dir) = test_app(Config::default());
+    
+    // Start from a fresh conversation with a few messages
+    let mut original = screensage::Conversation::new("Fork Test", "test-model");
+    original.add_message(screensage::MessageRole::User, "First");
+    original.add_message(screensage::MessageRole::Assistant, "Second");
+    original.add_message(screensage::MessageRole::User, "Third");
+    original.save_in(&dir.path().join("conversations")).unwrap();
+    let _ = app.update(screensage::app::Message::SelectConversation(original.id.clone()));
+    assert_eq!(app.conversation().id, original.id);
+    
+    // Forking switches to a new conversation holding the messages up to the fork point
+    let _ = app.update(screensage::app::Message::ForkFromMessage(1));
+    let fork_id = app.conversation().id.clone();
+    assert_ne!(fork_id, original.id);
+    assert_eq!(app.conversation().forked_from.as_deref(), Some(original.id.as_str()));
+    assert_eq!(app.conversation().messages.len(), 2);
+    assert_eq!(app.conversation().messages[1].content, "Second");
+    assert!(app.conversations().iter().any(|conversation| conversation.id == fork_id));
+    
+    // The original remains selectable and unchanged
+    let _ = app.update(screensage::app::Message::SelectConversation(original.id.clone()));
+    assert_eq!(app.conversation().id, original.id);
+    assert_eq!(app.conversation().messages.len(), 3);
+    assert_eq!(app.conversation().messages[2].content, "Third");
+}
+
+#[test]
+fn test_auto_save_writes_dirty_conversation_once() {
+    let (mut app, dir) = test_app(Config::default());
+    
+    let mut conversation = screensage::Conversation::new("Auto Save Test", "test-model");
+    conversation.add_message(screensage::MessageRole::User, "Hello");
+    let path = dir.path().join("conversations").join(format!("{}.json", conversation.id));
+    conversation.save_in(&dir.path().join("conversations")).unwrap();
+    let _ = app.update(screensage::app::Message::SelectConversation(conversation.id.clone()));
+    std::fs::remove_file(&path).unwrap();
+    
+    // Bookmarking marks the conversation dirty without writing it immediately
+    let _ = app.update(screensage::app::Message::ToggleBookmark(0));
+    assert!(!path.exists());
+    
+    // The next tick saves it
+    let _ = app.update(screensage::app::Message::AutoSave);
+    let saved = screensage::Conversation::load(&path).unwrap();
+    assert!(saved.messages[0].bookmarked);
+    
+    // Further ticks without changes do not write again
+    std::fs::remove_file(&path).unwrap();
+    let _ = app.update(screensage::app::Message::AutoSave);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_redacts_secrets_before_sending() {
+    let mut config = Config::default();
+    config.privacy.redact_patterns = vec![r"sk-[A-Za-z0-9]{8,}".to_string()];
+    config.dry_run = true;
+    
+    // The request carries the redacted text
+    let (mut app, _dir) = test_app(config.clone());
+    app.update_message("my key is sk-abcdef123456".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    let preview = app.dry_run_preview().expect("dry run should produce a preview");
+    assert!(preview.contains("my key is [REDACTED]"));
+    assert!(!preview.contains("sk-abcdef123456"));
+    assert!(app.warning().is_some());
+}
+
+#[test]
+fn test_attached_images_are_sent_with_the_next_message() {
+    let dir = tempfile::tempdir().unwrap();
+    let screenshot = dir.path().join("screenshot.png");
+    std::fs::write(&screenshot, b"\x89PNG\r\n\x1a\n").unwrap();
+    let notes = dir.path().join("notes.txt");
+    std::fs::write(&notes, "plain text").unwrap();
+    
+    let mut config = Config::default();
+    config.ollama.default_model = "llava".to_string();
+    config.dry_run = true;
+    let (mut app, _dir) = test_app(config);
+    
+    // Files that aren't images are refused with a warning
+    attach_image(&mut app, notes);
+    assert!(app.pending_images().is_empty());
+    assert!(app.warning().is_some());
+    
+    attach_image(&mut app, screenshot.clone());
+    attach_image(&mut app, screenshot);
+    let _ = app.update(screensage::app::Message::RemovePendingImage(1));
+    assert_eq!(app.pending_images(), ["iVBORw0KGgo="]);
+    
+    app.update_message("What is on my screen?".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    let preview = app.dry_run_preview().expect("dry run should produce a preview");
+    let request: serde_json::Value = serde_json::from_str(preview).unwrap();
+    let message = request["messages"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(message["content"], "What is on my screen?");
+    assert_eq!(message["images"], serde_json::json!(["iVBORw0KGgo="]));
+}
+
+#[test]
+fn test_generated_response_keeps_context_for_the_next_message() {
+    let mut config = Config::default();
+    config.ollama.reuse_context = true;
+    let (mut app, _dir) = test_app(config);
+    app.update_message("Hello".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    
+    let _ = app.update(screensage::app::Message::GeneratedResponse(
+        "llama3.2".to_string(),
+        "Hi there".to_string(),
+        Some(vec![1, 2, 3]),
+    ));
+    let conversation = app.conversation();
+    assert_eq!(conversation.messages.last().unwrap().content, "Hi there");
+    let context = conversation.context.as_ref().unwrap();
+    assert_eq!(context.messages, conversation.messages.len());
+    assert_eq!(context.tokens, [1, 2, 3]);
+}
+
+#[test]
+fn test_redacts_secrets_before_storing() {
+    let mut config = Config::default();
+    config.privacy.redact_patterns = vec![r"sk-[A-Za-z0-9]{8,}".to_string()];
+    
+    let (mut app, _dir) = test_app(config);
+    app.update_message("my key is sk-abcdef123456".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    
+    // The stored conversation never sees the secret
+    let stored = app.conversation().messages.iter()
+        .rev()
+        .find(|message| message.role == screensage::MessageRole::User)
+        .unwrap();
+    assert_eq!(stored.content, "my key is [REDACTED]");
+    assert!(app.warning().is_some());
+    
+    // Messages without matches clear the warning
+    app.update_message("nothing secret".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    assert!(app.warning().is_none());
+}
+
+#[test]
+fn test_input_beyond_limit_is_truncated() {
+    let mut config = Config::default();
+    config.ui.max_input_chars = 5;
+    let (mut app, _dir) = test_app(config);
+    
+    // Multi-byte characters straddle the limit in bytes but not in chars
+    let _ = app.update(screensage::app::Message::InputChanged("ééééééé".to_string()));
+    assert_eq!(app.message(), "ééééé");
+    assert!(app.input_truncated());
+    
+    // Input within the limit clears the warning
+    let _ = app.update(screensage::app::Message::InputChanged("éé".to_string()));
+    assert_eq!(app.message(), "éé");
+    assert!(!app.input_truncated());
+}
+
+#[test]
+fn test_zero_max_input_chars_is_unlimited() {
+    let mut config = Config::default();
+    config.ui.max_input_chars = 0;
+    let (mut app, _dir) = test_app(config);
+    
+    let long_input = "x".repeat(200_000);
+    let _ = app.update(screensage::app::Message::InputChanged(long_input.clone()));
+    assert_eq!(app.message(), long_input);
+    assert!(!app.input_truncated());
+}
+
+#[test]
+fn test_regenerate_more_creative_bumps_temperature_for_one_request() {
+    let mut config = Config::default();
+    config.dry_run = true;
+    config.ollama.temperature = 0.7;
+    config.ollama.temperature_bump = 0.5;
+    
+    let (mut app, _dir) = test_app(config);
+    app.add_message(screensage::MessageRole::User, "Tell me a story");
+    app.add_message(screensage::MessageRole::Assistant, "Once upon a time.");
+    let _ = app.update(screensage::app::Message::RegenerateMoreCreative);
+    
+    // The outgoing request carries the bumped temperature and ends at the prompt
+    let preview = app.dry_run_preview().expect("dry run should produce a preview");
+    let request: serde_json::Value = serde_json::from_str(preview).unwrap();
+    assert!((request["options"]["temperature"].as_f64().unwrap() - 1.2).abs() < 1e-6);
+    assert_eq!(request["messages"].as_array().unwrap().last().unwrap()["content"], "Tell me a story");
+    
+    // The configuration keeps its temperature
+    assert_eq!(app.config().ollama.temperature, 0.7);
+}
+
+#[test]
+fn test_message_selection_toggles_and_clears_on_switch() {
+    let (mut app, _dir) = test_app(Config::default());
+    app.add_message(screensage::MessageRole::User, "First");
+    app.add_message(screensage::MessageRole::Assistant, "Second");
+    app.add_message(screensage::MessageRole::User, "Third");
+    
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(2));
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(0));
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(7));
+    assert_eq!(app.selected_indices(), [0, 2]);
+    
+    // Toggling a selected message deselects it
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(0));
+    assert_eq!(app.selected_indices(), [2]);
+    
+    let _ = app.update(screensage::app::Message::ClearSelection);
+    assert!(app.selected_indices().is_empty());
+}
+
+#[test]
+fn test_cycle_opacity_wraps_and_updates_config() {
+    let mut config = Config::default();
+    config.window.opacity = 0.6;
+    config.window.opacity_presets = vec![0.3, 0.6, 1.0];
+    let (mut app, _dir) = test_app(config);
+    
+    let _ = app.update(screensage::app::Message::CycleOpacity);
+    assert_eq!(app.config().window.opacity, 1.0);
+    let _ = app.update(screensage::app::Message::CycleOpacity);
+    assert_eq!(app.config().window.opacity, 0.3);
+}
+
+#[test]
+fn test_settings_apply_valid_changes_only() {
+    use screensage::ui::settings::Setting;
+    
+    let (mut app, _dir) = test_app(Config::default());
+    let _ = app.update(screensage::app::Message::ToggleSettings);
+    assert!(app.settings().is_some());
+    
+    let _ = app.update(screensage::app::Message::SettingChanged(Setting::Temperature, "1.2".to_string()));
+    assert_eq!(app.config().ollama.temperature, 1.2);
+    assert!(app.settings().unwrap().error.is_none());
+    
+    // An invalid value is explained and the last valid one kept
+    let _ = app.update(screensage::app::Message::SettingChanged(Setting::Temperature, "5".to_string()));
+    assert_eq!(app.config().ollama.temperature, 1.2);
+    assert!(app.settings().unwrap().error.as_deref().unwrap().contains("ollama.temperature"));
+    
+    let _ = app.update(screensage::app::Message::ToggleSettings);
+    assert!(app.settings().is_none());
+}
+
+#[test]
+fn test_settings_list_running_models() {
+    use screensage::ollama::models::RunningModel;
+    
+    // Without a connection there is nothing to list
+    let (mut app, _dir) = test_app(Config::default());
+    let _ = app.update(screensage::app::Message::ToggleSettings);
+    assert!(matches!(app.settings().unwrap().running_models, Some(Err(_))));
+    
+    let model = RunningModel {
+        name: "llama3.2:latest".to_string(),
+        size: 5_137_025_024,
+        size_vram: 5_137_025_024,
+        expires_at: "2024-06-04T14:38:31.83753-07:00".to_string(),
+    };
+    let _ = app.update(screensage::app::Message::RunningModelsListed(Ok(vec![model.clone()])));
+    assert_eq!(app.settings().unwrap().running_models, Some(Ok(vec![model])));
+}
+
+#[test]
+fn test_set_theme_updates_theme_and_config() {
+    let (mut app, _dir) = test_app(Config::default());
+    assert_eq!(app.theme(), iced::Theme::Light);
+    
+    let _ = app.update(screensage::app::Message::SetTheme(screensage::config::ThemeMode::Dark));
+    assert_eq!(app.theme(), iced::Theme::Dark);
+    assert_eq!(app.config().theme.mode, screensage::config::ThemeMode::Dark);
+}
+
+#[test]
+fn test_dry_run_regenerate_keeps_response_without_history() {
+    let mut config = Config::default();
+    config.dry_run = true;
+    let (mut app, _dir) = test_app(config);
+    app.add_message(screensage::MessageRole::User, "Tell me a story");
+    app.add_message(screensage::MessageRole::Assistant, "Once upon a time.");
+    
+    // A dry run only previews the request, so the response isn't replaced or kept as a version
+    let _ = app.update(screensage::app::Message::RegenerateMoreCreative);
+    assert_eq!(app.conversation().messages[1].content, "Once upon a time.");
+    assert!(app.response_history(1).is_empty());
+}
+
+#[test]
+fn test_timers_pause_while_window_is_hidden() {
+    let mut config = Config::default();
+    config.ui.smooth_scroll = true;
+    let (mut app, _dir) = test_app(config);
+    
+    let _ = app.update(screensage::app::Message::ScrollToBottom);
+    assert!(app.timers().contains(&screensage::app::Timer::SmoothScroll));
+    
+    // An unfocused window is still on screen, so it keeps animating
+    let _ = app.update(screensage::app::Message::FocusChanged(false));
+    assert!(app.is_visible());
+    assert!(app.timers().contains(&screensage::app::Timer::SmoothScroll));
+    
+    // Animations stop while the window is hidden or minimized, but auto-saving carries on
+    let _ = app.update(screensage::app::Message::WindowModeFetched(iced::window::Mode::Hidden));
+    assert!(!app.is_visible());
+    assert_eq!(app.timers(), [screensage::app::Timer::AutoSave]);
+    
+    let _ = app.update(screensage::app::Message::WindowModeFetched(iced::window::Mode::Windowed));
+    assert!(app.is_visible());
+    assert!(app.timers().contains(&screensage::app::Timer::SmoothScroll));
+    
+    let _ = app.update(screensage::app::Message::WindowModeFetched(iced::window::Mode::Hidden));
+    let _ = app.update(screensage::app::Message::FocusChanged(true));
+    assert!(app.is_visible());
+}
+
+#[test]
+fn test_missing_model_download_progress() {
+    let mut config = Config::default();
+    config.ollama.default_model = "llama3.2".to_string();
+    let (mut app, _dir) = test_app(config);
+    
+    let _ = app.update(screensage::app::Message::ModelChecked(Ok(false)));
+    assert_eq!(app.pull_status(), Some("Downloading llama3.2..."));
+    
+    // Progress doesn't replace the loading message of a response generated meanwhile
+    app.update_message("Hello".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    let loading = app.loading_state().map(str::to_string);
+    
+    let progress = screensage::ollama::models::PullProgress {
+        status: "pulling dde5aa3fc5ff".to_string(),
+        completed: Some(90),
+        total: Some(200),
+    };
+    let _ = app.update(screensage::app::Message::ModelPullProgress(progress));
+    assert_eq!(app.pull_status(), Some("Downloading llama3.2: 45%"));
+    assert_eq!(app.loading_state().map(str::to_string), loading);
+    
+    let _ = app.update(screensage::app::Message::ModelPullFinished(Err("file does not exist".to_string())));
+    assert_eq!(app.pull_status(), None);
+    assert_eq!(app.loading_state().map(str::to_string), loading);
+    assert!(app.error().unwrap().contains("file does not exist"));
+}
+
+#[test]
+fn test_model_choices_include_configured_model() {
+    let mut config = Config::default();
+    config.ollama.default_model = "my-finetune".to_string();
+    let (mut app, _dir) = test_app(config);
+    
+    let installed = vec!["llama3.2:latest".to_string(), "mistral:latest".to_string()];
+    let _ = app.update(screensage::app::Message::ModelsListed(Ok(installed)));
+    assert_eq!(app.model_choices(), ["my-finetune", "llama3.2:latest", "mistral:latest"]);
+    
+    // A failed listing keeps the models listed before
+    let _ = app.update(screensage::app::Message::ModelsListed(Err("connection refused".to_string())));
+    assert_eq!(app.model_choices().len(), 3);
+}
+
+#[test]
+fn test_new_conversation_uses_default_model() {
+    let mut config = Config::default();
+    config.ollama.default_model = "mistral".to_string();
+    let (mut app, _dir) = test_app(config);
+    app.add_message(screensage::data::conversation::MessageRole::User, "Hello");
+    let previous_id = app.conversation().id.clone();
+    
+    let _ = app.update(screensage::app::Message::NewConversation);
+    assert_ne!(app.conversation().id, previous_id);
+    assert!(app.conversation().is_empty());
+    assert_eq!(app.conversation().model, "mistral");
+}
+
+#[test]
+fn test_deleting_current_conversation_shows_another() {
+    let (mut app, _dir) = test_app(Config::default());
+    
+    // A new conversation has no file yet, so this doesn't touch saved conversations
+    let _ = app.update(screensage::app::Message::NewConversation);
+    let deleted_id = app.conversation().id.clone();
+    
+    // Nothing is deleted until the sidebar's confirmation is accepted
+    let _ = app.update(screensage::app::Message::DeleteConversation(deleted_id.clone()));
+    assert_eq!(app.conversation().id, deleted_id);
+    let _ = app.update(screensage::app::Message::RequestDeleteConversation(deleted_id.clone()));
+    let _ = app.update(screensage::app::Message::CancelDeleteConversation);
+    let _ = app.update(screensage::app::Message::DeleteConversation(deleted_id.clone()));
+    assert_eq!(app.conversation().id, deleted_id);
+    
+    let _ = app.update(screensage::app::Message::RequestDeleteConversation(deleted_id.clone()));
+    let _ = app.update(screensage::app::Message::DeleteConversation(deleted_id.clone()));
+    assert_ne!(app.conversation().id, deleted_id);
+    assert!(app.conversations().iter().all(|conversation| conversation.id != deleted_id));
+    assert_eq!(app.error(), None);
+}
+
+#[test]
+fn test_editing_a_message_drops_the_replies_after_it() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    let (mut app, _dir) = test_app(config);
+    let _ = app.update(screensage::app::Message::NewConversation);
+    app.add_message(MessageRole::User, "Waht is 2+2?");
+    app.add_message(MessageRole::Assistant, "I'm not sure what you mean");
+    app.add_message(MessageRole::User, "Never mind");
+    app.add_message(MessageRole::Assistant, "OK");
+    
+    // Only user messages can be edited
+    let _ = app.update(screensage::app::Message::EditMessage(1));
+    assert_eq!(app.editing(), None);
+    
+    let _ = app.update(screensage::app::Message::EditMessage(0));
+    assert_eq!(app.editing(), Some(0));
+    assert_eq!(app.message(), "Waht is 2+2?");
+    
+    app.update_message("What is 2+2?".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    assert_eq!(app.editing(), None);
+    let messages = &app.conversation().messages;
+    assert_eq!(messages[0].content, "What is 2+2?");
+    assert!(messages.iter().all(|message| message.content != "Never mind"));
+}
+
+#[test]
+fn test_context_warning_above_threshold() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.ollama.context_warning_tokens = 10;
+    let (mut app, _dir) = test_app(config);
+    let _ = app.update(screensage::app::Message::NewConversation);
+    
+    // 40 characters estimate to 10 tokens, which is still within the threshold
+    app.add_message(MessageRole::User, &"a".repeat(40));
+    assert!(!app.context_warning());
+    app.add_message(MessageRole::Assistant, "One more");
+    assert!(app.context_warning());
+}
+
+#[test]
+fn test_backspace_goes_back_to_the_line_above() {
+    let (mut app, _dir) = test_app(Config::default());
+    app.update_message("First line".to_string());
+    let _ = app.update(screensage::app::Message::NewLine);
+    
+    // Backspace on the new, empty line leaves the text input with nothing to change
+    let backspace = screensage::ui::input::line_changed(app.message(), String::new());
+    let _ = app.update(backspace);
+    assert_eq!(app.message(), "First line");
+    
+    // The earlier line is the one being typed again, so it can be edited
+    let _ = app.update(screensage::ui::input::line_changed(app.message(), "First lines".to_string()));
+    assert_eq!(app.message(), "First lines");
+}
+
+#[test]
+fn test_input_height_resets_after_sending() {
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    let (mut app, _dir) = test_app(config);
+    let _ = app.update(screensage::app::Message::NewConversation);
+    
+    app.update_message("First line".to_string());
+    let _ = app.update(screensage::app::Message::NewLine);
+    let _ = app.update(screensage::app::Message::InputChanged("First line\nSecond line".to_string()));
+    assert!(screensage::ui::input::input_height(app.message()) > screensage::ui::input::input_height(""));
+    
+    let _ = app.update(screensage::app::Message::SendMessage);
+    assert_eq!(app.message(), "");
+    assert_eq!(screensage::ui::input::input_height(app.message()), screensage::ui::input::input_height("One line"));
+}
+
+#[test]
+fn test_find_highlights_matching_messages() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    let (mut app, _dir) = test_app(config);
+    let _ = app.update(screensage::app::Message::NewConversation);
+    app.add_message(MessageRole::User, "How do I read a file in Rust?");
+    app.add_message(MessageRole::Assistant, "Use std::fs::read_to_string.");
+    app.add_message(MessageRole::User, "And write a FILE?");
+    
+    let _ = app.update(screensage::app::Message::ToggleFind);
+    let _ = app.update(screensage::app::Message::FindChanged("file".to_string()));
+    assert_eq!(app.find_matches(), (&[0, 2][..], Some(0)));
+    
+    // Stepping wraps around at both ends
+    let _ = app.update(screensage::app::Message::NextFindMatch);
+    assert_eq!(app.find_matches().1, Some(1));
+    let _ = app.update(screensage::app::Message::NextFindMatch);
+    assert_eq!(app.find_matches().1, Some(0));
+    let _ = app.update(screensage::app::Message::PreviousFindMatch);
+    assert_eq!(app.find_matches().1, Some(1));
+    
+    // Reordering messages finds the matches again where they moved to
+    let _ = app.update(screensage::app::Message::MoveMessage(1, 0));
+    assert_eq!(app.find_matches(), (&[1, 2][..], None));
+    
+    // An empty query clears the highlighting
+    let _ = app.update(screensage::app::Message::FindChanged(String::new()));
+    assert_eq!(app.find_matches(), (&[][..], None));
+}
+
+#[test]
+fn test_clearing_starts_an_empty_conversation() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    config.ollama.default_model = "mistral".to_string();
+    let (mut app, _dir) = test_app(config);
+    let _ = app.update(screensage::app::Message::NewConversation);
+    app.add_message(MessageRole::User, "Hello");
+    app.add_message(MessageRole::Assistant, "Hi there");
+    let cleared_id = app.conversation().id.clone();
+    
+    // Nothing is cleared until confirmed
+    let _ = app.update(screensage::app::Message::ClearConversation);
+    assert_eq!(app.conversation().messages.len(), 2);
+    let _ = app.update(screensage::app::Message::RequestClearConversation);
+    let _ = app.update(screensage::app::Message::CancelClearConversation);
+    let _ = app.update(screensage::app::Message::ClearConversation);
+    assert_eq!(app.conversation().messages.len(), 2);
+    
+    let _ = app.update(screensage::app::Message::RequestClearConversation);
+    let _ = app.update(screensage::app::Message::ClearConversation);
+    assert!(app.conversation().messages.is_empty());
+    assert_ne!(app.conversation().id, cleared_id);
+    assert_eq!(app.conversation().model, "mistral");
+}
+
+/// Start a conversation with a saved exchange in it, returning its id
+fn saved_exchange(app: &mut App) -> String {
+    use screensage::data::conversation::MessageRole;
+    
+    let _ = app.update(screensage::app::Message::NewConversation);
+    app.add_message(MessageRole::User, "Hello");
+    app.add_message(MessageRole::Assistant, "Hi there");
+    let _ = app.update(screensage::app::Message::SaveConversation);
+    app.conversation().id.clone()
+}
+
+#[test]
+fn test_cleared_conversation_stays_in_the_sidebar() {
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    let (mut app, dir) = test_app(config);
+    let cleared_id = saved_exchange(&mut app);
+    
+    let _ = app.update(screensage::app::Message::RequestClearConversation);
+    let _ = app.update(screensage::app::Message::ClearConversation);
+    
+    let kept = app.conversations().iter().find(|c| c.id == cleared_id).expect("cleared conversation is listed");
+    assert_eq!(kept.messages.len(), 2);
+    assert!(dir.path().join("conversations").join(format!("{}.json", cleared_id)).exists());
+}
+
+#[test]
+fn test_clearing_can_delete_the_saved_file() {
+    let mut config = Config::default();
+    config.conversation.auto_save = false;
+    config.conversation.clear_deletes_file = true;
+    let (mut app, dir) = test_app(config);
+    let cleared_id = saved_exchange(&mut app);
+    let path = dir.path().join("conversations").join(format!("{}.json", cleared_id));
+    assert!(path.exists());
+    
+    let _ = app.update(screensage::app::Message::RequestClearConversation);
+    let _ = app.update(screensage::app::Message::ClearConversation);
+    
+    assert!(app.conversation().messages.is_empty());
+    assert!(!path.exists());
+    assert!(app.conversations().iter().all(|c| c.id != cleared_id));
+}
+
+#[test]
+fn test_selection_follows_messages_when_old_ones_are_dropped() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.conversation.max_chars = 1000;
+    config.conversation.auto_save = false;
+    let (mut app, _dir) = test_app(config);
+    app.add_message(MessageRole::User, &"a".repeat(600));
+    app.add_message(MessageRole::Assistant, &"b".repeat(600));
+    app.add_message(MessageRole::User, "Keep this");
+    app.add_message(MessageRole::Assistant, "Kept");
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(0));
+    let _ = app.update(screensage::app::Message::ToggleMessageSelection(2));
+    
+    // Sending overflows max_chars, so the first message is dropped along with its selection
+    app.update_message("Next".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    assert_eq!(app.conversation().messages[0].content, "b".repeat(600));
+    assert_eq!(app.selected_indices(), [1]);
+    assert_eq!(app.conversation().messages[1].content, "Keep this");
+}
+
+#[test]
+fn test_response_history_follows_its_response_when_old_messages_are_dropped() {
+    use screensage::data::conversation::MessageRole;
+    
+    let mut config = Config::default();
+    config.conversation.max_chars = 1000;
+    config.conversation.auto_save = false;
+    let (mut app, _dir) = test_app(config);
+    app.add_message(MessageRole::User, &"a".repeat(600));
+    app.add_message(MessageRole::Assistant, &"b".repeat(600));
+    app.add_message(MessageRole::User, "Tell me a story");
+    app.add_message(MessageRole::Assistant, "Once upon a time.");
+    let _ = app.update(screensage::app::Message::RegenerateMoreCreative);
+    let _ = app.update(screensage::app::Message::MessageReceived("A dragon appeared.".to_string()));
+    assert_eq!(app.response_history(3), ["Once upon a time."]);
+    
+    // Dropping the first message moves the regenerated response, and its earlier versions with it
+    app.update_message("Next".to_string());
+    let _ = app.update(screensage::app::Message::SendMessage);
+    assert_eq!(app.conversation().messages[2].content, "A dragon appeared.");
+    assert_eq!(app.response_history(2), ["Once upon a time."]);
+    assert!(app.response_history(3).is_empty());
+}
@@ -12,7 +12,7 @@ fn test_default_config() {
     
     assert_eq!(config.ollama.api_url, "http://localhost:11434");
     
-    assert!(config.conversation.max_length > 0);
+    assert!(config.conversation.max_chars > 0);
     assert!(config.conversation.auto_save);
     
     assert_eq!(config.logging.level, "info");
@@ -1,44 +1,52 @@
 use screensage::{Conversation, Message, MessageRole};
-use chrono::Utc;
+use screensage::data::clock::{Clock, FakeClock};
+use chrono::{Duration, TimeZone, Utc};
+use std::sync::Arc;
+
+fn fake_clock() -> Arc<FakeClock> {
+    Arc::new(FakeClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()))
+}
 
 #[test]
 fn test_message_creation() {
     let content = "Test message";
-    let message = Message::new(MessageRole::User, content);
+    let clock = fake_clock();
+    let message = Message::with_clock(MessageRole::User, content, &*clock);
     
     assert_eq!(message.role, MessageRole::User);
     assert_eq!(message.content, content);
-    assert!(message.timestamp <= Utc::now());
+    assert_eq!(message.timestamp, clock.now());
 }
 
 #[test]
 fn test_conversation_creation() {
     let title = "Test Conversation";
     let model = "test-model";
-    let conversation = Conversation::new(title, model);
+    let clock = fake_clock();
+    let conversation = Conversation::with_clock(title, model, clock.clone());
     
     assert_eq!(conversation.title, title);
     assert_eq!(conversation.model, model);
     assert!(conversation.messages.is_empty());
-    assert!(conversation.created_at <= Utc::now());
+    assert_eq!(conversation.created_at, clock.now());
     assert_eq!(conversation.created_at, conversation.updated_at);
 }
 
 #[test]
 fn test_add_message() {
-    let mut conversation = Conversation::new("Test", "test-model");
+    let clock = fake_clock();
+    let mut conversation = Conversation::with_clock("Test", "test-model", clock.clone());
     let before_update = conversation.updated_at;
     
-    // Wait a moment to ensure timestamp difference
-    std::thread::sleep(std::time::Duration::from_millis(10));
-    
-    // Add a message
+    // Add a message a second later
+    clock.advance(Duration::seconds(1));
     conversation.add_message(MessageRole::User, "Test message");
     
     assert_eq!(conversation.messages.len(), 1);
     assert_eq!(conversation.messages[0].role, MessageRole::User);
     assert_eq!(conversation.messages[0].content, "Test message");
-    assert!(conversation.updated_at > before_update);
+    assert_eq!(conversation.messages[0].timestamp, before_update + Duration::seconds(1));
+    assert_eq!(conversation.updated_at, before_update + Duration::seconds(1));
 }
 
 #[test]